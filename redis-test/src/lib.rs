@@ -22,9 +22,14 @@
 //! let result = my_exists(&mut mock_connection, "foo").unwrap();
 //! assert_eq!(result, true);
 //! ```
+//!
+//! Commands can also be answered with errors, including a dropped connection with
+//! [`MockCmd::dropped`], to test how code handles failures.
 
 use std::collections::VecDeque;
+use std::io;
 use std::iter::FromIterator;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 use redis::{Cmd, ConnectionLike, ErrorKind, Pipeline, RedisError, RedisResult, Value};
@@ -127,6 +132,7 @@ impl IntoRedisCmdBytes for &mut Pipeline {
 pub struct MockCmd {
     cmd_bytes: Vec<u8>,
     responses: Result<Vec<Value>, RedisError>,
+    drops_connection: bool,
 }
 
 impl MockCmd {
@@ -140,6 +146,7 @@ impl MockCmd {
         MockCmd {
             cmd_bytes: cmd.into_redis_cmd_bytes(),
             responses: response.map(|r| vec![r.into_redis_value()]),
+            drops_connection: false,
         }
     }
 
@@ -153,15 +160,35 @@ impl MockCmd {
         MockCmd {
             cmd_bytes: cmd.into_redis_cmd_bytes(),
             responses: responses.map(|xs| xs.into_iter().map(|x| x.into_redis_value()).collect()),
+            drops_connection: false,
+        }
+    }
+
+    /// Create a new `MockCmd` given a Redis command/pipeline during which the connection is
+    /// dropped.  The command fails with a `BrokenPipe` I/O error, and so does every following
+    /// command, while the connection reports that it is no longer open.
+    pub fn dropped<C>(cmd: C) -> Self
+    where
+        C: IntoRedisCmdBytes,
+    {
+        MockCmd {
+            cmd_bytes: cmd.into_redis_cmd_bytes(),
+            responses: Err(connection_dropped()),
+            drops_connection: true,
         }
     }
 }
 
+fn connection_dropped() -> RedisError {
+    RedisError::from(io::Error::from(io::ErrorKind::BrokenPipe))
+}
+
 /// A mock Redis client for testing without a server. `MockRedisConnection` checks whether the
 /// client submits a specific sequence of commands and generates an error if it does not.
 #[derive(Clone)]
 pub struct MockRedisConnection {
     commands: Arc<Mutex<VecDeque<MockCmd>>>,
+    dropped: Arc<AtomicBool>,
 }
 
 impl MockRedisConnection {
@@ -172,12 +199,21 @@ impl MockRedisConnection {
     {
         MockRedisConnection {
             commands: Arc::new(Mutex::new(VecDeque::from_iter(commands))),
+            dropped: Arc::new(AtomicBool::new(false)),
         }
     }
+
+    fn check_open(&self) -> RedisResult<()> {
+        if self.dropped.load(Ordering::SeqCst) {
+            return Err(connection_dropped());
+        }
+        Ok(())
+    }
 }
 
 impl ConnectionLike for MockRedisConnection {
     fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.check_open()?;
         let mut commands = self.commands.lock().unwrap();
         let next_cmd = commands.pop_front().ok_or_else(|| {
             RedisError::from((
@@ -199,6 +235,9 @@ impl ConnectionLike for MockRedisConnection {
                 ),
             )));
         }
+        if next_cmd.drops_connection {
+            self.dropped.store(true, Ordering::SeqCst);
+        }
 
         next_cmd
             .responses
@@ -221,6 +260,7 @@ impl ConnectionLike for MockRedisConnection {
         _offset: usize,
         _count: usize,
     ) -> RedisResult<Vec<Value>> {
+        self.check_open()?;
         let mut commands = self.commands.lock().unwrap();
         let next_cmd = commands.pop_front().ok_or_else(|| {
             RedisError::from((
@@ -242,6 +282,9 @@ impl ConnectionLike for MockRedisConnection {
                 ),
             )));
         }
+        if next_cmd.drops_connection {
+            self.dropped.store(true, Ordering::SeqCst);
+        }
 
         next_cmd.responses
     }
@@ -251,11 +294,11 @@ impl ConnectionLike for MockRedisConnection {
     }
 
     fn check_connection(&mut self) -> bool {
-        true
+        !self.dropped.load(Ordering::SeqCst)
     }
 
     fn is_open(&self) -> bool {
-        true
+        !self.dropped.load(Ordering::SeqCst)
     }
 }
 
@@ -294,7 +337,7 @@ impl AioConnectionLike for MockRedisConnection {
 #[cfg(test)]
 mod tests {
     use super::{MockCmd, MockRedisConnection};
-    use redis::{cmd, pipe, ErrorKind, Value};
+    use redis::{cmd, pipe, ConnectionLike, ErrorKind, Value};
 
     #[test]
     fn sync_basic_test() {
@@ -398,6 +441,45 @@ mod tests {
         assert_eq!(results, vec!["hello", "world"]);
     }
 
+    #[cfg(feature = "aio")]
+    #[tokio::test]
+    async fn async_pipeline_test() {
+        let mut conn = MockRedisConnection::new(vec![
+            MockCmd::with_values(
+                pipe()
+                    .cmd("SET")
+                    .arg("foo")
+                    .arg(42)
+                    .ignore()
+                    .cmd("GET")
+                    .arg("foo"),
+                Ok(vec![Value::Okay, Value::Int(42)]),
+            ),
+            MockCmd::new(cmd("GET").arg("bar"), Ok("")),
+        ]);
+
+        let results: Vec<i64> = pipe()
+            .cmd("SET")
+            .arg("foo")
+            .arg(42)
+            .ignore()
+            .cmd("GET")
+            .arg("foo")
+            .query_async(&mut conn)
+            .await
+            .expect("success");
+        assert_eq!(results, vec![42]);
+
+        let err = pipe()
+            .cmd("GET")
+            .arg("baz")
+            .query_async::<_, Vec<String>>(&mut conn)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+        assert!(err.detail().unwrap().contains("unexpected command"));
+    }
+
     #[test]
     fn pipeline_atomic_test() {
         let mut conn = MockRedisConnection::new(vec![MockCmd::with_values(
@@ -420,4 +502,24 @@ mod tests {
             .expect("success");
         assert_eq!(results, vec!["hello", "world"]);
     }
+
+    #[test]
+    fn dropped_connection_test() {
+        let mut conn = MockRedisConnection::new(vec![
+            MockCmd::new(cmd("GET").arg("foo"), Ok(42)),
+            MockCmd::dropped(cmd("GET").arg("bar")),
+            MockCmd::new(cmd("GET").arg("foo"), Ok(42)),
+        ]);
+
+        assert_eq!(cmd("GET").arg("foo").query(&mut conn), Ok(42));
+        assert!(conn.is_open());
+
+        let err = cmd("GET").arg("bar").query::<i32>(&mut conn).unwrap_err();
+        assert!(err.is_connection_dropped());
+        assert!(!conn.is_open());
+        assert!(!conn.check_connection());
+
+        let err = cmd("GET").arg("foo").query::<i32>(&mut conn).unwrap_err();
+        assert!(err.is_connection_dropped());
+    }
 }
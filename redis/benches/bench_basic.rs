@@ -41,6 +41,27 @@ fn bench_simple_getsetdel_async(b: &mut Bencher) {
     });
 }
 
+fn bench_simple_getsetdel_async_unboxed(b: &mut Bencher) {
+    let ctx = TestContext::new();
+    let runtime = current_thread_runtime();
+    let mut con = runtime.block_on(ctx.async_connection()).unwrap();
+
+    b.iter(|| {
+        runtime
+            .block_on(async {
+                let key = "test_key";
+                con.send_packed_command(redis::cmd("SET").arg(key).arg(42))
+                    .await?;
+                let _: isize = redis::from_redis_value(
+                    &con.send_packed_command(redis::cmd("GET").arg(key)).await?,
+                )?;
+                con.send_packed_command(redis::cmd("DEL").arg(key)).await?;
+                Ok::<_, RedisError>(())
+            })
+            .unwrap()
+    });
+}
+
 fn bench_simple_getsetdel_pipeline(b: &mut Bencher) {
     let ctx = TestContext::new();
     let mut con = ctx.connection();
@@ -168,6 +189,10 @@ fn bench_query(c: &mut Criterion) {
     group
         .bench_function("simple_getsetdel", bench_simple_getsetdel)
         .bench_function("simple_getsetdel_async", bench_simple_getsetdel_async)
+        .bench_function(
+            "simple_getsetdel_async_unboxed",
+            bench_simple_getsetdel_async_unboxed,
+        )
         .bench_function("simple_getsetdel_pipeline", bench_simple_getsetdel_pipeline)
         .bench_function(
             "simple_getsetdel_pipeline_precreated",
@@ -11,6 +11,7 @@ use std::{
 };
 
 use crate::aio::{AsyncStream, RedisRuntime};
+use crate::connection::bind_error;
 use crate::types::RedisResult;
 
 #[cfg(all(feature = "tls-native-tls", not(feature = "tls-rustls")))]
@@ -18,6 +19,8 @@ use async_native_tls::{TlsConnector, TlsStream};
 
 #[cfg(feature = "tls-rustls")]
 use crate::connection::create_rustls_config;
+#[cfg(any(feature = "tls-native-tls", feature = "tls-rustls"))]
+use crate::connection::TlsCertificateCheck;
 #[cfg(feature = "tls-rustls")]
 use futures_rustls::{client::TlsStream, TlsConnector};
 
@@ -29,8 +32,11 @@ use futures_util::ready;
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
 #[inline(always)]
-async fn connect_tcp(addr: &SocketAddr) -> io::Result<TcpStream> {
-    let socket = TcpStream::connect(addr).await?;
+async fn connect_tcp(addr: &SocketAddr, local_addr: Option<SocketAddr>) -> io::Result<TcpStream> {
+    let socket = match local_addr {
+        Some(local_addr) => connect_tcp_from(*addr, local_addr).await?,
+        None => TcpStream::connect(addr).await?,
+    };
     #[cfg(feature = "tcp_nodelay")]
     socket.set_nodelay(true)?;
     #[cfg(feature = "keep-alive")]
@@ -50,6 +56,35 @@ async fn connect_tcp(addr: &SocketAddr) -> io::Result<TcpStream> {
     }
 }
 
+/// Connects from the given local address, binding the socket before connecting.
+async fn connect_tcp_from(addr: SocketAddr, local_addr: SocketAddr) -> io::Result<TcpStream> {
+    let socket = socket2::Socket::new(
+        socket2::Domain::for_address(addr),
+        socket2::Type::STREAM,
+        Some(socket2::Protocol::TCP),
+    )?;
+    socket
+        .bind(&local_addr.into())
+        .map_err(|err| bind_error(local_addr, err))?;
+    // async-std can't connect a socket created elsewhere, so start connecting in non-blocking
+    // mode and wait for the socket to become writable on the reactor async-std runs on.
+    socket.set_nonblocking(true)?;
+    let started = socket.connect(&addr.into());
+    let stream = async_io::Async::new(std::net::TcpStream::from(socket))?;
+    if started.is_err() {
+        stream.writable().await?;
+    }
+    if let Some(err) = stream.get_ref().take_error()? {
+        return Err(err);
+    }
+    // The connection is established once the peer address is known.  Otherwise connecting
+    // failed right away, rather than being in progress, so that error is returned.
+    if let Err(not_connected) = stream.get_ref().peer_addr() {
+        return Err(started.err().unwrap_or(not_connected));
+    }
+    Ok(stream.into_inner()?.into())
+}
+
 pin_project_lite::pin_project! {
     /// Wraps the async_std `AsyncRead/AsyncWrite` in order to implement the required the tokio traits
     /// for it
@@ -189,8 +224,11 @@ impl AsyncRead for AsyncStd {
 
 #[async_trait]
 impl RedisRuntime for AsyncStd {
-    async fn connect_tcp(socket_addr: SocketAddr) -> RedisResult<Self> {
-        Ok(connect_tcp(&socket_addr)
+    async fn connect_tcp(
+        socket_addr: SocketAddr,
+        local_addr: Option<SocketAddr>,
+    ) -> RedisResult<Self> {
+        Ok(connect_tcp(&socket_addr, local_addr)
             .await
             .map(|con| Self::Tcp(AsyncStdWrapped::new(con)))?)
     }
@@ -199,9 +237,11 @@ impl RedisRuntime for AsyncStd {
     async fn connect_tcp_tls(
         hostname: &str,
         socket_addr: SocketAddr,
+        local_addr: Option<SocketAddr>,
         insecure: bool,
+        certificate_check: Option<&TlsCertificateCheck>,
     ) -> RedisResult<Self> {
-        let tcp_stream = connect_tcp(&socket_addr).await?;
+        let tcp_stream = connect_tcp(&socket_addr, local_addr).await?;
         let tls_connector = if insecure {
             TlsConnector::new()
                 .danger_accept_invalid_certs(true)
@@ -210,27 +250,40 @@ impl RedisRuntime for AsyncStd {
         } else {
             TlsConnector::new()
         };
-        Ok(tls_connector
-            .connect(hostname, tcp_stream)
-            .await
-            .map(|con| Self::TcpTls(AsyncStdWrapped::new(Box::new(con))))?)
+        let con = tls_connector.connect(hostname, tcp_stream).await?;
+        if let Some(check) = certificate_check {
+            let certificate = con.peer_certificate()?;
+            check.verify(
+                certificate
+                    .map(|cert| cert.to_der())
+                    .transpose()?
+                    .as_deref(),
+            )?;
+        }
+        Ok(Self::TcpTls(AsyncStdWrapped::new(Box::new(con))))
     }
 
     #[cfg(feature = "tls-rustls")]
     async fn connect_tcp_tls(
         hostname: &str,
         socket_addr: SocketAddr,
+        local_addr: Option<SocketAddr>,
         insecure: bool,
+        certificate_check: Option<&TlsCertificateCheck>,
     ) -> RedisResult<Self> {
-        let tcp_stream = connect_tcp(&socket_addr).await?;
+        let tcp_stream = connect_tcp(&socket_addr, local_addr).await?;
 
         let config = create_rustls_config(insecure)?;
         let tls_connector = TlsConnector::from(Arc::new(config));
 
-        Ok(tls_connector
+        let con = tls_connector
             .connect(hostname.try_into()?, tcp_stream)
-            .await
-            .map(|con| Self::TcpTls(AsyncStdWrapped::new(Box::new(con))))?)
+            .await?;
+        if let Some(check) = certificate_check {
+            let certificate = con.get_ref().1.peer_certificates().and_then(|c| c.first());
+            check.verify(certificate.map(|cert| cert.0.as_slice()))?;
+        }
+        Ok(Self::TcpTls(AsyncStdWrapped::new(Box::new(con))))
     }
 
     #[cfg(unix)]
@@ -1,9 +1,13 @@
 #[cfg(feature = "async-std-comp")]
 use super::async_std;
 use super::ConnectionLike;
-use super::{setup_connection, AsyncStream, RedisRuntime};
+use super::{reset_connection, setup_connection, AsyncStream, RedisRuntime, Runtime};
 use crate::cmd::{cmd, Cmd};
-use crate::connection::{ConnectionAddr, ConnectionInfo, Msg, RedisConnectionInfo};
+use crate::command_table::{command_table_cmd, load_command_table, CommandTable};
+use crate::connection::{
+    aggregate_connect_errors, blocking_pop_cmd, ConnectionAddr, ConnectionConfig, KeyspaceEvent,
+    Msg, ProtocolVersion, RedisConnectionConfig, RedisConnectionInfo,
+};
 #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
 use crate::parser::ValueCodec;
 use crate::types::{ErrorKind, FromRedisValue, RedisError, RedisFuture, RedisResult, Value};
@@ -14,13 +18,14 @@ use ::tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 #[cfg(feature = "tokio-comp")]
 use ::tokio::net::lookup_host;
 use combine::{parser::combinator::AnySendSyncPartialState, stream::PointerOffset};
-use futures_util::future::select_ok;
 use futures_util::{
-    future::FutureExt,
+    future::{Future, FutureExt},
     stream::{Stream, StreamExt},
 };
+use std::borrow::Cow;
 use std::net::SocketAddr;
 use std::pin::Pin;
+use std::time::Duration;
 #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
 use tokio_util::codec::Decoder;
 
@@ -30,14 +35,34 @@ pub struct Connection<C = Pin<Box<dyn AsyncStream + Send + Sync>>> {
     buf: Vec<u8>,
     decoder: combine::stream::Decoder<AnySendSyncPartialState, PointerOffset<[u8]>>,
     db: i64,
+    connection_info: RedisConnectionConfig,
+    protocol: ProtocolVersion,
 
     // Flag indicating whether the connection was left in the PubSub state after dropping `PubSub`.
     //
     // This flag is checked when attempting to send a command, and if it's raised, we attempt to
     // exit the pubsub state before executing the new request.
     pubsub: bool,
+
+    // Flag indicating whether the connection may have been left inside a `MULTI` block, because a
+    // `Transaction` was dropped or its future cancelled before `EXEC` or `DISCARD` completed.
+    //
+    // This flag is checked when attempting to send a command, and if it's raised, we send
+    // `DISCARD` before executing the new request.
+    transaction: bool,
+
+    // Applied to every command sent by the user before it is encoded.
+    command_hook: Option<Box<CommandHook>>,
+
+    // The commands reported by the server, if `validate_commands` is set.
+    command_table: Option<CommandTable>,
+
+    // The time to wait for each response to a request, or `None` to wait indefinitely.
+    response_timeout: Option<Duration>,
 }
 
+type CommandHook = dyn Fn(&mut Cmd) + Send + Sync;
+
 fn assert_sync<T: Sync>() {}
 
 #[allow(unused)]
@@ -52,14 +77,26 @@ impl<C> Connection<C> {
             buf,
             decoder,
             db,
+            connection_info,
+            protocol,
             pubsub,
+            transaction,
+            command_hook,
+            command_table,
+            response_timeout,
         } = self;
         Connection {
             con: f(con),
             buf,
             decoder,
             db,
+            connection_info,
+            protocol,
             pubsub,
+            transaction,
+            command_hook,
+            command_table,
+            response_timeout,
         }
     }
 }
@@ -71,17 +108,99 @@ where
     /// Constructs a new `Connection` out of a `AsyncRead + AsyncWrite` object
     /// and a `RedisConnectionInfo`
     pub async fn new(connection_info: &RedisConnectionInfo, con: C) -> RedisResult<Self> {
+        Connection::with_config(&connection_info.clone().into(), con).await
+    }
+
+    pub(crate) async fn with_config(
+        connection_info: &RedisConnectionConfig,
+        con: C,
+    ) -> RedisResult<Self> {
         let mut rv = Connection {
             con,
             buf: Vec::new(),
             decoder: combine::stream::Decoder::new(),
             db: connection_info.db,
+            connection_info: connection_info.clone(),
+            protocol: ProtocolVersion::RESP2,
             pubsub: false,
+            transaction: false,
+            command_hook: None,
+            command_table: None,
+            response_timeout: None,
         };
-        setup_connection(connection_info, &mut rv).await?;
+        rv.protocol = setup_connection(connection_info, &mut rv).await?;
+        if cfg!(debug_assertions) && connection_info.validate_commands {
+            let reply = command_table_cmd().query_async(&mut rv).await;
+            rv.command_table = load_command_table(reply)?;
+        }
         Ok(rv)
     }
 
+    /// Returns the protocol version negotiated with the server while setting up the connection.
+    ///
+    /// This may be [`ProtocolVersion::RESP2`] even if RESP3 was requested, when the server
+    /// doesn't support it.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol
+    }
+
+    /// Sets the time to wait for each response to a request, or `None` to wait indefinitely,
+    /// which is the default unless the connection info sets a response timeout.
+    ///
+    /// A request that times out fails with a `TimedOut` I/O error.  As its response could
+    /// still arrive and be mistaken for the response to the next request, the connection is
+    /// shut down, so that all further requests fail as well.  Messages received after
+    /// converting the connection into [`PubSub`] or [`Monitor`] are not bound by the timeout.
+    pub fn set_response_timeout(&mut self, timeout: Option<Duration>) {
+        self.response_timeout = timeout;
+    }
+
+    /// Pops an element from the first non-empty list in `keys`, blocking for up to `timeout`
+    /// if all of them are empty.  Returns the name of the list and the element, or `None` if
+    /// the timeout elapsed.  A zero `timeout` blocks indefinitely.
+    ///
+    /// This extends the response timeout of the connection by `timeout` for this request, or
+    /// disables it for a zero `timeout`, like
+    /// [`Connection::blpop_timeout`](crate::Connection::blpop_timeout) does for synchronous
+    /// connections.
+    pub async fn blpop_timeout<K: ToRedisArgs, V: FromRedisValue>(
+        &mut self,
+        keys: K,
+        timeout: Duration,
+    ) -> RedisResult<Option<(String, V)>> {
+        self.blocking_pop("BLPOP", keys, timeout).await
+    }
+
+    /// Like [`blpop_timeout`](Self::blpop_timeout), but pops from the tail of the lists
+    /// (`BRPOP`).
+    pub async fn brpop_timeout<K: ToRedisArgs, V: FromRedisValue>(
+        &mut self,
+        keys: K,
+        timeout: Duration,
+    ) -> RedisResult<Option<(String, V)>> {
+        self.blocking_pop("BRPOP", keys, timeout).await
+    }
+
+    async fn blocking_pop<K: ToRedisArgs, V: FromRedisValue>(
+        &mut self,
+        name: &str,
+        keys: K,
+        timeout: Duration,
+    ) -> RedisResult<Option<(String, V)>> {
+        let response_timeout = if timeout.is_zero() {
+            None
+        } else {
+            self.response_timeout.map(|dur| dur + timeout)
+        };
+        self.restore_state().await?;
+        let cmd = blocking_pop_cmd(name, keys, timeout);
+        let cmd = self.apply_command_hook(&cmd);
+        let value = self
+            .send_single_command_within(&cmd, response_timeout)
+            .await?;
+        from_redis_value(&value)
+    }
+
     /// Converts this [`Connection`] into [`PubSub`].
     pub fn into_pubsub(self) -> PubSub<C> {
         PubSub::new(self)
@@ -92,9 +211,262 @@ where
         Monitor::new(self)
     }
 
+    /// Resets the state of the connection with `RESET`, then authenticates, negotiates the
+    /// protocol and selects the database again as configured when the connection was opened.
+    ///
+    /// `RESET` discards any transaction in progress, unwatches all keys, leaves the pub/sub and
+    /// monitor modes and deauthenticates the connection, which makes it a good fit for recycling
+    /// pooled connections.  Servers before Redis 6.2 don't know `RESET`; for those only the
+    /// configured database is selected again.
+    pub async fn reset(&mut self) -> RedisResult<()> {
+        let connection_info = self.connection_info.clone();
+        self.protocol = reset_connection(&connection_info, self.protocol, self).await?;
+        Ok(())
+    }
+
+    /// Closes the connection gracefully by sending `QUIT`, waiting for the server to acknowledge
+    /// it and then shutting down the socket.
+    pub async fn quit(mut self) -> RedisResult<()> {
+        match cmd("QUIT").query_async(&mut self).await? {
+            Value::Okay => {}
+            _ => fail!((
+                ErrorKind::ResponseError,
+                "Redis server refused to close the connection"
+            )),
+        }
+        // The server closes its side after acknowledging, so a failing shutdown is harmless.
+        let _ = self.con.shutdown().await;
+        Ok(())
+    }
+
+    /// Sends an already encoded (packed) command into the TCP socket and
+    /// reads the single response from it.
+    ///
+    /// Unlike [`ConnectionLike::req_packed_command`] this does not box the returned future.
+    pub async fn send_packed_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        self.restore_state().await?;
+        let cmd = self.apply_command_hook(cmd);
+        self.send_single_command(&cmd).await
+    }
+
+    /// Sets a hook that can rewrite each command before it is sent, e.g. to prefix keys.
+    ///
+    /// The hook applies to single commands, the commands of pipelines and commands queued
+    /// into a [`Transaction`], but not to the commands this connection sends by itself, like
+    /// `MULTI`, `EXEC` or the commands sent while setting up the connection.
+    pub fn set_command_hook<F>(&mut self, hook: F)
+    where
+        F: Fn(&mut Cmd) + Send + Sync + 'static,
+    {
+        self.command_hook = Some(Box::new(hook));
+    }
+
+    /// Removes the hook set by [`set_command_hook`](Self::set_command_hook).
+    pub fn clear_command_hook(&mut self) {
+        self.command_hook = None;
+    }
+
+    fn apply_command_hook<'c>(&self, cmd: &'c Cmd) -> Cow<'c, Cmd> {
+        match self.command_hook {
+            Some(ref hook) => {
+                let mut cmd = cmd.clone();
+                hook(&mut cmd);
+                Cow::Owned(cmd)
+            }
+            None => Cow::Borrowed(cmd),
+        }
+    }
+
+    /// Starts a transaction by sending `MULTI`.
+    ///
+    /// Commands are queued with [`Transaction::queue`] and executed atomically by
+    /// [`Transaction::exec`].  If the returned [`Transaction`] is dropped without calling
+    /// [`exec`](Transaction::exec) or [`discard`](Transaction::discard), e.g. because the future
+    /// using it was cancelled, `DISCARD` is sent before the next command on this connection, so
+    /// that command is never queued into the abandoned transaction.
+    ///
+    /// For transactions that don't need to look at the replies of queued commands, atomic
+    /// pipelines are simpler to use.
+    pub async fn multi(&mut self) -> RedisResult<Transaction<'_, C>> {
+        self.restore_state().await?;
+        // Raise the flag before sending, so cancelling this future can't leave the transaction
+        // open either.
+        self.transaction = true;
+        match self.send_single_command(&cmd("MULTI")).await {
+            Ok(Value::Okay) => Ok(Transaction { con: self }),
+            Ok(_) => fail!((
+                ErrorKind::ResponseError,
+                "Redis server refused to start a transaction"
+            )),
+            Err(err) => {
+                if !err.is_unrecoverable_error() {
+                    self.transaction = false;
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Sends multiple already encoded (packed) command into the TCP socket
+    /// and reads `count` responses from it.  This is used to implement
+    /// pipelining.
+    ///
+    /// Unlike [`ConnectionLike::req_packed_commands`] this does not box the returned future.
+    pub async fn send_packed_commands(
+        &mut self,
+        cmd: &crate::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.restore_state().await?;
+        self.buf.clear();
+        match self.command_hook {
+            Some(ref hook) => {
+                let mut cmd = cmd.clone();
+                for cmd in cmd.commands_mut() {
+                    hook(cmd);
+                }
+                cmd.write_packed_pipeline(&mut self.buf);
+            }
+            None => cmd.write_packed_pipeline(&mut self.buf),
+        }
+        self.write_and_read_responses(offset, count).await
+    }
+
+    /// Writes pre-encoded bytes into the TCP socket as they are and reads `count` responses
+    /// from it.
+    ///
+    /// The bytes are not passed to the command hook.  If `count` doesn't match
+    /// the number of commands in them, the following requests receive the wrong responses.
+    pub async fn send_raw(&mut self, bytes: &[u8], count: usize) -> RedisResult<Vec<Value>> {
+        self.restore_state().await?;
+        self.buf.clear();
+        self.buf.extend_from_slice(bytes);
+        self.write_and_read_responses(0, count).await
+    }
+
+    /// Writes the buffer and reads `offset + count` responses, returning the last `count`.
+    async fn write_and_read_responses(
+        &mut self,
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.write_buf().await?;
+
+        let mut first_err = None;
+        let mut responses = Vec::with_capacity(count);
+        for idx in 0..(offset + count) {
+            let response = self.read_response_within(self.response_timeout).await;
+            match response {
+                Err(err) if err.is_unrecoverable_error() => {
+                    // After an I/O or parse error the remaining responses can't be read
+                    // reliably, so shut the connection down to make any further use fail
+                    // instead of returning responses that belong to other requests.
+                    let _ = self.con.shutdown().await;
+                    return Err(err.with_partial_responses(responses));
+                }
+                response if idx >= offset => responses.push(response),
+                Ok(_) => {}
+                Err(err) => {
+                    if first_err.is_none() {
+                        first_err = Some(err);
+                    }
+                }
+            }
+        }
+
+        if let Some(err) = first_err {
+            Err(err)
+        } else {
+            responses.into_iter().collect()
+        }
+    }
+
     /// Fetches a single response from the connection.
     async fn read_response(&mut self) -> RedisResult<Value> {
-        crate::parser::parse_redis_value_async(&mut self.decoder, &mut self.con).await
+        let response =
+            crate::parser::parse_redis_value_async(&mut self.decoder, &mut self.con).await;
+        #[cfg(feature = "debug-logging")]
+        if self.connection_info.log_commands {
+            crate::logging::log_response(&response);
+        }
+        response
+    }
+
+    /// Fetches the response to a request, shutting the connection down if it doesn't arrive
+    /// within `timeout`.
+    async fn read_response_within(&mut self, timeout: Option<Duration>) -> RedisResult<Value> {
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => return self.read_response().await,
+        };
+        match Runtime::locate()
+            .timeout(timeout, self.read_response())
+            .await
+        {
+            Ok(response) => response,
+            Err(err) => {
+                let _ = self.con.shutdown().await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Sends a single command and reads its response, without checking the connection state.
+    async fn send_single_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        self.send_single_command_within(cmd, self.response_timeout)
+            .await
+    }
+
+    /// Like `send_single_command`, but waits for the response for `timeout` instead of the
+    /// response timeout.
+    async fn send_single_command_within(
+        &mut self,
+        cmd: &Cmd,
+        timeout: Option<Duration>,
+    ) -> RedisResult<Value> {
+        self.buf.clear();
+        cmd.write_packed_command(&mut self.buf);
+        self.write_buf().await?;
+        self.read_response_within(timeout).await
+    }
+
+    /// Checks the commands in the buffer, if validating them is enabled, and writes it.
+    async fn write_buf(&mut self) -> RedisResult<()> {
+        if let Some(ref table) = self.command_table {
+            table.validate_packed(&self.buf)?;
+        }
+        #[cfg(feature = "debug-logging")]
+        if self.connection_info.log_commands {
+            crate::logging::log_request(&self.buf);
+        }
+        self.con.write_all(&self.buf).await?;
+        Ok(())
+    }
+
+    /// Leaves the pubsub state or an abandoned transaction before sending a new request.
+    async fn restore_state(&mut self) -> RedisResult<()> {
+        if self.pubsub {
+            self.exit_pubsub().await?;
+        }
+        if self.transaction {
+            self.discard_transaction().await?;
+        }
+        Ok(())
+    }
+
+    /// Discards an abandoned transaction.
+    ///
+    /// The server answers with an error if no transaction was open, e.g. because `MULTI` was
+    /// never sent, which is fine as well.
+    async fn discard_transaction(&mut self) -> RedisResult<()> {
+        match self.send_single_command(&cmd("DISCARD")).await {
+            Err(err) if err.is_unrecoverable_error() => Err(err),
+            _ => {
+                self.transaction = false;
+                Ok(())
+            }
+        }
     }
 
     /// Brings [`Connection`] out of `PubSub` mode.
@@ -133,6 +505,10 @@ where
                 .get_packed_pipeline();
 
             // Execute commands
+            #[cfg(feature = "debug-logging")]
+            if self.connection_info.log_commands {
+                crate::logging::log_request(&unsubscribe);
+            }
             self.con.write_all(&unsubscribe).await?;
         }
 
@@ -176,12 +552,14 @@ where
     }
 }
 
-pub(crate) async fn connect<C>(connection_info: &ConnectionInfo) -> RedisResult<Connection<C>>
+pub(crate) async fn connect<C>(connection_info: &ConnectionConfig) -> RedisResult<Connection<C>>
 where
     C: Unpin + RedisRuntime + AsyncRead + AsyncWrite + Send,
 {
     let con = connect_simple::<C>(connection_info).await?;
-    Connection::new(&connection_info.redis, con).await
+    let mut connection = Connection::with_config(&connection_info.redis, con).await?;
+    connection.set_response_timeout(connection_info.response_timeout);
+    Ok(connection)
 }
 
 impl<C> ConnectionLike for Connection<C>
@@ -189,16 +567,7 @@ where
     C: Unpin + AsyncRead + AsyncWrite + Send,
 {
     fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
-        (async move {
-            if self.pubsub {
-                self.exit_pubsub().await?;
-            }
-            self.buf.clear();
-            cmd.write_packed_command(&mut self.buf);
-            self.con.write_all(&self.buf).await?;
-            self.read_response().await
-        })
-        .boxed()
+        (async move { self.send_packed_command(cmd).await }).boxed()
     }
 
     fn req_packed_commands<'a>(
@@ -207,52 +576,82 @@ where
         offset: usize,
         count: usize,
     ) -> RedisFuture<'a, Vec<Value>> {
-        (async move {
-            if self.pubsub {
-                self.exit_pubsub().await?;
-            }
+        (async move { self.send_packed_commands(cmd, offset, count).await }).boxed()
+    }
 
-            self.buf.clear();
-            cmd.write_packed_pipeline(&mut self.buf);
-            self.con.write_all(&self.buf).await?;
+    fn req_raw<'a>(
+        &'a mut self,
+        bytes: &'a [u8],
+        expected_responses: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        (async move { self.send_raw(bytes, expected_responses).await }).boxed()
+    }
 
-            let mut first_err = None;
+    fn get_db(&self) -> i64 {
+        self.db
+    }
+}
 
-            for _ in 0..offset {
-                let response = self.read_response().await;
-                if let Err(err) = response {
-                    if first_err.is_none() {
-                        first_err = Some(err);
-                    }
-                }
-            }
+/// A transaction started by [`Connection::multi`].
+///
+/// The transaction ends with [`exec`](Self::exec) or [`discard`](Self::discard).  Dropping it
+/// without calling either discards the transaction before the next command is sent on the
+/// connection.
+///
+/// ```rust,no_run
+/// # async fn do_something() -> redis::RedisResult<()> {
+/// let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// let mut con = client.get_async_connection().await?;
+/// let mut transaction = con.multi().await?;
+/// transaction.queue(redis::cmd("INCR").arg("counter")).await?;
+/// transaction.queue(redis::cmd("GET").arg("counter")).await?;
+/// let (_, counter): (i64, i64) = transaction.exec().await?;
+/// # Ok(()) }
+/// ```
+pub struct Transaction<'a, C = Pin<Box<dyn AsyncStream + Send + Sync>>> {
+    con: &'a mut Connection<C>,
+}
 
-            let mut rv = Vec::with_capacity(count);
-            for _ in 0..count {
-                let response = self.read_response().await;
-                match response {
-                    Ok(item) => {
-                        rv.push(item);
-                    }
-                    Err(err) => {
-                        if first_err.is_none() {
-                            first_err = Some(err);
-                        }
-                    }
-                }
-            }
+impl<'a, C> Transaction<'a, C>
+where
+    C: Unpin + AsyncRead + AsyncWrite + Send,
+{
+    /// Queues a command to be executed by [`exec`](Self::exec).
+    ///
+    /// Fails if the server refused to queue the command, in which case `EXEC` fails as well.
+    pub async fn queue(&mut self, cmd: &Cmd) -> RedisResult<()> {
+        let cmd = self.con.apply_command_hook(cmd);
+        match self.con.send_single_command(&cmd).await? {
+            Value::Status(ref status) if status == "QUEUED" => Ok(()),
+            _ => fail!((
+                ErrorKind::ResponseError,
+                "Redis server refused to queue the command"
+            )),
+        }
+    }
 
-            if let Some(err) = first_err {
-                Err(err)
-            } else {
-                Ok(rv)
-            }
-        })
-        .boxed()
+    /// Executes all queued commands with `EXEC` and returns their responses.
+    ///
+    /// The response is nil if a watched key was modified.
+    pub async fn exec<T: FromRedisValue>(self) -> RedisResult<T> {
+        let response = self.con.send_single_command(&cmd("EXEC")).await;
+        self.finish(response)
     }
 
-    fn get_db(&self) -> i64 {
-        self.db
+    /// Discards all queued commands with `DISCARD`.
+    pub async fn discard(self) -> RedisResult<()> {
+        let response = self.con.send_single_command(&cmd("DISCARD")).await;
+        self.finish(response)
+    }
+
+    fn finish<T: FromRedisValue>(self, response: RedisResult<Value>) -> RedisResult<T> {
+        match response {
+            Err(err) if err.is_unrecoverable_error() => Err(err),
+            response => {
+                self.con.transaction = false;
+                from_redis_value(&response?)
+            }
+        }
     }
 }
 
@@ -299,6 +698,39 @@ where
             .await
     }
 
+    /// Subscribes to the keyevent notifications matching the `event` pattern for the database of
+    /// this connection, and returns a [`Stream`] of the parsed [`KeyspaceEvent`]s.
+    ///
+    /// This subscribes to `__keyevent@<db>__:<event>`, so `"*"` receives all events and
+    /// `"expired"` only expirations.  The server only sends notifications for the event classes
+    /// enabled by its `notify-keyspace-events` configuration option.  Messages from other
+    /// subscriptions of this connection are skipped by the returned stream.
+    pub async fn subscribe_keyevents(
+        &mut self,
+        event: &str,
+    ) -> RedisResult<impl Stream<Item = KeyspaceEvent> + '_> {
+        let db = self.0.db;
+        self.psubscribe(format!("__keyevent@{db}__:{event}"))
+            .await?;
+        Ok(self
+            .on_message()
+            .filter_map(|msg| std::future::ready(msg.keyspace_event())))
+    }
+
+    /// Reads the next [`Msg`] from the connection, skipping any other pubsub replies (such as
+    /// subscription confirmations).
+    ///
+    /// Unlike [`PubSub::on_message`] this keeps no state outside of the connection, so no
+    /// buffered data is lost between calls.
+    #[cfg(feature = "connection-manager")]
+    pub(crate) async fn next_message(&mut self) -> RedisResult<Msg> {
+        loop {
+            if let Some(msg) = Msg::from_value(&self.0.read_response().await?) {
+                return Ok(msg);
+            }
+        }
+    }
+
     /// Returns [`Stream`] of [`Msg`]s from this [`PubSub`]s subscriptions.
     ///
     /// The message itself is still generic and can be converted into an appropriate type through
@@ -381,13 +813,46 @@ async fn get_socket_addrs(
     }
 }
 
+// Connects to the addresses in order and returns the first successful connection. If all
+// attempts fail, the returned error lists why each of them failed.
+async fn connect_to_any<T, F>(
+    socket_addrs: impl Iterator<Item = SocketAddr>,
+    connect: impl Fn(SocketAddr) -> F,
+) -> RedisResult<T>
+where
+    F: Future<Output = RedisResult<T>>,
+{
+    let mut errors = Vec::new();
+    for socket_addr in socket_addrs {
+        match connect(socket_addr).await {
+            Ok(con) => return Ok(con),
+            Err(err) => errors.push((socket_addr, err)),
+        }
+    }
+    Err(aggregate_connect_errors(errors))
+}
+
 pub(crate) async fn connect_simple<T: RedisRuntime>(
-    connection_info: &ConnectionInfo,
+    connection_info: &ConnectionConfig,
 ) -> RedisResult<T> {
+    match connection_info.connect_timeout {
+        Some(timeout) => {
+            Runtime::locate()
+                .timeout(timeout, connect_socket::<T>(connection_info))
+                .await?
+        }
+        None => connect_socket::<T>(connection_info).await,
+    }
+}
+
+async fn connect_socket<T: RedisRuntime>(connection_info: &ConnectionConfig) -> RedisResult<T> {
     Ok(match connection_info.addr {
         ConnectionAddr::Tcp(ref host, port) => {
             let socket_addrs = get_socket_addrs(host, port).await?;
-            select_ok(socket_addrs.map(<T>::connect_tcp)).await?.0
+            connect_to_any(socket_addrs, |socket_addr| {
+                <T>::connect_tcp(socket_addr, connection_info.local_addr)
+            })
+            .await?
         }
 
         #[cfg(any(feature = "tls-native-tls", feature = "tls-rustls"))]
@@ -396,12 +861,18 @@ pub(crate) async fn connect_simple<T: RedisRuntime>(
             port,
             insecure,
         } => {
+            let server_name = connection_info.tls_server_name.as_deref().unwrap_or(host);
             let socket_addrs = get_socket_addrs(host, port).await?;
-            select_ok(
-                socket_addrs.map(|socket_addr| <T>::connect_tcp_tls(host, socket_addr, insecure)),
-            )
+            connect_to_any(socket_addrs, |socket_addr| {
+                <T>::connect_tcp_tls(
+                    server_name,
+                    socket_addr,
+                    connection_info.local_addr,
+                    insecure,
+                    connection_info.tls_certificate_check.as_ref(),
+                )
+            })
             .await?
-            .0
         }
 
         #[cfg(not(any(feature = "tls-native-tls", feature = "tls-rustls")))]
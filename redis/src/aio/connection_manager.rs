@@ -1,19 +1,24 @@
 use super::RedisFuture;
 use crate::cmd::Cmd;
-use crate::types::{RedisError, RedisResult, Value};
+use crate::types::{ErrorKind, RedisError, RedisResult, Value};
 use crate::{
     aio::{ConnectionLike, MultiplexedConnection, Runtime},
-    Client,
+    AuthStrategy, Client,
 };
 #[cfg(all(not(feature = "tokio-comp"), feature = "async-std-comp"))]
 use ::async_std::net::ToSocketAddrs;
 use arc_swap::{self, ArcSwap};
 use futures::{
+    channel::oneshot,
     future::{self, Shared},
     FutureExt,
 };
 use futures_util::future::BoxFuture;
-use std::sync::Arc;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio_retry::strategy::{jitter, ExponentialBackoff};
 use tokio_retry::Retry;
 
@@ -35,11 +40,34 @@ use tokio_retry::Retry;
 ///   user, but it will trigger a reconnection in the background.
 /// - The reconnect code will atomically swap the current (dead) connection
 ///   with a future that will eventually resolve to a `MultiplexedConnection`
-///   or to a `RedisError`
+///   or to a `RedisError`. Only one such future is created for each lost
+///   connection, however many clones see their commands fail at once.
 /// - All commands that are issued after the reconnect process has been
 ///   initiated, will have to await the connection future.
 /// - If reconnecting fails, all pending commands will be failed as well. A
 ///   new reconnection attempt will be triggered if the error is an I/O error.
+/// - With [`ReconnectPolicy::Never`], none of this happens: connection errors
+///   are only passed on, and the dead connection is kept, so that all further
+///   commands fail until a new manager is created.
+/// - If enabled with
+///   [`set_failover_reconnect_delay`](ConnectionManager::set_failover_reconnect_delay),
+///   errors signalling a failover trigger a reconnection as well, which is
+///   retried with a short fixed delay instead of the exponential backoff.
+/// - If enabled with [`set_retry_on_read_only`](ConnectionManager::set_retry_on_read_only),
+///   a `READONLY` error also triggers a reconnection, and the request is sent
+///   once more on the new connection.
+/// - If enabled with
+///   [`set_fail_fast_while_reconnecting`](ConnectionManager::set_fail_fast_while_reconnecting),
+///   commands issued while reconnecting fail with [`ErrorKind::Reconnecting`] right away,
+///   instead of awaiting the connection future.
+/// - If enabled with
+///   [`set_max_connection_lifetime`](ConnectionManager::set_max_connection_lifetime),
+///   the connection is replaced once it is older than the given lifetime, without waiting
+///   for an error.  The new connection is set up in the background, and the old one keeps
+///   serving commands until it is established.
+/// - Once the last clone of the `ConnectionManager` is dropped, the background
+///   tasks driving the connection and reconnecting to the server are cancelled,
+///   which closes the connection.
 ///
 /// [multiplexed-connection]: struct.MultiplexedConnection.html
 #[derive(Clone)]
@@ -50,11 +78,76 @@ pub struct ConnectionManager {
     ///
     /// The `ArcSwap` is required to be able to replace the connection
     /// without making the `ConnectionManager` mutable.
-    connection: Arc<ArcSwap<SharedRedisFuture<MultiplexedConnection>>>,
+    connection: Arc<ArcSwap<SharedRedisFuture<TimedConnection>>>,
+    /// Held by the clone replacing the connection, so that a single replacement is set up for
+    /// each lost connection, however many clones notice its loss at once.
+    reconnect_lock: Arc<Mutex<()>>,
+    /// Set while an expired connection is being replaced, so that a single replacement is set
+    /// up at a time.
+    rotating: Arc<AtomicBool>,
 
     runtime: Runtime,
     retry_strategy: ExponentialBackoff,
     number_of_retries: usize,
+    /// Whether `READONLY` errors trigger a reconnection and a single retry.
+    retry_on_read_only: bool,
+    /// Whether requests fail instead of awaiting a pending reconnection.
+    fail_fast_while_reconnecting: bool,
+    reconnect_policy: ReconnectPolicy,
+    /// The delay between reconnection attempts after a failover, if those are detected.
+    failover_reconnect_delay: Option<Duration>,
+    /// The age after which the connection is replaced, even though it still works.
+    max_connection_lifetime: Option<Duration>,
+    /// Resolves the password for each new connection, instead of the one of the client.
+    password_provider: Option<PasswordProvider>,
+
+    /// Dropped together with the last clone, which resolves `cancelled`.
+    _cancel: Arc<oneshot::Sender<()>>,
+    /// Resolves once the last clone was dropped, cancelling the spawned tasks.
+    cancelled: Shared<oneshot::Receiver<()>>,
+}
+
+impl fmt::Debug for ConnectionManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionManager")
+            .field("client", &self.client)
+            .field("connection", &self.current_connection())
+            .field("reconnecting", &self.connection.load().peek().is_none())
+            .field("number_of_retries", &self.number_of_retries)
+            .field("retry_on_read_only", &self.retry_on_read_only)
+            .field(
+                "fail_fast_while_reconnecting",
+                &self.fail_fast_while_reconnecting,
+            )
+            .field("reconnect_policy", &self.reconnect_policy)
+            .field("failover_reconnect_delay", &self.failover_reconnect_delay)
+            .field("max_connection_lifetime", &self.max_connection_lifetime)
+            .field("rotating", &self.rotating.load(Ordering::Relaxed))
+            .field("password_provider", &self.password_provider.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Describes whether a [`ConnectionManager`] replaces a dropped connection by itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReconnectPolicy {
+    /// Reconnect in the background when the connection was dropped.  This is the default.
+    #[default]
+    Automatic,
+    /// Never reconnect, leaving it to the caller to replace the manager once its connection
+    /// failed.
+    Never,
+}
+
+/// Resolves the password used for a new connection.
+type PasswordProvider = Arc<dyn Fn() -> BoxFuture<'static, RedisResult<String>> + Send + Sync>;
+
+/// A connection together with the time it was established.
+#[derive(Clone)]
+struct TimedConnection {
+    connection: MultiplexedConnection,
+    established: Instant,
 }
 
 /// A `RedisResult` that can be cloned because `RedisError` is behind an `Arc`.
@@ -63,12 +156,15 @@ type CloneableRedisResult<T> = Result<T, Arc<RedisError>>;
 /// Type alias for a shared boxed future that will resolve to a `CloneableRedisResult`.
 type SharedRedisFuture<T> = Shared<BoxFuture<'static, CloneableRedisResult<T>>>;
 
-/// Handle a command result. If the connection was dropped, reconnect.
+/// Handle a command result. If the connection was dropped, or the server became a replica and
+/// retrying on `READONLY` or detecting failovers is enabled, reconnect.
 macro_rules! reconnect_if_dropped {
     ($self:expr, $result:expr, $current:expr) => {
         if let Err(ref e) = $result {
-            if e.is_connection_dropped() {
-                $self.reconnect($current);
+            if let Some(delays) = $self.failover_delays(e) {
+                $self.reconnect(&$current, delays);
+            } else if e.is_connection_dropped() || $self.is_read_only_error($result) {
+                $self.reconnect(&$current, $self.backoff_delays());
             }
         }
     };
@@ -80,7 +176,7 @@ macro_rules! reconnect_if_io_error {
     ($self:expr, $result:expr, $current:expr) => {
         if let Err(e) = $result {
             if e.is_io_error() {
-                $self.reconnect($current);
+                $self.reconnect(&$current, $self.backoff_delays());
             }
             return Err(e);
         }
@@ -88,9 +184,9 @@ macro_rules! reconnect_if_io_error {
 }
 
 impl ConnectionManager {
-    const DEFAULT_CONNECTION_RETRY_EXPONENT_BASE: u64 = 2;
-    const DEFAULT_CONNECTION_RETRY_FACTOR: u64 = 100;
-    const DEFAULT_NUMBER_OF_CONNECTION_RETRIESE: usize = 6;
+    pub(crate) const DEFAULT_CONNECTION_RETRY_EXPONENT_BASE: u64 = 2;
+    pub(crate) const DEFAULT_CONNECTION_RETRY_FACTOR: u64 = 100;
+    pub(crate) const DEFAULT_NUMBER_OF_CONNECTION_RETRIESE: usize = 6;
 
     /// Connect to the server and store the connection inside the returned `ConnectionManager`.
     ///
@@ -124,8 +220,16 @@ impl ConnectionManager {
 
         let runtime = Runtime::locate();
         let retry_strategy = ExponentialBackoff::from_millis(exponent_base).factor(factor);
-        let connection =
-            Self::new_connection(client.clone(), retry_strategy.clone(), number_of_retries).await?;
+        let (cancel, cancelled) = oneshot::channel();
+        let cancelled = cancelled.shared();
+        let connection = Self::new_connection(
+            client.clone(),
+            None,
+            backoff_delays(&retry_strategy, number_of_retries),
+            runtime.clone(),
+            cancelled.clone(),
+        )
+        .await?;
 
         // Wrap the connection in an `ArcSwap` instance for fast atomic access
         Ok(Self {
@@ -133,59 +237,291 @@ impl ConnectionManager {
             connection: Arc::new(ArcSwap::from_pointee(
                 future::ok(connection).boxed().shared(),
             )),
+            reconnect_lock: Arc::default(),
+            rotating: Arc::default(),
             runtime,
             number_of_retries,
             retry_strategy,
+            retry_on_read_only: false,
+            fail_fast_while_reconnecting: false,
+            reconnect_policy: ReconnectPolicy::Automatic,
+            failover_reconnect_delay: None,
+            max_connection_lifetime: None,
+            password_provider: None,
+            _cancel: Arc::new(cancel),
+            cancelled,
         })
     }
 
     async fn new_connection(
         client: Client,
-        exponential_backoff: ExponentialBackoff,
-        number_of_retries: usize,
-    ) -> RedisResult<MultiplexedConnection> {
-        let retry_strategy = exponential_backoff.map(jitter).take(number_of_retries);
-        Retry::spawn(retry_strategy, || client.get_multiplexed_async_connection()).await
+        password_provider: Option<PasswordProvider>,
+        retry_delays: Vec<Duration>,
+        runtime: Runtime,
+        cancelled: Shared<oneshot::Receiver<()>>,
+    ) -> RedisResult<TimedConnection> {
+        let (connection, driver) = Retry::start(retry_delays, || {
+            let client = client.clone();
+            let password_provider = password_provider.clone();
+            async move {
+                let client = match password_provider {
+                    Some(provider) => client.with_password(provider().await?),
+                    None => client,
+                };
+                client.create_multiplexed_async_connection().await
+            }
+        })
+        .await?;
+        spawn_until_cancelled(&runtime, cancelled, driver);
+        Ok(TimedConnection {
+            connection,
+            established: Instant::now(),
+        })
     }
 
     /// Reconnect and overwrite the old connection.
     ///
     /// The `current` guard points to the shared future that was active
-    /// when the connection loss was detected.
-    fn reconnect(&self, current: arc_swap::Guard<Arc<SharedRedisFuture<MultiplexedConnection>>>) {
+    /// when the connection loss was detected.  Failed attempts are retried
+    /// after each of the `retry_delays`.
+    fn reconnect(
+        &self,
+        current: &Arc<SharedRedisFuture<TimedConnection>>,
+        retry_delays: Vec<Duration>,
+    ) {
+        if self.reconnect_policy == ReconnectPolicy::Never {
+            return;
+        }
+        // Clones noticing the same loss after the connection was replaced leave it be, without
+        // setting up another connection attempt.
+        let _lock = self.reconnect_lock.lock().unwrap();
+        if !Arc::ptr_eq(&self.connection.load(), current) {
+            return;
+        }
         let client = self.client.clone();
-        let retry_strategy = self.retry_strategy.clone();
-        let number_of_retries = self.number_of_retries;
-        let new_connection: SharedRedisFuture<MultiplexedConnection> = async move {
-            Ok(Self::new_connection(client, retry_strategy, number_of_retries).await?)
+        let password_provider = self.password_provider.clone();
+        let runtime = self.runtime.clone();
+        let cancelled = self.cancelled.clone();
+        let new_connection: SharedRedisFuture<TimedConnection> = async move {
+            Ok(
+                Self::new_connection(client, password_provider, retry_delays, runtime, cancelled)
+                    .await?,
+            )
         }
         .boxed()
         .shared();
 
-        // Update the connection in the connection manager
-        let new_connection_arc = Arc::new(new_connection.clone());
-        let prev = self
-            .connection
-            .compare_and_swap(&current, new_connection_arc);
+        // Update the connection in the connection manager.  Holding the lock, no other clone
+        // can have replaced it since the check above.
+        self.connection.store(Arc::new(new_connection.clone()));
+
+        // Start the connection attempt immediately but do not wait on it.
+        spawn_until_cancelled(
+            &self.runtime,
+            self.cancelled.clone(),
+            new_connection.map(|_| ()),
+        );
+    }
+
+    /// Sets the delay between reconnection attempts after a failover, or `None` to not detect
+    /// failovers, which is the default.
+    ///
+    /// During a coordinated `FAILOVER` the old master turns into a replica, which fails writes
+    /// with `READONLY` and blocking commands with `UNBLOCKED`.  When enabled, these errors
+    /// trigger a reconnection, as the new master is expected to be available almost
+    /// immediately.  The first attempt is made right away, further ones after the given delay
+    /// instead of the exponential backoff.  Only the failing request is passed the error.
+    /// The setting applies to each clone of the manager separately.
+    pub fn set_failover_reconnect_delay(&mut self, delay: Option<Duration>) {
+        self.failover_reconnect_delay = delay;
+    }
+
+    /// The delays between reconnection attempts after the given error, if it signals a failover.
+    fn failover_delays(&self, err: &RedisError) -> Option<Vec<Duration>> {
+        let delay = self.failover_reconnect_delay?;
+        match err.code() {
+            Some("READONLY") | Some("UNBLOCKED") => Some(vec![delay; self.number_of_retries]),
+            _ => None,
+        }
+    }
+
+    /// The delays between reconnection attempts after the connection was dropped.
+    fn backoff_delays(&self) -> Vec<Duration> {
+        backoff_delays(&self.retry_strategy, self.number_of_retries)
+    }
+
+    /// Sets whether a `READONLY` error reconnects and sends the request once more.
+    ///
+    /// After a failover, the configured address may still point to the old master, which
+    /// became a replica, until DNS or a virtual IP is updated.  Reconnecting resolves the
+    /// address again, so the retry likely reaches the new master.  This is disabled by
+    /// default, as `READONLY` is expected when writing to a replica on purpose.  The setting
+    /// applies to each clone of the manager separately.
+    pub fn set_retry_on_read_only(&mut self, retry: bool) {
+        self.retry_on_read_only = retry;
+    }
+
+    /// Sets whether the manager reconnects by itself once the connection was dropped.
+    ///
+    /// With [`ReconnectPolicy::Never`] connection errors are passed on and all further
+    /// commands fail as well, which suits supervisors that recreate the manager themselves.
+    /// This also disables retrying on `READONLY`.  The setting applies to each clone of the
+    /// manager separately.
+    pub fn set_reconnect_policy(&mut self, policy: ReconnectPolicy) {
+        self.reconnect_policy = policy;
+    }
+
+    /// Sets whether requests fail with [`ErrorKind::Reconnecting`] while a reconnection is
+    /// pending, instead of awaiting it, which is the default.
+    ///
+    /// This lets latency-sensitive callers shed load instead of queuing requests behind a slow
+    /// reconnection.  The request retried after a `READONLY` error with
+    /// [`set_retry_on_read_only`](Self::set_retry_on_read_only) fails as well, as it is sent
+    /// right after triggering the reconnection.  The setting applies to each clone of the
+    /// manager separately.
+    pub fn set_fail_fast_while_reconnecting(&mut self, fail_fast: bool) {
+        self.fail_fast_while_reconnecting = fail_fast;
+    }
+
+    /// Fails if the current connection is still being established and requests should not
+    /// await it.
+    fn check_reconnecting(&self, current: &SharedRedisFuture<TimedConnection>) -> RedisResult<()> {
+        if self.fail_fast_while_reconnecting && current.peek().is_none() {
+            fail!((ErrorKind::Reconnecting, "The connection is being replaced"));
+        }
+        Ok(())
+    }
+
+    /// Sets the age after which the connection is replaced, or `None` to keep it until it is
+    /// dropped, which is the default.
+    ///
+    /// Once a request finds the connection older than this, a new one is set up in the
+    /// background.  Requests are still sent on the old connection until the new one is
+    /// established, and if setting it up fails, the old connection is kept and the next request
+    /// tries again.  Combined with [`set_password_provider`](Self::set_password_provider), this
+    /// rotates short-lived credentials.  An idle connection is only replaced by the next
+    /// request.  This has no effect with [`ReconnectPolicy::Never`].  The setting applies to
+    /// each clone of the manager separately.
+    pub fn set_max_connection_lifetime(&mut self, lifetime: Option<Duration>) {
+        self.max_connection_lifetime = lifetime;
+    }
 
-        // If the swap happened...
-        if Arc::ptr_eq(&prev, &current) {
-            // ...start the connection attempt immediately but do not wait on it.
-            self.runtime.spawn(new_connection.map(|_| ()));
+    /// Sets a callback resolving the password for each new connection, which is used instead
+    /// of the password of the client.
+    ///
+    /// The callback is called for every connection attempt, so that it can fetch a fresh
+    /// token, e.g. for IAM authentication.  If it fails, the attempt fails with its error and
+    /// is retried like other connection errors.  The connection established while creating the
+    /// manager still uses the password of the client.  The setting applies to each clone of
+    /// the manager separately.
+    ///
+    /// This fails with [`ErrorKind::InvalidClientConfig`] if the client authenticates with
+    /// [`AuthStrategy::Commands`], which ignores the password.
+    pub fn set_password_provider<F, Fut>(&mut self, provider: F) -> RedisResult<()>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = RedisResult<String>> + Send + 'static,
+    {
+        if let AuthStrategy::Commands(_) = self.client.config().redis.auth_strategy {
+            fail!((
+                ErrorKind::InvalidClientConfig,
+                "A password provider can't be used with custom authentication commands"
+            ));
+        }
+        self.password_provider = Some(Arc::new(move || provider().boxed()));
+        Ok(())
+    }
+
+    /// Replaces the connection in the background once it is older than the maximum lifetime.
+    ///
+    /// Unlike [`reconnect`](Self::reconnect), the old connection is only swapped out once the
+    /// new one is established, and kept if that fails.
+    fn replace_if_expired(
+        &self,
+        current: &Arc<SharedRedisFuture<TimedConnection>>,
+        connection: &TimedConnection,
+    ) {
+        let expired = match self.max_connection_lifetime {
+            Some(lifetime) => connection.established.elapsed() >= lifetime,
+            None => false,
+        };
+        if !expired
+            || self.reconnect_policy == ReconnectPolicy::Never
+            || self.rotating.swap(true, Ordering::AcqRel)
+        {
+            return;
+        }
+        let client = self.client.clone();
+        let password_provider = self.password_provider.clone();
+        let retry_delays = self.backoff_delays();
+        let runtime = self.runtime.clone();
+        let cancelled = self.cancelled.clone();
+        let connection = self.connection.clone();
+        let reconnect_lock = self.reconnect_lock.clone();
+        let rotating = self.rotating.clone();
+        let current = current.clone();
+        let rotation = async move {
+            let new_connection =
+                Self::new_connection(client, password_provider, retry_delays, runtime, cancelled)
+                    .await;
+            if let Ok(new_connection) = new_connection {
+                // If the connection was lost and replaced in the meantime, that replacement is
+                // kept and the new connection is dropped.
+                let _lock = reconnect_lock.lock().unwrap();
+                if Arc::ptr_eq(&connection.load(), &current) {
+                    connection.store(Arc::new(future::ok(new_connection).boxed().shared()));
+                }
+            }
+            rotating.store(false, Ordering::Release);
+        };
+        spawn_until_cancelled(&self.runtime, self.cancelled.clone(), rotation);
+    }
+
+    /// Returns a clone of the current connection, or `None` while reconnecting or after
+    /// reconnecting failed.
+    ///
+    /// This doesn't wait for a pending reconnection.  The returned connection is not replaced
+    /// once it is dropped, so commands sent on it keep failing after a connection loss.
+    pub fn current_connection(&self) -> Option<MultiplexedConnection> {
+        match self.connection.load().peek() {
+            Some(Ok(timed)) => Some(timed.connection.clone()),
+            _ => None,
+        }
+    }
+
+    fn is_read_only_error<T>(&self, result: &RedisResult<T>) -> bool {
+        match result {
+            Err(e) => {
+                self.retry_on_read_only
+                    && self.reconnect_policy == ReconnectPolicy::Automatic
+                    && e.kind() == ErrorKind::ReadOnly
+            }
+            Ok(_) => false,
         }
     }
 
     /// Sends an already encoded (packed) command into the TCP socket and
     /// reads the single response from it.
     pub async fn send_packed_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        let result = self.send_packed_command_once(cmd).await;
+        if self.is_read_only_error(&result) {
+            return self.send_packed_command_once(cmd).await;
+        }
+        result
+    }
+
+    async fn send_packed_command_once(&mut self, cmd: &Cmd) -> RedisResult<Value> {
         // Clone connection to avoid having to lock the ArcSwap in write mode
         let guard = self.connection.load();
+        self.check_reconnecting(&guard)?;
         let connection_result = (**guard)
             .clone()
             .await
             .map_err(|e| e.clone_mostly("Reconnecting failed"));
         reconnect_if_io_error!(self, connection_result, guard);
-        let result = connection_result?.send_packed_command(cmd).await;
+        let mut timed = connection_result?;
+        self.replace_if_expired(&guard, &timed);
+        let result = timed.connection.send_packed_command(cmd).await;
         reconnect_if_dropped!(self, &result, guard);
         result
     }
@@ -198,15 +534,32 @@ impl ConnectionManager {
         cmd: &crate::Pipeline,
         offset: usize,
         count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        let result = self.send_packed_commands_once(cmd, offset, count).await;
+        if self.is_read_only_error(&result) {
+            return self.send_packed_commands_once(cmd, offset, count).await;
+        }
+        result
+    }
+
+    async fn send_packed_commands_once(
+        &mut self,
+        cmd: &crate::Pipeline,
+        offset: usize,
+        count: usize,
     ) -> RedisResult<Vec<Value>> {
         // Clone shared connection future to avoid having to lock the ArcSwap in write mode
         let guard = self.connection.load();
+        self.check_reconnecting(&guard)?;
         let connection_result = (**guard)
             .clone()
             .await
             .map_err(|e| e.clone_mostly("Reconnecting failed"));
         reconnect_if_io_error!(self, connection_result, guard);
-        let result = connection_result?
+        let mut timed = connection_result?;
+        self.replace_if_expired(&guard, &timed);
+        let result = timed
+            .connection
             .send_packed_commands(cmd, offset, count)
             .await;
         reconnect_if_dropped!(self, &result, guard);
@@ -214,6 +567,50 @@ impl ConnectionManager {
     }
 }
 
+impl ConnectionManager {
+    /// Writes pre-encoded bytes into the TCP socket as they are and reads `count` responses
+    /// from it.
+    ///
+    /// If `count` doesn't match the number of commands in the bytes, all following requests
+    /// receive the wrong responses until the connection is replaced.  Unlike other requests,
+    /// this is not retried on `READONLY`.
+    pub async fn send_raw(&mut self, bytes: &[u8], count: usize) -> RedisResult<Vec<Value>> {
+        // Clone shared connection future to avoid having to lock the ArcSwap in write mode
+        let guard = self.connection.load();
+        self.check_reconnecting(&guard)?;
+        let connection_result = (**guard)
+            .clone()
+            .await
+            .map_err(|e| e.clone_mostly("Reconnecting failed"));
+        reconnect_if_io_error!(self, connection_result, guard);
+        let mut timed = connection_result?;
+        self.replace_if_expired(&guard, &timed);
+        let result = timed.connection.send_raw(bytes, count).await;
+        reconnect_if_dropped!(self, &result, guard);
+        result
+    }
+}
+
+/// Returns the jittered delays of the exponential backoff for the given number of retries.
+fn backoff_delays(retry_strategy: &ExponentialBackoff, number_of_retries: usize) -> Vec<Duration> {
+    retry_strategy
+        .clone()
+        .map(jitter)
+        .take(number_of_retries)
+        .collect()
+}
+
+/// Spawns `task`, dropping it early once the last `ConnectionManager` clone was dropped.
+fn spawn_until_cancelled(
+    runtime: &Runtime,
+    cancelled: Shared<oneshot::Receiver<()>>,
+    task: impl Future<Output = ()> + Send + 'static,
+) {
+    runtime.spawn(async move {
+        future::select(Box::pin(task), cancelled).await;
+    });
+}
+
 impl ConnectionLike for ConnectionManager {
     fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
         (async move { self.send_packed_command(cmd).await }).boxed()
@@ -228,7 +625,15 @@ impl ConnectionLike for ConnectionManager {
         (async move { self.send_packed_commands(cmd, offset, count).await }).boxed()
     }
 
+    fn req_raw<'a>(
+        &'a mut self,
+        bytes: &'a [u8],
+        expected_responses: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        (async move { self.send_raw(bytes, expected_responses).await }).boxed()
+    }
+
     fn get_db(&self) -> i64 {
-        self.client.connection_info().redis.db
+        self.client.config().redis.db
     }
 }
@@ -1,7 +1,13 @@
 //! Adds async IO support to redis.
-use crate::cmd::{cmd, Cmd};
-use crate::connection::RedisConnectionInfo;
-use crate::types::{ErrorKind, RedisFuture, RedisResult, Value};
+use crate::cmd::{cmd, pipe, Cmd};
+use crate::connection::{
+    add_handshake_commands, check_resp3_supported, check_role, check_setup_replies,
+    handshake_pipeline, protocol_from_handshake, protocol_from_hello, setup_commands_pipeline,
+    AuthStrategy, ProtocolVersion, RedisConnectionConfig, RequiredRole, SelectStrategy,
+};
+use crate::types::{from_redis_value, ErrorKind, FromRedisValue, RedisFuture, RedisResult, Value};
+#[cfg(any(feature = "tls-native-tls", feature = "tls-rustls"))]
+use crate::TlsCertificateCheck;
 use ::tokio::io::{AsyncRead, AsyncWrite};
 use async_trait::async_trait;
 use futures_util::Future;
@@ -23,15 +29,20 @@ pub mod tokio;
 /// Represents the ability of connecting via TCP or via Unix socket
 #[async_trait]
 pub(crate) trait RedisRuntime: AsyncStream + Send + Sync + Sized + 'static {
-    /// Performs a TCP connection
-    async fn connect_tcp(socket_addr: SocketAddr) -> RedisResult<Self>;
+    /// Performs a TCP connection, optionally from the given local address
+    async fn connect_tcp(
+        socket_addr: SocketAddr,
+        local_addr: Option<SocketAddr>,
+    ) -> RedisResult<Self>;
 
     // Performs a TCP TLS connection
     #[cfg(any(feature = "tls-native-tls", feature = "tls-rustls"))]
     async fn connect_tcp_tls(
         hostname: &str,
         socket_addr: SocketAddr,
+        local_addr: Option<SocketAddr>,
         insecure: bool,
+        certificate_check: Option<&TlsCertificateCheck>,
     ) -> RedisResult<Self>;
 
     /// Performs a UNIX connection
@@ -50,6 +61,16 @@ pub trait AsyncStream: AsyncRead + AsyncWrite {}
 impl<S> AsyncStream for S where S: AsyncRead + AsyncWrite {}
 
 /// An async abstraction over connections.
+///
+/// The futures returned by this trait are boxed, which allocates once per request.  On hot
+/// paths the inherent `send_packed_command` and `send_packed_commands` methods of
+/// [`Connection`], [`MultiplexedConnection`] and `ConnectionManager` can be used instead, which
+/// return unboxed futures; the `simple_getsetdel_async_unboxed` benchmark compares the two.
+///
+/// The trait itself keeps returning [`RedisFuture`]: an associated future type borrowing the
+/// connection needs generic associated types (Rust 1.65) and `impl Trait` in traits needs
+/// Rust 1.75, both newer than the minimum supported Rust version, and either would break
+/// existing implementations of the trait.
 pub trait ConnectionLike {
     /// Sends an already encoded (packed) command into the TCP socket and
     /// reads the single response from it.
@@ -65,67 +86,260 @@ pub trait ConnectionLike {
         count: usize,
     ) -> RedisFuture<'a, Vec<Value>>;
 
+    /// Writes pre-encoded RESP bytes as they are and reads `expected_responses` responses, e.g.
+    /// to replay captured traffic or to send commands that can't be built with [`Cmd`].
+    ///
+    /// Nothing checks that `expected_responses` matches the number of commands in `bytes`.  If
+    /// it doesn't, the responses get out of step with the requests and all following requests
+    /// on the connection receive the wrong responses.  Connections that can't send raw bytes,
+    /// like cluster connections, which route each command by its keys, fail with
+    /// [`ErrorKind::InvalidClientConfig`].
+    fn req_raw<'a>(
+        &'a mut self,
+        bytes: &'a [u8],
+        expected_responses: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        let _ = (bytes, expected_responses);
+        Box::pin(async {
+            fail!((
+                ErrorKind::InvalidClientConfig,
+                "This connection doesn't support sending raw bytes"
+            ))
+        })
+    }
+
     /// Returns the database this connection is bound to.  Note that this
     /// information might be unreliable because it's initially cached and
     /// also might be incorrect if the connection like object is not
     /// actually connected.
     fn get_db(&self) -> i64;
+
+    /// Sends the command and converts the response, mapping a nil response to `Ok(None)`.
+    ///
+    /// This makes it possible to tell a missing value (such as `GET` of a key that does not
+    /// exist) apart from a response that could not be converted into `RV`, which is still
+    /// reported as an error.
+    fn query_optional<'a, RV>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Option<RV>>
+    where
+        Self: Sized + Send,
+        RV: FromRedisValue,
+    {
+        Box::pin(async move {
+            match self.req_packed_command(cmd).await? {
+                Value::Nil => Ok(None),
+                value => from_redis_value(&value).map(Some),
+            }
+        })
+    }
 }
 
-// Initial setup for every connection.
-async fn setup_connection<C>(connection_info: &RedisConnectionInfo, con: &mut C) -> RedisResult<()>
+// Sends `AUTH` if a password is configured, falling back to the old single argument form.
+async fn authenticate<C>(connection_info: &RedisConnectionConfig, con: &mut C) -> RedisResult<()>
 where
     C: ConnectionLike,
 {
-    if let Some(password) = &connection_info.password {
-        let mut command = cmd("AUTH");
-        if let Some(username) = &connection_info.username {
-            command.arg(username);
-        }
-        match command.arg(password).query_async(con).await {
-            Ok(Value::Okay) => (),
-            Err(e) => {
-                let err_msg = e.detail().ok_or((
+    let password = match &connection_info.password {
+        Some(password) => password,
+        None => return Ok(()),
+    };
+    let mut command = cmd("AUTH");
+    if let Some(username) = &connection_info.username {
+        command.arg(username);
+    }
+    match command.arg(password).query_async(con).await {
+        Ok(Value::Okay) => (),
+        Err(e) => {
+            let err_msg = e.detail().ok_or((
+                ErrorKind::AuthenticationFailed,
+                "Password authentication failed",
+            ))?;
+
+            if !err_msg.contains("wrong number of arguments for 'auth' command") {
+                fail!((
                     ErrorKind::AuthenticationFailed,
                     "Password authentication failed",
-                ))?;
+                ));
+            }
 
-                if !err_msg.contains("wrong number of arguments for 'auth' command") {
+            let mut command = cmd("AUTH");
+            match command.arg(password).query_async(con).await {
+                Ok(Value::Okay) => (),
+                _ => {
                     fail!((
                         ErrorKind::AuthenticationFailed,
-                        "Password authentication failed",
+                        "Password authentication failed"
                     ));
                 }
+            }
+        }
+        _ => {
+            fail!((
+                ErrorKind::AuthenticationFailed,
+                "Password authentication failed"
+            ));
+        }
+    }
+    Ok(())
+}
 
-                let mut command = cmd("AUTH");
-                match command.arg(password).query_async(con).await {
-                    Ok(Value::Okay) => (),
-                    _ => {
-                        fail!((
-                            ErrorKind::AuthenticationFailed,
-                            "Password authentication failed"
-                        ));
-                    }
-                }
+// Authenticates and selects the database as configured.
+async fn authenticate_and_select<C>(
+    connection_info: &RedisConnectionConfig,
+    con: &mut C,
+) -> RedisResult<ProtocolVersion>
+where
+    C: ConnectionLike,
+{
+    if let AuthStrategy::Commands(ref commands) = connection_info.auth_strategy {
+        for command in commands {
+            command.query_async::<_, Value>(con).await?;
+        }
+    }
+
+    let protocol = handshake(connection_info, con).await?;
+
+    if let Some(pipeline) = setup_commands_pipeline(connection_info) {
+        let replies = pipeline.query_async::<_, Vec<Value>>(con).await?;
+        check_setup_replies(connection_info, &replies)?;
+    }
+    Ok(protocol)
+}
+
+// Sends `AUTH`, `HELLO` and `SELECT` as configured, in a single round trip if possible.
+async fn handshake<C>(
+    connection_info: &RedisConnectionConfig,
+    con: &mut C,
+) -> RedisResult<ProtocolVersion>
+where
+    C: ConnectionLike,
+{
+    if let Some(pipeline) = handshake_pipeline(connection_info)? {
+        let replies = pipeline.query_async::<_, Vec<Value>>(con).await;
+        if let Some(protocol) = protocol_from_handshake(connection_info, replies) {
+            return Ok(protocol);
+        }
+    }
+
+    if matches!(connection_info.auth_strategy, AuthStrategy::Auth) {
+        authenticate(connection_info, con).await?;
+    }
+
+    let protocol = match connection_info.protocol {
+        ProtocolVersion::RESP2 => ProtocolVersion::RESP2,
+        ProtocolVersion::RESP3 => {
+            check_resp3_supported()?;
+            match cmd("HELLO").arg(3).query_async(con).await {
+                Ok(reply) => protocol_from_hello(&reply)?,
+                Err(e) if e.is_unknown_command() => ProtocolVersion::RESP2,
+                Err(e) => return Err(e),
             }
-            _ => {
-                fail!((
-                    ErrorKind::AuthenticationFailed,
-                    "Password authentication failed"
-                ));
+        }
+    };
+
+    if connection_info.should_select() {
+        select_db(connection_info.db, con).await?;
+    }
+    Ok(protocol)
+}
+
+async fn select_db<C>(db: i64, con: &mut C) -> RedisResult<()>
+where
+    C: ConnectionLike,
+{
+    match cmd("SELECT").arg(db).query_async(con).await {
+        Ok(Value::Okay) => Ok(()),
+        _ => fail!((
+            ErrorKind::ResponseError,
+            "Redis server refused to switch database"
+        )),
+    }
+}
+
+// Sends `RESET` and sets the connection up again, falling back to only selecting the database
+// on servers that don't support `RESET`.  Returns the protocol version spoken afterwards.
+async fn reset_connection<C>(
+    connection_info: &RedisConnectionConfig,
+    protocol: ProtocolVersion,
+    con: &mut C,
+) -> RedisResult<ProtocolVersion>
+where
+    C: ConnectionLike,
+{
+    match cmd("RESET").query_async(con).await {
+        Ok(Value::Status(ref status)) if status == "RESET" => {
+            authenticate_and_select(connection_info, con).await
+        }
+        Ok(_) => fail!((
+            ErrorKind::ResponseError,
+            "Redis server refused to reset the connection"
+        )),
+        Err(e) if e.is_unknown_command() => {
+            if connection_info.select_strategy != SelectStrategy::Never {
+                select_db(connection_info.db, con).await?;
             }
+            Ok(protocol)
         }
+        Err(e) => Err(e),
     }
+}
 
-    if connection_info.db != 0 {
-        match cmd("SELECT").arg(connection_info.db).query_async(con).await {
-            Ok(Value::Okay) => (),
-            _ => fail!((
-                ErrorKind::ResponseError,
-                "Redis server refused to switch database"
-            )),
+// Like `reset_connection`, but sends `RESET` and all setup commands as a single request, so that
+// requests sent on clones of a multiplexed connection can't end up in between, on a connection
+// that is not authenticated.  If any of the commands fails, e.g. because the server doesn't know
+// `RESET` or only supports `AUTH` without a username, the connection is reset once more one
+// command at a time, which falls back to older commands and reports the exact error.
+async fn reset_connection_at_once<C>(
+    connection_info: &RedisConnectionConfig,
+    protocol: ProtocolVersion,
+    con: &mut C,
+) -> RedisResult<ProtocolVersion>
+where
+    C: ConnectionLike,
+{
+    let auth_commands = match connection_info.auth_strategy {
+        AuthStrategy::Commands(ref commands) => commands.as_slice(),
+        AuthStrategy::Auth => &[],
+    };
+    let mut pipeline = pipe();
+    pipeline.cmd("RESET");
+    for command in auth_commands {
+        pipeline.add_command(command.clone());
+    }
+    let handshake_len = add_handshake_commands(connection_info, &mut pipeline)?;
+    for command in &connection_info.setup_commands {
+        pipeline.add_command(command.clone());
+    }
+
+    if let Ok(replies) = pipeline.query_async::<_, Vec<Value>>(con).await {
+        let handshake_start = 1 + auth_commands.len();
+        let setup_start = handshake_start + handshake_len;
+        if replies.len() == setup_start + connection_info.setup_commands.len()
+            && replies[0] == Value::Status("RESET".to_string())
+        {
+            let handshake = replies[handshake_start..setup_start].to_vec();
+            if let Some(protocol) = protocol_from_handshake(connection_info, Ok(handshake)) {
+                check_setup_replies(connection_info, &replies[setup_start..])?;
+                return Ok(protocol);
+            }
         }
     }
+    reset_connection(connection_info, protocol, con).await
+}
+
+// Initial setup for every connection.  Returns the negotiated protocol version.
+async fn setup_connection<C>(
+    connection_info: &RedisConnectionConfig,
+    con: &mut C,
+) -> RedisResult<ProtocolVersion>
+where
+    C: ConnectionLike,
+{
+    let protocol = authenticate_and_select(connection_info, con).await?;
+
+    if connection_info.required_role != RequiredRole::Any {
+        let reply = cmd("ROLE").query_async(con).await?;
+        check_role(connection_info.required_role, &reply)?;
+    }
 
     // result is ignored, as per the command's instructions.
     // https://redis.io/commands/client-setinfo/
@@ -133,16 +347,24 @@ where
         .query_async(con)
         .await;
 
-    Ok(())
+    Ok(protocol)
 }
 
 mod connection;
 pub use connection::*;
 mod multiplexed_connection;
 pub use multiplexed_connection::*;
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+mod poll_connection;
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub use poll_connection::*;
 #[cfg(feature = "connection-manager")]
 mod connection_manager;
 #[cfg(feature = "connection-manager")]
 pub use connection_manager::*;
+#[cfg(feature = "connection-manager")]
+mod pubsub_manager;
+#[cfg(feature = "connection-manager")]
+pub use pubsub_manager::*;
 mod runtime;
 pub(super) use runtime::*;
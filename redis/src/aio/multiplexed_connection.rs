@@ -1,10 +1,17 @@
-use super::ConnectionLike;
-use crate::aio::setup_connection;
-use crate::cmd::Cmd;
-use crate::connection::RedisConnectionInfo;
+use super::{ConnectionLike, Runtime};
+use crate::aio::{reset_connection_at_once, setup_connection};
+use crate::cmd::{cmd, Cmd};
+use crate::command_table::{command_table_cmd, load_command_table, CommandTable};
+use crate::commands::is_blocking_cmd;
+use crate::commands::TrackingOptions;
+use crate::connection::{
+    command_name, Invalidation, ProtocolVersion, RedisConnectionConfig, RedisConnectionInfo,
+    INVALIDATION_CHANNEL,
+};
 #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
-use crate::parser::ValueCodec;
-use crate::types::{RedisError, RedisFuture, RedisResult, Value};
+use crate::parser::{BufferPool, ValueCodec};
+use crate::types::{ErrorKind, RedisError, RedisFuture, RedisResult, Value};
+use crate::Client;
 use ::tokio::{
     io::{AsyncRead, AsyncWrite},
     sync::{mpsc, oneshot},
@@ -14,56 +21,194 @@ use futures_util::{
     ready,
     sink::Sink,
     stream::{self, Stream, StreamExt, TryStreamExt as _},
+    task::AtomicWaker,
 };
 use pin_project_lite::pin_project;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::fmt::Debug;
 use std::io;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::task::{self, Poll};
+use std::time::{Duration, Instant};
 #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
 use tokio_util::codec::Decoder;
+use tokio_util::sync::CancellationToken;
+
+// A response, together with the share of the response budget it takes until it is received
+type Delivery<T> = (T, Option<BudgetPermit>);
 
 // Senders which the result of a single request are sent through
-type PipelineOutput<O, E> = oneshot::Sender<Result<Vec<O>, E>>;
+enum PipelineOutput<O, E> {
+    // All responses are collected and sent at once
+    Collect(oneshot::Sender<Delivery<Result<Vec<O>, E>>>),
+    // Each response is sent as soon as it arrives
+    Stream(mpsc::UnboundedSender<Delivery<Result<O, E>>>),
+    // Each response is sent through its own sender, in order
+    Each(VecDeque<oneshot::Sender<Delivery<Result<O, E>>>>),
+}
+
+impl<O, E> PipelineOutput<O, E> {
+    fn is_closed(&self) -> bool {
+        match self {
+            PipelineOutput::Collect(sender) => sender.is_closed(),
+            PipelineOutput::Stream(sender) => sender.is_closed(),
+            PipelineOutput::Each(senders) => senders.iter().all(|sender| sender.is_closed()),
+        }
+    }
+
+    fn send_err(self, err: E) {
+        // `Err` means that the receiver was dropped, so nobody cares about the error
+        match self {
+            PipelineOutput::Collect(sender) => {
+                let _ = sender.send((Err(err), None));
+            }
+            PipelineOutput::Stream(sender) => {
+                let _ = sender.send((Err(err), None));
+            }
+            // The remaining senders are dropped, failing their requests as well
+            PipelineOutput::Each(mut senders) => {
+                if let Some(sender) = senders.pop_front() {
+                    let _ = sender.send((Err(err), None));
+                }
+            }
+        }
+    }
+}
 
 struct InFlight<O, E> {
     output: PipelineOutput<O, E>,
     expected_response_count: usize,
     current_response_count: usize,
-    buffer: Vec<O>,
-    first_err: Option<E>,
+    buffer: Vec<Result<O, E>>,
+    _request: PendingRequest,
 }
 
 impl<O, E> InFlight<O, E> {
-    fn new(output: PipelineOutput<O, E>, expected_response_count: usize) -> Self {
+    fn new(
+        output: PipelineOutput<O, E>,
+        expected_response_count: usize,
+        request: PendingRequest,
+    ) -> Self {
         Self {
             output,
             expected_response_count,
             current_response_count: 0,
             buffer: Vec::new(),
-            first_err: None,
+            _request: request,
+        }
+    }
+}
+
+// Counts the requests which were sent through a `Pipeline`, but not completed by the driver yet.
+// Shared by all clones of the pipeline; if the last one is dropped while requests are pending,
+// e.g. because the driver is never polled, a warning is logged.
+struct PendingRequests(Arc<AtomicUsize>);
+
+#[cfg(feature = "debug-logging")]
+impl Drop for PendingRequests {
+    fn drop(&mut self) {
+        let pending = self.0.load(Ordering::Acquire);
+        if pending > 0 {
+            log::warn!(
+                "Multiplexed connection dropped with {pending} pending requests, which only \
+                 complete if its driver keeps being polled"
+            );
         }
     }
 }
 
+// Counts as a pending request until it is dropped along with its message or in-flight entry.
+struct PendingRequest(Arc<AtomicUsize>);
+
+impl PendingRequest {
+    fn new(pending: &PendingRequests) -> Self {
+        pending.0.fetch_add(1, Ordering::AcqRel);
+        PendingRequest(pending.0.clone())
+    }
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+// Limits the total size of the responses which were read, but not yet received by their
+// requests.  Shared by the driver and all clones of a connection.
+struct ResponseBudget {
+    // The limit in bytes, `usize::MAX` if there is none
+    limit: AtomicUsize,
+    used: AtomicUsize,
+    // Wakes the driver once responses were received
+    waker: AtomicWaker,
+}
+
+impl Default for ResponseBudget {
+    fn default() -> Self {
+        Self {
+            limit: AtomicUsize::new(usize::MAX),
+            used: AtomicUsize::new(0),
+            waker: AtomicWaker::new(),
+        }
+    }
+}
+
+impl ResponseBudget {
+    // Takes `size` bytes of the budget until the returned permit is dropped.  Nothing is
+    // accounted while there is no limit.
+    fn permit(self: &Arc<Self>, size: usize) -> Option<BudgetPermit> {
+        if self.limit.load(Ordering::Relaxed) == usize::MAX {
+            return None;
+        }
+        self.used.fetch_add(size, Ordering::AcqRel);
+        Some(BudgetPermit {
+            budget: self.clone(),
+            size,
+        })
+    }
+
+    fn is_exceeded(&self) -> bool {
+        self.used.load(Ordering::Acquire) > self.limit.load(Ordering::Relaxed)
+    }
+}
+
+// A share of a `ResponseBudget`, which is given back once the response holding it is received
+// or dropped.
+struct BudgetPermit {
+    budget: Arc<ResponseBudget>,
+    size: usize,
+}
+
+impl Drop for BudgetPermit {
+    fn drop(&mut self) {
+        self.budget.used.fetch_sub(self.size, Ordering::AcqRel);
+        self.budget.waker.wake();
+    }
+}
+
 // A single message sent through the pipeline
 struct PipelineMessage<S, I, E> {
     input: S,
     output: PipelineOutput<I, E>,
     response_count: usize,
+    request: PendingRequest,
 }
 
 /// Wrapper around a `Stream + Sink` where each item sent through the `Sink` results in one or more
 /// items being output by the `Stream` (the number is specified at time of sending). With the
 /// interface provided by `Pipeline` an easy interface of request to response, hiding the `Stream`
 /// and `Sink`.
-struct Pipeline<SinkItem, I, E>(mpsc::Sender<PipelineMessage<SinkItem, I, E>>);
+struct Pipeline<SinkItem, I, E>(
+    mpsc::Sender<PipelineMessage<SinkItem, I, E>>,
+    Arc<PendingRequests>,
+);
 
 impl<SinkItem, I, E> Clone for Pipeline<SinkItem, I, E> {
     fn clone(&self) -> Self {
-        Pipeline(self.0.clone())
+        Pipeline(self.0.clone(), self.1.clone())
     }
 }
 
@@ -78,42 +223,101 @@ where
     }
 }
 
+// Why the driver of a `Pipeline` stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DriverExit {
+    // All clones of the pipeline were dropped, so no more requests can come in
+    Dropped,
+    // The response stream ended, e.g. because the server closed the connection
+    StreamEnded,
+    // Writing a request failed, which was passed on to its caller
+    WriteFailed,
+}
+
+impl DriverExit {
+    #[cfg(feature = "debug-logging")]
+    fn log(self, id: u64) {
+        match self {
+            DriverExit::Dropped => {
+                log::debug!("Multiplexed connection {id} driver stopped: dropped")
+            }
+            DriverExit::StreamEnded => {
+                log::warn!("Multiplexed connection {id} driver stopped: the response stream ended")
+            }
+            DriverExit::WriteFailed => {
+                log::warn!("Multiplexed connection {id} driver stopped: writing a request failed")
+            }
+        }
+    }
+
+    #[cfg(not(feature = "debug-logging"))]
+    fn log(self, _id: u64) {}
+}
+
+// Turns the responses collected for a request into its result, given the number of responses
+// that were expected.
+type CollectResponses<I, E> = fn(Vec<Result<I, E>>, usize) -> Result<Vec<I>, E>;
+
+// Estimates the number of bytes a response takes in memory.
+type ResponseSize<I, E> = fn(&Result<I, E>) -> usize;
+
 pin_project! {
     struct PipelineSink<T, I, E> {
         #[pin]
         sink_stream: T,
         in_flight: VecDeque<InFlight<I, E>>,
         error: Option<E>,
+        collect: CollectResponses<I, E>,
+        budget: Arc<ResponseBudget>,
+        size: ResponseSize<I, E>,
     }
 }
 
 impl<T, I, E> PipelineSink<T, I, E>
 where
-    T: Stream<Item = Result<I, E>> + 'static,
+    T: Stream<Item = Result<I, E>>,
 {
-    fn new<SinkItem>(sink_stream: T) -> Self
+    fn new<SinkItem>(
+        sink_stream: T,
+        collect: CollectResponses<I, E>,
+        budget: Arc<ResponseBudget>,
+        size: ResponseSize<I, E>,
+    ) -> Self
     where
-        T: Sink<SinkItem, Error = E> + Stream<Item = Result<I, E>> + 'static,
+        T: Sink<SinkItem, Error = E> + Stream<Item = Result<I, E>>,
     {
         PipelineSink {
             sink_stream,
             in_flight: VecDeque::new(),
             error: None,
+            collect,
+            budget,
+            size,
         }
     }
 
     // Read messages from the stream and send them back to the caller
-    fn poll_read(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Result<(), ()>> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Result<(), DriverExit>> {
         loop {
             // No need to try reading a message if there is no message in flight
             if self.in_flight.is_empty() {
                 return Poll::Ready(Ok(()));
             }
+            // Stop reading while too many responses wait to be received, until one of them is
+            if self.budget.is_exceeded() {
+                self.budget.waker.register(cx.waker());
+                if self.budget.is_exceeded() {
+                    return Poll::Pending;
+                }
+            }
             let item = match ready!(self.as_mut().project().sink_stream.poll_next(cx)) {
                 Some(result) => result,
                 // The redis response stream is not going to produce any more items so we `Err`
                 // to break out of the `forward` combinator and stop handling requests
-                None => return Poll::Ready(Err(())),
+                None => {
+                    self.as_mut().send_interrupted();
+                    return Poll::Ready(Err(DriverExit::StreamEnded));
+                }
             };
             self.as_mut().send_result(item);
         }
@@ -128,15 +332,22 @@ where
                 None => return,
             };
 
-            match result {
-                Ok(item) => {
-                    entry.buffer.push(item);
+            match (&mut entry.output, result) {
+                (PipelineOutput::Stream(sender), result) => {
+                    // The receiver may have been dropped, in which case the remaining responses
+                    // are still read but discarded
+                    let permit = self_.budget.permit((self_.size)(&result));
+                    let _ = sender.send((result, permit));
                 }
-                Err(err) => {
-                    if entry.first_err.is_none() {
-                        entry.first_err = Some(err);
+                (PipelineOutput::Each(senders), result) => {
+                    if let Some(sender) = senders.pop_front() {
+                        let permit = self_.budget.permit((self_.size)(&result));
+                        let _ = sender.send((result, permit));
                     }
                 }
+                (PipelineOutput::Collect(_), result) => {
+                    entry.buffer.push(result);
+                }
             }
 
             entry.current_response_count += 1;
@@ -147,23 +358,42 @@ where
         }
 
         let entry = self_.in_flight.pop_front().unwrap();
-        let response = match entry.first_err {
-            Some(err) => Err(err),
-            None => Ok(entry.buffer),
-        };
+        if let PipelineOutput::Collect(output) = entry.output {
+            let permit = self_
+                .budget
+                .permit(entry.buffer.iter().map(*self_.size).sum());
+            let response = (self_.collect)(entry.buffer, entry.expected_response_count);
 
-        // `Err` means that the receiver was dropped in which case it does not
-        // care about the output and we can continue by just dropping the value
-        // and sender
-        entry.output.send(response).ok();
+            // `Err` means that the receiver was dropped in which case it does not
+            // care about the output and we can continue by just dropping the value
+            // and sender
+            output.send((response, permit)).ok();
+        }
+    }
+
+    // Completes the request whose responses were being read when the stream ended, so that the
+    // responses which were already read are not lost.
+    fn send_interrupted(self: Pin<&mut Self>) {
+        let self_ = self.project();
+        if let Some(entry) = self_.in_flight.pop_front() {
+            if let PipelineOutput::Collect(output) = entry.output {
+                if !entry.buffer.is_empty() {
+                    let permit = self_
+                        .budget
+                        .permit(entry.buffer.iter().map(*self_.size).sum());
+                    let response = (self_.collect)(entry.buffer, entry.expected_response_count);
+                    output.send((response, permit)).ok();
+                }
+            }
+        }
     }
 }
 
 impl<SinkItem, T, I, E> Sink<PipelineMessage<SinkItem, I, E>> for PipelineSink<T, I, E>
 where
-    T: Sink<SinkItem, Error = E> + Stream<Item = Result<I, E>> + 'static,
+    T: Sink<SinkItem, Error = E> + Stream<Item = Result<I, E>>,
 {
-    type Error = ();
+    type Error = DriverExit;
 
     // Retrieve incoming messages and write them to the sink
     fn poll_ready(
@@ -185,6 +415,7 @@ where
             input,
             output,
             response_count,
+            request,
         }: PipelineMessage<SinkItem, I, E>,
     ) -> Result<(), Self::Error> {
         // If there is nothing to receive our output we do not need to send the message as it is
@@ -197,20 +428,28 @@ where
         let self_ = self.as_mut().project();
 
         if let Some(err) = self_.error.take() {
-            let _ = output.send(Err(err));
-            return Err(());
+            output.send_err(err);
+            return Err(DriverExit::WriteFailed);
         }
 
         match self_.sink_stream.start_send(input) {
+            // Nothing is read for the request, so complete it right away instead of queueing it,
+            // where it would take the response to the next request.
+            Ok(()) if response_count == 0 => {
+                if let PipelineOutput::Collect(sender) = output {
+                    let _ = sender.send(((self_.collect)(Vec::new(), 0), None));
+                }
+                Ok(())
+            }
             Ok(()) => {
                 self_
                     .in_flight
-                    .push_back(InFlight::new(output, response_count));
+                    .push_back(InFlight::new(output, response_count, request));
                 Ok(())
             }
             Err(err) => {
-                let _ = output.send(Err(err));
-                Err(())
+                output.send_err(err);
+                Err(DriverExit::WriteFailed)
             }
         }
     }
@@ -226,6 +465,7 @@ where
             .poll_flush(cx)
             .map_err(|err| {
                 self.as_mut().send_result(Err(err));
+                DriverExit::WriteFailed
             }))?;
         self.poll_read(cx)
     }
@@ -242,6 +482,7 @@ where
         let this = self.as_mut().project();
         this.sink_stream.poll_close(cx).map_err(|err| {
             self.send_result(Err(err));
+            DriverExit::WriteFailed
         })
     }
 }
@@ -252,10 +493,15 @@ where
     I: Send + 'static,
     E: Send + 'static,
 {
-    fn new<T>(sink_stream: T) -> (Self, impl Future<Output = ()>)
+    fn new<T>(
+        sink_stream: T,
+        collect: CollectResponses<I, E>,
+        budget: Arc<ResponseBudget>,
+        size: ResponseSize<I, E>,
+    ) -> (Self, impl Future<Output = DriverExit>)
     where
-        T: Sink<SinkItem, Error = E> + Stream<Item = Result<I, E>> + 'static,
-        T: Send + 'static,
+        T: Sink<SinkItem, Error = E> + Stream<Item = Result<I, E>>,
+        T: Send,
         T::Item: Send,
         T::Error: Send,
         T::Error: ::std::fmt::Debug,
@@ -264,19 +510,48 @@ where
         let (sender, mut receiver) = mpsc::channel(BUFFER_SIZE);
         let f = stream::poll_fn(move |cx| receiver.poll_recv(cx))
             .map(Ok)
-            .forward(PipelineSink::new::<SinkItem>(sink_stream))
-            .map(|_| ());
-        (Pipeline(sender), f)
+            .forward(PipelineSink::new::<SinkItem>(
+                sink_stream,
+                collect,
+                budget,
+                size,
+            ))
+            .map(|result| result.err().unwrap_or(DriverExit::Dropped));
+        let pending = PendingRequests(Arc::new(AtomicUsize::new(0)));
+        (Pipeline(sender, Arc::new(pending)), f)
     }
 
-    // `None` means that the stream was out of items causing that poll loop to shut down.
-    async fn send(&mut self, item: SinkItem) -> Result<I, Option<E>> {
-        self.send_recv_multiple(item, 1)
-            .await
-            // We can unwrap since we do a request for `1` item
-            .map(|mut item| item.pop().unwrap())
+    // The number of requests sent through any clone of the pipeline, but not completed yet.
+    fn pending(&self) -> usize {
+        (self.1).0.load(Ordering::Acquire)
     }
 
+    // Builds the message for a request, which counts as pending until it is completed.
+    fn message(
+        &self,
+        input: SinkItem,
+        response_count: usize,
+        output: PipelineOutput<I, E>,
+    ) -> PipelineMessage<SinkItem, I, E> {
+        PipelineMessage {
+            input,
+            output,
+            response_count,
+            request: PendingRequest::new(&self.1),
+        }
+    }
+
+    #[cfg(test)]
+    fn pending_requests(&self) -> usize {
+        self.1 .0.load(Ordering::Acquire)
+    }
+
+    // Whether the driver shut down, so that no more requests can be sent.
+    fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+
+    // `None` means that the stream was out of items causing that poll loop to shut down.
     async fn send_recv_multiple(
         &mut self,
         input: SinkItem,
@@ -284,16 +559,40 @@ where
     ) -> Result<Vec<I>, Option<E>> {
         let (sender, receiver) = oneshot::channel();
 
-        self.0
-            .send(PipelineMessage {
-                input,
-                response_count: count,
-                output: sender,
-            })
-            .await
-            .map_err(|_| None)?;
+        let message = self.message(input, count, PipelineOutput::Collect(sender));
+        self.0.send(message).await.map_err(|_| None)?;
+        Self::recv_multiple(receiver).await
+    }
+
+    // Like `send_recv_multiple`, but fails with `ErrorKind::Busy` instead of waiting if the
+    // request buffer is full.
+    async fn try_send_recv_multiple(
+        &mut self,
+        input: SinkItem,
+        count: usize,
+    ) -> Result<Vec<I>, Option<E>>
+    where
+        E: From<RedisError>,
+    {
+        let (sender, receiver) = oneshot::channel();
+
+        let message = self.message(input, count, PipelineOutput::Collect(sender));
+        self.0.try_send(message).map_err(|err| match err {
+            mpsc::error::TrySendError::Full(_) => Some(E::from(RedisError::from((
+                ErrorKind::Busy,
+                "The request buffer of the connection is full",
+            )))),
+            mpsc::error::TrySendError::Closed(_) => None,
+        })?;
+        Self::recv_multiple(receiver).await
+    }
+
+    async fn recv_multiple(
+        receiver: oneshot::Receiver<Delivery<Result<Vec<I>, E>>>,
+    ) -> Result<Vec<I>, Option<E>> {
         match receiver.await {
-            Ok(result) => result.map_err(Some),
+            // Dropping the permit gives its share of the budget back
+            Ok((result, _permit)) => result.map_err(Some),
             Err(_) => {
                 // The `sender` was dropped which likely means that the stream part
                 // failed for one reason or another
@@ -301,21 +600,208 @@ where
             }
         }
     }
+
+    // Like `send_recv_multiple`, but yields each of the `count` responses as soon as it arrives.
+    // A `None` error is yielded if the stream part shut down before all responses were received.
+    async fn send_recv_stream(
+        &mut self,
+        input: SinkItem,
+        count: usize,
+    ) -> Result<impl Stream<Item = Result<I, Option<E>>> + Unpin, Option<E>> {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+
+        let message = self.message(input, count, PipelineOutput::Stream(sender));
+        self.0.send(message).await.map_err(|_| None)?;
+        let mut remaining = count;
+        Ok(stream::poll_fn(move |cx| {
+            if remaining == 0 {
+                return Poll::Ready(None);
+            }
+            match ready!(receiver.poll_recv(cx)) {
+                Some((result, _permit)) => {
+                    remaining -= 1;
+                    Poll::Ready(Some(result.map_err(Some)))
+                }
+                // The `sender` was dropped before all responses arrived
+                None => {
+                    remaining = 0;
+                    Poll::Ready(Some(Err(None)))
+                }
+            }
+        }))
+    }
 }
 
 /// A connection object which can be cloned, allowing requests to be be sent concurrently
 /// on the same underlying connection (tcp/unix socket).
+///
+/// ## Ordering
+///
+/// All clones share a single request queue, and requests are written to the socket in the
+/// order in which they enter that queue.  A request enters the queue while its future is
+/// polled, not when the future is created, so:
+///
+/// - Requests sent one after another from the same clone, awaiting each, are executed in
+///   that order.
+/// - A request that is sent after the response to another request was received, from any
+///   clone, is executed after it.
+/// - Requests whose futures are polled concurrently, from the same clone or from different
+///   ones, may be executed in any order relative to each other.
+/// - The commands of a single pipeline are written together and are never interleaved with
+///   commands from other requests, so the server executes them back to back.  Note that
+///   without [`atomic`](crate::Pipeline::atomic) other connections to the server may still
+///   run commands in between.
+///
+/// To force an order between commands without waiting for a round trip in between, send them
+/// together in one pipeline:
+///
+/// ```rust,no_run
+/// # async fn do_something() -> redis::RedisResult<()> {
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// let mut con = client.get_multiplexed_async_connection().await?;
+/// let (count, members): (i64, Vec<String>) = redis::pipe()
+///     .sadd("members", "alice")
+///     .smembers("members")
+///     .query_async(&mut con)
+///     .await?;
+/// # Ok(()) }
+/// ```
+///
+/// ## Blocking commands
+///
+/// Responses are read in the order the requests were sent, so a blocking command like `BLPOP`
+/// holds up the responses to all requests sent after it, from any clone, until it returns.
+/// Send blocking commands on a separate connection instead, e.g. a dedicated
+/// `MultiplexedConnection` or a [`Connection`](crate::aio::Connection).  Commands like
+/// `SUBSCRIBE` and `MONITOR` are worse: they leave the connection receiving messages instead of
+/// responses, so they need a [`PubSub`](crate::aio::PubSub) or
+/// [`Monitor`](crate::aio::Monitor) connection.  With [`BlockingCommandPolicy::Reject`], sending
+/// any of these commands, alone or in a pipeline, fails instead, and with
+/// [`BlockingCommandPolicy::Warn`] a warning is logged.
 #[derive(Clone)]
 pub struct MultiplexedConnection {
     pipeline: Pipeline<Vec<u8>, Value, RedisError>,
     db: i64,
+    connection_info: Arc<RedisConnectionConfig>,
+    protocol: ProtocolVersion,
+    response_timeout: Option<Duration>,
+    // The error that shut the driver down, if any.
+    closed_reason: Arc<Mutex<Option<RedisError>>>,
+    // The client that created this connection, used to reconnect.
+    client: Option<Client>,
+    slow_command_hook: Option<Arc<SlowCommandHook>>,
+    id: u64,
+    created_at: Instant,
+    // The database this handle sends its requests to, if it differs from the connection's.
+    bound_db: Option<i64>,
+    // When a request last succeeded, shared by all clones.
+    last_used: Arc<Mutex<Instant>>,
+    response_budget: Arc<ResponseBudget>,
+    blocking_command_policy: BlockingCommandPolicy,
+    // The interval of pings on the idle connection, together with the sender which cancels the
+    // task sending them when it is replaced or dropped with the last clone.
+    idle_ping: Arc<Mutex<Option<IdlePing>>>,
+    // Request buffers, which the codec returns after writing them.
+    buffer_pool: Arc<BufferPool>,
+    // The id of the connection receiving invalidations and the options tracking was enabled
+    // with, if it was.
+    tracking: Arc<Mutex<Option<Tracking>>>,
+    // The names of the commands whose identical requests share a single response, in uppercase.
+    coalesced_commands: Arc<HashSet<String>>,
+    // The coalesced requests in flight, with the senders of the requests waiting for them.
+    coalesced_requests: Arc<Mutex<CoalescedRequests>>,
+    // The commands reported by the server, if `validate_commands` is set.
+    command_table: Option<Arc<CommandTable>>,
+}
+
+type IdlePing = (Duration, oneshot::Sender<()>);
+
+// The id of the next connection, counting all connections of the process.
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(0);
+
+type Tracking = (i64, TrackingOptions);
+
+// Coalesced requests are identified by the database and the packed command.
+type CoalescedRequests = HashMap<(i64, Vec<u8>), Vec<oneshot::Sender<RedisResult<Value>>>>;
+
+// A coalesced request in flight.  If it is dropped before it completes, the requests waiting for
+// it are failed, so that they send the command themselves.
+struct CoalescedRequest {
+    requests: Arc<Mutex<CoalescedRequests>>,
+    key: Option<(i64, Vec<u8>)>,
+}
+
+impl CoalescedRequest {
+    // Passes the result on to the requests waiting for this one.
+    fn complete(mut self, result: &RedisResult<Value>) {
+        let key = self.key.take().unwrap();
+        let waiting = self.requests.lock().unwrap().remove(&key);
+        for sender in waiting.into_iter().flatten() {
+            let result = match result {
+                Ok(value) => Ok(value.clone()),
+                Err(err) => Err(err.clone_mostly("Coalesced request failed")),
+            };
+            let _ = sender.send(result);
+        }
+    }
+}
+
+impl Drop for CoalescedRequest {
+    fn drop(&mut self) {
+        if let Some(ref key) = self.key {
+            self.requests.lock().unwrap().remove(key);
+        }
+    }
+}
+
+/// Describes whether a [`MultiplexedConnection`] sends blocking commands like `BLPOP`, which hold
+/// up the responses to all requests sent after them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BlockingCommandPolicy {
+    /// Send blocking commands like any other.  This is the default.
+    #[default]
+    Allow,
+    /// Send blocking commands, but log a warning for each of them through the `log` crate.
+    Warn,
+    /// Fail blocking commands with [`ErrorKind::InvalidClientConfig`] without sending them.
+    /// Pipelines containing a blocking command fail as a whole.
+    Reject,
+}
+
+type SlowCommandCallback = dyn Fn(&str, Duration) + Send + Sync;
+
+// A callback invoked for requests taking at least `threshold`.
+struct SlowCommandHook {
+    threshold: Duration,
+    callback: Box<SlowCommandCallback>,
+}
+
+impl SlowCommandHook {
+    // Calls the callback if the request started at `start` took at least the threshold.
+    fn check(&self, start: Instant, name: impl FnOnce() -> String) {
+        let elapsed = start.elapsed();
+        if elapsed >= self.threshold {
+            (self.callback)(&name(), elapsed);
+        }
+    }
 }
 
 impl Debug for MultiplexedConnection {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("MultiplexedConnection")
+            .field("id", &self.id)
             .field("pipeline", &self.pipeline)
+            .field("pending_requests", &self.pipeline.pending())
             .field("db", &self.db)
+            .field("bound_db", &self.bound_db)
+            .field("protocol", &self.protocol)
+            .field("response_timeout", &self.response_timeout)
+            .field("closed", &self.is_closed())
+            .field(
+                "slow_command_threshold",
+                &self.slow_command_hook.as_ref().map(|hook| hook.threshold),
+            )
             .finish()
     }
 }
@@ -323,81 +809,986 @@ impl Debug for MultiplexedConnection {
 impl MultiplexedConnection {
     /// Constructs a new `MultiplexedConnection` out of a `AsyncRead + AsyncWrite` object
     /// and a `ConnectionInfo`
-    pub async fn new<C>(
+    ///
+    /// The returned future drives the connection and has to be polled, e.g. by spawning it, for
+    /// requests to make progress.  The stream doesn't have to be `'static`: the driver may
+    /// borrow it and be polled within a scope, e.g. joined with the code using the connection.
+    /// Once the driver is dropped, all requests fail.  With the `debug-logging` feature, a
+    /// warning is logged if the last clone of the connection is dropped while requests are still
+    /// pending, which helps finding drivers that stopped being polled too early.
+    pub async fn new<'a, C>(
         connection_info: &RedisConnectionInfo,
         stream: C,
-    ) -> RedisResult<(Self, impl Future<Output = ()>)>
+    ) -> RedisResult<(Self, impl Future<Output = ()> + 'a)>
     where
-        C: Unpin + AsyncRead + AsyncWrite + Send + 'static,
+        C: Unpin + AsyncRead + AsyncWrite + Send + 'a,
     {
-        fn boxed(
-            f: impl Future<Output = ()> + Send + 'static,
-        ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        MultiplexedConnection::with_config(&connection_info.clone().into(), stream).await
+    }
+
+    pub(crate) async fn with_config<'a, C>(
+        connection_info: &RedisConnectionConfig,
+        stream: C,
+    ) -> RedisResult<(Self, impl Future<Output = ()> + 'a)>
+    where
+        C: Unpin + AsyncRead + AsyncWrite + Send + 'a,
+    {
+        fn boxed<'a>(
+            f: impl Future<Output = ()> + Send + 'a,
+        ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
             Box::pin(f)
         }
 
         #[cfg(all(not(feature = "tokio-comp"), not(feature = "async-std-comp")))]
         compile_error!("tokio-comp or async-std-comp features required for aio feature");
 
-        let codec = ValueCodec::default()
+        let closed_reason = Arc::new(Mutex::new(None));
+        let buffer_pool = Arc::new(BufferPool::default());
+        let codec = ValueCodec::default().buffer_pool(buffer_pool.clone());
+        #[cfg(feature = "debug-logging")]
+        let codec = codec.log_commands(connection_info.log_commands);
+        let codec = codec
             .framed(stream)
-            .and_then(|msg| async move { msg });
-        let (pipeline, driver) = Pipeline::new(codec);
-        let driver = boxed(driver);
+            .and_then(|msg| async move { msg })
+            .inspect_err({
+                let closed_reason = closed_reason.clone();
+                move |err| {
+                    if err.is_unrecoverable_error() {
+                        *closed_reason.lock().unwrap() =
+                            Some(err.clone_mostly("The connection was closed"));
+                    }
+                }
+            });
+        // Pushes, e.g. client tracking invalidations, don't answer a request, so they are dropped
+        // instead of being taken for the response to the oldest request in flight.
+        #[cfg(feature = "resp3")]
+        let codec = codec.try_filter(|value| std::future::ready(!matches!(value, Value::Push(_))));
+        let response_budget = Arc::new(ResponseBudget::default());
+        let (pipeline, driver) = Pipeline::new(
+            codec,
+            collect_responses,
+            response_budget.clone(),
+            response_size,
+        );
+        let id = NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
+        let driver = boxed(driver.map(move |exit| exit.log(id)));
+        let now = Instant::now();
         let mut con = MultiplexedConnection {
             pipeline,
             db: connection_info.db,
+            connection_info: Arc::new(connection_info.clone()),
+            protocol: ProtocolVersion::RESP2,
+            response_timeout: None,
+            closed_reason,
+            client: None,
+            slow_command_hook: None,
+            id,
+            created_at: now,
+            bound_db: None,
+            last_used: Arc::new(Mutex::new(now)),
+            response_budget,
+            blocking_command_policy: BlockingCommandPolicy::default(),
+            idle_ping: Arc::default(),
+            buffer_pool,
+            tracking: Arc::default(),
+            coalesced_commands: Arc::default(),
+            coalesced_requests: Arc::default(),
+            command_table: None,
         };
-        let driver = {
-            let auth = setup_connection(connection_info, &mut con);
+        let ((protocol, command_table), driver) = {
+            let auth = async {
+                let protocol = setup_connection(connection_info, &mut con).await?;
+                let mut command_table = None;
+                if cfg!(debug_assertions) && connection_info.validate_commands {
+                    let reply = command_table_cmd().query_async(&mut con).await;
+                    command_table = load_command_table(reply)?.map(Arc::new);
+                }
+                RedisResult::Ok((protocol, command_table))
+            };
             futures_util::pin_mut!(auth);
 
             match futures_util::future::select(auth, driver).await {
-                futures_util::future::Either::Left((result, driver)) => {
-                    result?;
-                    driver
-                }
+                futures_util::future::Either::Left((result, driver)) => (result?, driver),
                 futures_util::future::Either::Right(((), _)) => {
                     unreachable!("Multiplexed connection driver unexpectedly terminated")
                 }
             }
         };
+        con.protocol = protocol;
+        con.command_table = command_table;
+        #[cfg(feature = "debug-logging")]
+        log::debug!("Multiplexed connection {id} established");
         Ok((con, driver))
     }
 
+    /// Sets the time to wait for the response to a request, or `None` to wait indefinitely.
+    ///
+    /// A request that times out fails with a `TimedOut` I/O error; its response is discarded
+    /// once it arrives, so the connection stays usable.  The timeout applies to each clone of
+    /// the connection separately.
+    pub fn set_response_timeout(&mut self, timeout: Option<Duration>) {
+        self.response_timeout = timeout;
+    }
+
+    /// Sets whether blocking commands like `BLPOP`, `XREAD BLOCK` or `SUBSCRIBE` are sent, and
+    /// whether a warning is logged for them.  The policy applies
+    /// to each clone of the connection separately.
+    ///
+    /// See [Blocking commands](Self#blocking-commands) for why they are best sent on a separate
+    /// connection.
+    pub fn set_blocking_command_policy(&mut self, policy: BlockingCommandPolicy) {
+        self.blocking_command_policy = policy;
+    }
+
+    // Fails or warns about blocking commands, as configured by the policy.
+    fn check_blocking_command(&self, cmd: &Cmd) -> RedisResult<()> {
+        if self.blocking_command_policy == BlockingCommandPolicy::Allow || !is_blocking_cmd(cmd) {
+            return Ok(());
+        }
+        let detail = format!(
+            "{} would hold up all other requests on the connection",
+            command_name(cmd)
+        );
+        if self.blocking_command_policy == BlockingCommandPolicy::Warn {
+            log::warn!(
+                "Blocking command on multiplexed connection {}: {detail}",
+                self.id
+            );
+            return Ok(());
+        }
+        fail!((
+            ErrorKind::InvalidClientConfig,
+            "Blocking command on a multiplexed connection",
+            detail
+        ));
+    }
+
+    // Checks each command of a pipeline with `check_blocking_command`.
+    fn check_blocking_commands(&self, pipeline: &crate::Pipeline) -> RedisResult<()> {
+        pipeline
+            .cmd_iter()
+            .try_for_each(|cmd| self.check_blocking_command(cmd))
+    }
+
+    /// Sets the commands whose identical requests are coalesced, e.g. `["GET"]`, or none with an
+    /// empty list, which is the default.
+    ///
+    /// While a request for one of these commands is in flight, an identical request for the
+    /// same database, sent on this connection or one of its clones, is not sent but receives the
+    /// response to the request in flight.  This takes load off the server and the network for
+    /// read-hot keys, but is only correct for commands without side effects, as the response
+    /// may have been produced before the request was made.  Coalesced requests wait for the
+    /// response without a timeout of their own.  Only
+    /// [`send_packed_command`](Self::send_packed_command) coalesces requests, not pipelines.
+    /// The commands apply to each clone of the connection separately.
+    pub fn set_coalesced_commands<I, S>(&mut self, commands: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.coalesced_commands = Arc::new(
+            commands
+                .into_iter()
+                .map(|name| name.as_ref().to_ascii_uppercase())
+                .collect(),
+        );
+    }
+
+    // Sends the command, or waits for the response to an identical request in flight.
+    async fn send_coalesced_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        let key = (self.db, cmd.get_packed_command());
+        let waiting = {
+            let mut requests = self.coalesced_requests.lock().unwrap();
+            match requests.get_mut(&key) {
+                Some(senders) => {
+                    let (sender, receiver) = oneshot::channel();
+                    senders.push(sender);
+                    Some(receiver)
+                }
+                None => {
+                    requests.insert(key.clone(), Vec::new());
+                    None
+                }
+            }
+        };
+        if let Some(receiver) = waiting {
+            // The request in flight was dropped before it completed, so send the command anyway
+            return match receiver.await {
+                Ok(result) => result,
+                Err(_) => self.send_single_command(cmd).await,
+            };
+        }
+        let request = CoalescedRequest {
+            requests: self.coalesced_requests.clone(),
+            key: Some(key),
+        };
+        let result = self.send_single_command(cmd).await;
+        request.complete(&result);
+        result
+    }
+
+    /// Limits the total size of the responses which were read, but not yet received by the
+    /// requests they belong to, or removes the limit with `None`, which is the default.
+    ///
+    /// Once responses waiting to be received take more than `bytes`, e.g. because the tasks
+    /// awaiting them or consuming a [`send_packed_commands_stream`](Self::send_packed_commands_stream)
+    /// are slow, no further responses are read until some of them are received.  This bounds
+    /// the memory used for buffered responses, while the server sees the backpressure of a slow
+    /// reader.  The size of a response is estimated from its data, and a single response or the
+    /// responses to one pipeline may exceed the limit on their own.  The limit applies to the
+    /// connection, and so to all of its clones.
+    pub fn set_response_memory_budget(&mut self, bytes: Option<usize>) {
+        self.response_budget
+            .limit
+            .store(bytes.unwrap_or(usize::MAX), Ordering::Relaxed);
+        self.response_budget.waker.wake();
+    }
+
+    /// Sets a callback invoked with the command name and the elapsed time whenever the response
+    /// to a request takes at least `threshold` to arrive, e.g. to log slow commands.
+    ///
+    /// For pipelines the names of all commands are passed, separated by commas.  Requests that
+    /// fail, e.g. because of the response timeout, are reported as well.  The callback applies
+    /// to each clone of the connection separately and is called on the task awaiting the
+    /// response, so it should return quickly.
+    pub fn set_slow_command_hook<F>(&mut self, threshold: Duration, callback: F)
+    where
+        F: Fn(&str, Duration) + Send + Sync + 'static,
+    {
+        self.slow_command_hook = Some(Arc::new(SlowCommandHook {
+            threshold,
+            callback: Box::new(callback),
+        }));
+    }
+
+    /// Removes the callback set by [`set_slow_command_hook`](Self::set_slow_command_hook).
+    pub fn clear_slow_command_hook(&mut self) {
+        self.slow_command_hook = None;
+    }
+
+    // Invokes the slow command hook if the request started at `start` took long enough.
+    fn check_slow_command(&self, start: Instant, name: impl FnOnce() -> String) {
+        if let Some(ref hook) = self.slow_command_hook {
+            hook.check(start, name);
+        }
+    }
+
+    /// Returns the id of the connection, which is unique within the process and increases with
+    /// each new connection.
+    ///
+    /// The id is included in the messages logged with the `debug-logging` feature, so that they
+    /// can be correlated with the connection serving a request, e.g. across the reconnections of
+    /// a [`ConnectionManager`](crate::aio::ConnectionManager).  [`reconnect`](Self::reconnect)
+    /// replaces it with the id of the new connection.
+    pub fn connection_id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns when the connection was established.
+    ///
+    /// Pools can use this to close connections after a maximum lifetime, e.g. to pick up DNS
+    /// changes.  [`reconnect`](Self::reconnect) replaces it with the time of the new connection.
+    pub fn created_at(&self) -> Instant {
+        self.created_at
+    }
+
+    /// Returns when a request on this connection or one of its clones last succeeded, or when
+    /// the connection was established if no request succeeded yet.
+    pub fn last_used(&self) -> Instant {
+        *self.last_used.lock().unwrap()
+    }
+
+    // Records the time of a successful request.
+    fn mark_used<T>(&self, result: &RedisResult<T>) {
+        if result.is_ok() {
+            *self.last_used.lock().unwrap() = Instant::now();
+        }
+    }
+
+    /// Sends a `PING` whenever no request succeeded on the connection or any of its clones for
+    /// `interval`, or stops doing so with `None`, which is the default.
+    ///
+    /// This keeps idle connections from being closed by the idle timeouts of load balancers or
+    /// of the server, and detects connections which were dropped while idle: if the `PING`
+    /// fails because the connection is gone, [`is_closed`](Self::is_closed) returns true and
+    /// further requests fail right away, instead of hanging on a dead connection.  The pings
+    /// don't count as use for [`last_used`](Self::last_used).  The interval applies to the
+    /// connection, and so to all of its clones; the pings stop once the last clone is dropped.
+    pub fn set_idle_ping_interval(&mut self, interval: Option<Duration>) {
+        let (cancel, cancelled) = oneshot::channel();
+        // Replacing the sender cancels the task started before, if any.
+        *self.idle_ping.lock().unwrap() = interval.map(|interval| (interval, cancel));
+        if let Some(interval) = interval {
+            let ping = ping_when_idle(
+                interval,
+                self.pipeline.clone(),
+                self.last_used.clone(),
+                self.closed_reason.clone(),
+                self.response_timeout,
+            );
+            Runtime::locate().spawn(async move {
+                futures_util::pin_mut!(ping);
+                futures_util::future::select(ping, cancelled).await;
+            });
+        }
+    }
+
+    /// Returns true if the connection was closed, e.g. because the server went away or sent a
+    /// response that could not be parsed.
+    ///
+    /// All further requests fail, with an error describing why the connection was closed if it
+    /// is known.  Use [`reconnect`](Self::reconnect) to open a new connection.
+    pub fn is_closed(&self) -> bool {
+        self.pipeline.is_closed()
+    }
+
+    /// Enables server assisted client side caching with `CLIENT TRACKING`, returning a [`Stream`]
+    /// of the invalidations of the keys tracked for this connection.
+    ///
+    /// The invalidations are redirected to a new pubsub connection, which is opened with the same
+    /// connection info and owned by the returned stream, so this only works for connections
+    /// created by a [`Client`].  Tracking applies to the connection, and so to all of its clones.
+    /// [`reconnect`](Self::reconnect) enables it again on the new connection, as long as the
+    /// stream is still connected.  Dropping the stream closes its connection, after which the
+    /// server stops tracking keys for this connection.
+    pub async fn enable_tracking(
+        &mut self,
+        options: TrackingOptions,
+    ) -> RedisResult<impl Stream<Item = Invalidation> + Send + 'static> {
+        let client = match self.client {
+            Some(ref client) => client.clone(),
+            None => fail!((
+                ErrorKind::InvalidClientConfig,
+                "Only connections created by a Client can enable tracking"
+            )),
+        };
+        let mut connection = client.get_async_connection().await?;
+        let id = cmd("CLIENT").arg("ID").query_async(&mut connection).await?;
+        let mut pubsub = connection.into_pubsub();
+        pubsub.subscribe(INVALIDATION_CHANNEL).await?;
+        self.start_tracking((id, options)).await?;
+        Ok(pubsub
+            .into_on_message()
+            .filter_map(|msg| std::future::ready(msg.invalidation())))
+    }
+
+    // Sends `CLIENT TRACKING` redirecting the invalidations to the connection with the given id.
+    async fn start_tracking(&mut self, tracking: Tracking) -> RedisResult<()> {
+        let (id, ref options) = tracking;
+        cmd("CLIENT")
+            .arg("TRACKING")
+            .arg("ON")
+            .arg("REDIRECT")
+            .arg(id)
+            .arg(options)
+            .query_async::<_, ()>(self)
+            .await?;
+        *self.tracking.lock().unwrap() = Some(tracking);
+        Ok(())
+    }
+
+    /// Replaces this handle's connection with a new one, created with the same connection info.
+    ///
+    /// This only works for connections created by a [`Client`].  Other clones of this
+    /// `MultiplexedConnection` keep using the old connection; clone this handle again to share
+    /// the new one.  For automatic reconnection see
+    /// [`ConnectionManager`](crate::aio::ConnectionManager).
+    ///
+    /// If [tracking](Self::enable_tracking) was enabled but can't be enabled again, e.g. because
+    /// the connection receiving the invalidations was closed as well, the new connection is used
+    /// without tracking and the error is returned.
+    pub async fn reconnect(&mut self) -> RedisResult<()> {
+        let client = match self.client {
+            Some(ref client) => client.clone(),
+            None => fail!((
+                ErrorKind::InvalidClientConfig,
+                "Only connections created by a Client can reconnect"
+            )),
+        };
+        let (mut connection, driver) = client.create_multiplexed_async_connection().await?;
+        Runtime::locate().spawn(driver);
+        connection.response_timeout = self.response_timeout;
+        connection.slow_command_hook = self.slow_command_hook.clone();
+        connection.db = self.db;
+        connection.bound_db = self.bound_db;
+        connection.blocking_command_policy = self.blocking_command_policy;
+        connection.coalesced_commands = self.coalesced_commands.clone();
+        let idle_ping_interval = self.idle_ping.lock().unwrap().as_ref().map(|ping| ping.0);
+        connection.set_idle_ping_interval(idle_ping_interval);
+        let tracking = self.tracking.lock().unwrap().clone();
+        *self = connection;
+        match tracking {
+            Some(tracking) => self.start_tracking(tracking).await,
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn set_client(&mut self, client: Client) {
+        self.client = Some(client);
+    }
+
+    /// Returns the protocol version negotiated with the server while setting up the connection.
+    ///
+    /// This may be [`ProtocolVersion::RESP2`] even if RESP3 was requested, when the server
+    /// doesn't support it.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol
+    }
+
+    /// Resets the state of the connection with `RESET`, then authenticates, negotiates the
+    /// protocol and selects the database again as configured when the connection was opened.
+    ///
+    /// All clones of a `MultiplexedConnection` share the same underlying connection, so this
+    /// resets the state for all of them.  `RESET` and the setup are sent as a single request, so
+    /// that requests sent on other clones in the meantime are not sent to a connection that is
+    /// not set up.  Only if that fails, e.g. on servers before Redis 6.2 which don't know
+    /// `RESET`, the commands are sent one at a time, and requests of other clones may end up in
+    /// between.  For those servers only the configured database is selected again.
+    pub async fn reset(&mut self) -> RedisResult<()> {
+        let connection_info = self.connection_info.clone();
+        self.protocol = reset_connection_at_once(&connection_info, self.protocol, self).await?;
+        Ok(())
+    }
+
+    /// Returns a guard which buffers the commands sent through it, writing them together when it
+    /// is flushed or dropped.
+    ///
+    /// Unlike a [`Pipeline`](crate::Pipeline), each command still gets its own response future,
+    /// so commands can be added one by one as they are produced.  This saves writes for bursty
+    /// producers.  Commands are only sent by [`PipelineGuard::flush`]; the ones still buffered
+    /// when the guard is dropped are discarded.
+    ///
+    /// ```rust,no_run
+    /// # async fn do_something() -> redis::RedisResult<()> {
+    /// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+    /// let con = client.get_multiplexed_async_connection().await?;
+    /// let mut guard = con.pipeline_guard();
+    /// let set = guard.send_packed_command(redis::cmd("SET").arg("key").arg(1));
+    /// let incr = guard.send_packed_command(redis::cmd("INCR").arg("key"));
+    /// guard.flush().await?;
+    /// set.await?;
+    /// let value: i64 = redis::from_redis_value(&incr.await?)?;
+    /// # Ok(()) }
+    /// ```
+    pub fn pipeline_guard(&self) -> PipelineGuard {
+        PipelineGuard {
+            con: self.clone(),
+            buffer: Vec::new(),
+            senders: VecDeque::new(),
+            flushed_at: Arc::default(),
+        }
+    }
+
+    /// Returns a clone of this connection which sends its requests to database `db`.
+    ///
+    /// All clones share one connection, on which `SELECT` would switch the database for every
+    /// one of them.  Instead, the returned clone wraps each request into `SELECT db` and a
+    /// `SELECT` back to the database of the connection, written together with the request so
+    /// that no request of another clone runs in between.  This doesn't add a round trip, but
+    /// the server executes two more commands per request, so a separate connection is cheaper
+    /// for heavy use of another database.
+    ///
+    /// Requests sent with [`send_raw`](Self::send_raw) or a [`PipelineGuard`] are not wrapped.
+    ///
+    /// A failing `SELECT` doesn't keep the server from executing the wrapped request on the
+    /// database of the connection, so this selects `db` once before returning, and fails if the
+    /// database doesn't exist or the user may not select it.  Revoking the permission afterwards
+    /// makes requests of the returned clone fail with the error of `SELECT`, but they still run.
+    pub async fn with_db(&self, db: i64) -> RedisResult<Self> {
+        let mut con = self.clone();
+        con.db = db;
+        con.bound_db = (db != self.connection_db()).then_some(db);
+        if con.bound_db.is_some() {
+            con.request(|_| {}, 0, false).await?;
+        }
+        Ok(con)
+    }
+
+    // The database selected on the connection while setting it up.
+    fn connection_db(&self) -> i64 {
+        if self.connection_info.should_select() {
+            self.connection_info.db
+        } else {
+            0
+        }
+    }
+
+    // Checks the commands of a request, if validating them is enabled.
+    fn validate_request(&self, request: &[u8]) -> RedisResult<()> {
+        match self.command_table {
+            Some(ref table) => table.validate_packed(request),
+            None => Ok(()),
+        }
+    }
+
+    // Packs commands into a pooled buffer, wrapped into `SELECT`s to the bound database and back,
+    // if any, and validates them.  Returns the request and the number of responses to skip before
+    // and after the wrapped ones.
+    fn pack_request(&self, write: impl FnOnce(&mut Vec<u8>)) -> RedisResult<(Vec<u8>, usize)> {
+        let mut request = self.buffer_pool.take();
+        let packed = match self.bound_db {
+            Some(db) => {
+                cmd("SELECT").arg(db).write_packed_command(&mut request);
+                write(&mut request);
+                cmd("SELECT")
+                    .arg(self.connection_db())
+                    .write_packed_command(&mut request);
+                (request, 1)
+            }
+            None => {
+                write(&mut request);
+                (request, 0)
+            }
+        };
+        self.validate_request(&packed.0)?;
+        Ok(packed)
+    }
+
+    // Sends packed commands and collects `count` responses, scoped to the bound database.  With
+    // `try_send`, fails with `ErrorKind::Busy` instead of waiting if the request buffer is full.
+    async fn request(
+        &mut self,
+        write: impl FnOnce(&mut Vec<u8>),
+        count: usize,
+        try_send: bool,
+    ) -> RedisResult<Vec<Value>> {
+        let (input, skip) = self.pack_request(write)?;
+        let count = count + 2 * skip;
+        let result = if try_send {
+            let response = self.pipeline.try_send_recv_multiple(input, count);
+            await_response(self.response_timeout, &self.closed_reason, response).await
+        } else {
+            let response = self.pipeline.send_recv_multiple(input, count);
+            await_response(self.response_timeout, &self.closed_reason, response).await
+        };
+        let mut values = result.map_err(|err| err.skip_partial_responses(skip))?;
+        values.truncate(values.len() - skip);
+        values.drain(..skip);
+        Ok(values)
+    }
+
     /// Sends an already encoded (packed) command into the TCP socket and
     /// reads the single response from it.
     pub async fn send_packed_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
-        let value = self
-            .pipeline
-            .send(cmd.get_packed_command())
-            .await
-            .map_err(|err| {
-                err.unwrap_or_else(|| RedisError::from(io::Error::from(io::ErrorKind::BrokenPipe)))
-            })?;
-        Ok(value)
+        if !self.coalesced_commands.is_empty()
+            && self
+                .coalesced_commands
+                .contains(&command_name(cmd).to_ascii_uppercase())
+        {
+            return self.send_coalesced_command(cmd).await;
+        }
+        self.send_single_command(cmd).await
+    }
+
+    async fn send_single_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        self.check_blocking_command(cmd)?;
+        let start = Instant::now();
+        let result = self
+            .request(|buf| cmd.write_packed_command(buf), 1, false)
+            .await;
+        self.check_slow_command(start, || command_name(cmd));
+        self.mark_used(&result);
+        // We can unwrap since we do a request for `1` item
+        result.map(|mut value| value.pop().unwrap())
     }
 
     /// Sends multiple already encoded (packed) command into the TCP socket
     /// and reads `count` responses from it.  This is used to implement
     /// pipelining.
+    ///
+    /// All responses are collected before they are returned.  For large pipelines,
+    /// [`send_packed_commands_stream`](Self::send_packed_commands_stream) yields each response
+    /// as it arrives instead, so they can be processed without holding all of them in memory.
     pub async fn send_packed_commands(
         &mut self,
         cmd: &crate::Pipeline,
         offset: usize,
         count: usize,
     ) -> RedisResult<Vec<Value>> {
-        let mut value = self
-            .pipeline
-            .send_recv_multiple(cmd.get_packed_pipeline(), offset + count)
-            .await
-            .map_err(|err| {
-                err.unwrap_or_else(|| RedisError::from(io::Error::from(io::ErrorKind::BrokenPipe)))
-            })?;
+        self.check_blocking_commands(cmd)?;
+        let start = Instant::now();
+        let result = self
+            .request(|buf| cmd.write_packed_pipeline(buf), offset + count, false)
+            .await;
+        self.check_slow_command(start, || pipeline_command_names(cmd));
+        self.mark_used(&result);
+        let mut value = result.map_err(|err| err.skip_partial_responses(offset))?;
+
+        value.drain(..offset);
+        Ok(value)
+    }
+
+    /// Writes pre-encoded bytes into the TCP socket as they are and reads `count` responses
+    /// from it.
+    ///
+    /// If `count` doesn't match the number of commands in the bytes, all following requests on
+    /// this connection and its clones receive the wrong responses.
+    pub async fn send_raw(&mut self, bytes: &[u8], count: usize) -> RedisResult<Vec<Value>> {
+        self.validate_request(bytes)?;
+        let mut input = self.buffer_pool.take();
+        input.extend_from_slice(bytes);
+        let response = self.pipeline.send_recv_multiple(input, count);
+        let result = await_response(self.response_timeout, &self.closed_reason, response).await;
+        self.mark_used(&result);
+        result
+    }
+
+    /// Like [`send_packed_command`](Self::send_packed_command), but fails immediately with
+    /// [`ErrorKind::Busy`] instead of waiting if too many requests are already queued on the
+    /// connection.
+    ///
+    /// This allows shedding load instead of waiting for the connection to catch up.
+    pub async fn try_send_packed_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        self.check_blocking_command(cmd)?;
+        let start = Instant::now();
+        let result = self
+            .request(|buf| cmd.write_packed_command(buf), 1, true)
+            .await;
+        self.check_slow_command(start, || command_name(cmd));
+        self.mark_used(&result);
+        let mut value = result?;
+        // We can unwrap since we do a request for `1` item
+        Ok(value.pop().unwrap())
+    }
+
+    /// Like [`send_packed_commands`](Self::send_packed_commands), but fails immediately with
+    /// [`ErrorKind::Busy`] instead of waiting if too many requests are already queued on the
+    /// connection.
+    pub async fn try_send_packed_commands(
+        &mut self,
+        cmd: &crate::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.check_blocking_commands(cmd)?;
+        let start = Instant::now();
+        let result = self
+            .request(|buf| cmd.write_packed_pipeline(buf), offset + count, true)
+            .await;
+        self.check_slow_command(start, || pipeline_command_names(cmd));
+        self.mark_used(&result);
+        let mut value = result.map_err(|err| err.skip_partial_responses(offset))?;
 
         value.drain(..offset);
         Ok(value)
     }
+
+    /// Like [`send_packed_command`](Self::send_packed_command), but fails with
+    /// [`ErrorKind::Cancelled`] as soon as `token` is cancelled.
+    ///
+    /// A request that was not written to the connection yet is never sent.  If it was already
+    /// sent, its response is still read and discarded once it arrives, so that the following
+    /// requests receive the right responses.
+    pub async fn send_packed_command_cancellable(
+        &mut self,
+        cmd: &Cmd,
+        token: &CancellationToken,
+    ) -> RedisResult<Value> {
+        cancellable(token, self.send_packed_command(cmd)).await
+    }
+
+    /// Like [`send_packed_commands`](Self::send_packed_commands), but fails with
+    /// [`ErrorKind::Cancelled`] as soon as `token` is cancelled.
+    ///
+    /// As with [`send_packed_command_cancellable`](Self::send_packed_command_cancellable), the
+    /// responses to a pipeline that was already sent are still read and discarded.
+    pub async fn send_packed_commands_cancellable(
+        &mut self,
+        cmd: &crate::Pipeline,
+        offset: usize,
+        count: usize,
+        token: &CancellationToken,
+    ) -> RedisResult<Vec<Value>> {
+        cancellable(token, self.send_packed_commands(cmd, offset, count)).await
+    }
+
+    /// Sends multiple already encoded (packed) command into the TCP socket
+    /// and returns a stream of the `count` responses following the first `offset` ones.
+    ///
+    /// Unlike [`send_packed_commands`](Self::send_packed_commands), each response is yielded as
+    /// soon as it is received, so early results can be processed while later ones are still in
+    /// flight. Errors in the skipped `offset` responses are yielded as well.
+    pub async fn send_packed_commands_stream(
+        &mut self,
+        cmd: &crate::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<impl Stream<Item = RedisResult<Value>> + Send + Unpin + 'static> {
+        self.check_blocking_commands(cmd)?;
+        let closed_reason = self.closed_reason.clone();
+        let to_redis_error =
+            move |err: Option<RedisError>| err.unwrap_or_else(|| closed_error(&closed_reason));
+        let (input, skip) = self.pack_request(|buf| cmd.write_packed_pipeline(buf))?;
+        let responses = self
+            .pipeline
+            .send_recv_stream(input, offset + count + 2 * skip)
+            .await
+            .map_err(&to_redis_error)?;
+        let offset = offset + skip;
+        Ok(responses.enumerate().filter_map(move |(index, result)| {
+            let result = match result {
+                Ok(_) if index < offset || index >= offset + count => None,
+                Ok(value) => Some(Ok(value)),
+                Err(err) => Some(Err(to_redis_error(err))),
+            };
+            std::future::ready(result)
+        }))
+    }
+}
+
+/// Buffers commands sent on a [`MultiplexedConnection`] until it is flushed.
+///
+/// Created by [`MultiplexedConnection::pipeline_guard`].  The commands are checked like the
+/// ones sent with [`MultiplexedConnection::send_packed_command`], and written to the database
+/// the connection is bound to, if any.  Dropping the guard discards the commands that were not
+/// flushed, failing their responses with [`ErrorKind::Cancelled`].
+pub struct PipelineGuard {
+    con: MultiplexedConnection,
+    buffer: Vec<u8>,
+    senders: VecDeque<oneshot::Sender<Delivery<RedisResult<Value>>>>,
+    // When the buffered commands were flushed, for the slow command hook.
+    flushed_at: Arc<Mutex<Option<Instant>>>,
+}
+
+impl PipelineGuard {
+    /// Buffers an already encoded (packed) command, returning a future which resolves to its
+    /// response.
+    ///
+    /// The command is only written once the guard is flushed, so awaiting the returned future
+    /// before that never completes.
+    pub fn send_packed_command(
+        &mut self,
+        cmd: &Cmd,
+    ) -> impl Future<Output = RedisResult<Value>> + Send + 'static {
+        let (sender, receiver) = oneshot::channel();
+        let start = self.buffer.len();
+        let invalid = self
+            .con
+            .check_blocking_command(cmd)
+            .and_then(|()| {
+                cmd.write_packed_command(&mut self.buffer);
+                self.con.validate_request(&self.buffer[start..])
+            })
+            .err();
+        match invalid {
+            Some(_) => self.buffer.truncate(start),
+            None => self.senders.push_back(sender),
+        }
+
+        let response_timeout = self.con.response_timeout;
+        let closed_reason = self.con.closed_reason.clone();
+        let last_used = self.con.last_used.clone();
+        let slow_command_hook = self.con.slow_command_hook.clone();
+        let flushed_at = self.flushed_at.clone();
+        let name = slow_command_hook.as_ref().map(|_| command_name(cmd));
+        async move {
+            if let Some(err) = invalid {
+                return Err(err);
+            }
+            let response = receiver.map(|result| match result {
+                Ok((result, _permit)) => result.map_err(Some),
+                // The sender was dropped because the connection was closed
+                Err(_) => Err(None),
+            });
+            let result = await_response(response_timeout, &closed_reason, response).await;
+            if result.is_ok() {
+                *last_used.lock().unwrap() = Instant::now();
+            }
+            let flushed_at = *flushed_at.lock().unwrap();
+            if let (Some(hook), Some(name), Some(start)) = (slow_command_hook, name, flushed_at) {
+                hook.check(start, || name);
+            }
+            result
+        }
+    }
+
+    /// Writes all buffered commands to the connection.
+    pub async fn flush(&mut self) -> RedisResult<()> {
+        if self.senders.is_empty() {
+            return Ok(());
+        }
+        let buffer = std::mem::take(&mut self.buffer);
+        let (input, skip) = self
+            .con
+            .pack_request(|request| request.extend_from_slice(&buffer))?;
+        let mut senders = std::mem::take(&mut self.senders);
+        // Nobody waits for the responses to the `SELECT`s around the commands.
+        for _ in 0..skip {
+            senders.push_front(oneshot::channel().0);
+            senders.push_back(oneshot::channel().0);
+        }
+        *self.flushed_at.lock().unwrap() = Some(Instant::now());
+        self.flushed_at = Arc::default();
+        let message =
+            self.con
+                .pipeline
+                .message(input, senders.len(), PipelineOutput::Each(senders));
+        if self.con.pipeline.0.send(message).await.is_err() {
+            return Err(closed_error(&self.con.closed_reason));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PipelineGuard {
+    fn drop(&mut self) {
+        if self.senders.is_empty() {
+            return;
+        }
+        log::warn!(
+            "Discarding {} commands of a pipeline guard on multiplexed connection {}, which was \
+             dropped without flushing them",
+            self.senders.len(),
+            self.con.id
+        );
+        for sender in self.senders.drain(..) {
+            let err = RedisError::from((
+                ErrorKind::Cancelled,
+                "The pipeline guard was dropped without flushing the command",
+            ));
+            let _ = sender.send((Err(err), None));
+        }
+    }
+}
+
+impl Debug for PipelineGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PipelineGuard")
+            .field("con", &self.con)
+            .field("buffered", &self.senders.len())
+            .finish()
+    }
+}
+
+// Waits for the response to a request, failing if `response_timeout` elapses first.
+async fn await_response<T>(
+    response_timeout: Option<Duration>,
+    closed_reason: &Mutex<Option<RedisError>>,
+    response: impl Future<Output = Result<T, Option<RedisError>>>,
+) -> RedisResult<T> {
+    match response_timeout {
+        Some(timeout) => Runtime::locate().timeout(timeout, response).await?,
+        None => response.await,
+    }
+    .map_err(|err| err.unwrap_or_else(|| closed_error(closed_reason)))
+}
+
+// Sends `PING` whenever neither a request succeeded nor a `PING` was sent for `interval`, until
+// the connection is closed.
+async fn ping_when_idle(
+    interval: Duration,
+    mut pipeline: Pipeline<Vec<u8>, Value, RedisError>,
+    last_used: Arc<Mutex<Instant>>,
+    closed_reason: Arc<Mutex<Option<RedisError>>>,
+    response_timeout: Option<Duration>,
+) {
+    let mut last_ping = Instant::now();
+    while !pipeline.is_closed() {
+        let idle_since = std::cmp::max(*last_used.lock().unwrap(), last_ping);
+        let now = Instant::now();
+        if idle_since + interval > now {
+            Runtime::locate().sleep(idle_since + interval - now).await;
+            continue;
+        }
+        last_ping = now;
+        let response = pipeline.send_recv_multiple(cmd("PING").get_packed_command(), 1);
+        // Failures that close the connection are recorded by the driver.
+        let _ = await_response(response_timeout, &closed_reason, response).await;
+    }
+}
+
+// Waits for `request`, failing with `ErrorKind::Cancelled` if `token` is cancelled first.  Dropping
+// `request` drops the receiver of its responses, which makes the driver skip them.
+async fn cancellable<T>(
+    token: &CancellationToken,
+    request: impl Future<Output = RedisResult<T>>,
+) -> RedisResult<T> {
+    if token.is_cancelled() {
+        fail!((ErrorKind::Cancelled, "The request was cancelled"));
+    }
+    futures_util::pin_mut!(request);
+    let cancelled = token.cancelled();
+    futures_util::pin_mut!(cancelled);
+    match futures_util::future::select(request, cancelled).await {
+        futures_util::future::Either::Left((result, _)) => result,
+        futures_util::future::Either::Right(((), _)) => {
+            fail!((ErrorKind::Cancelled, "The request was cancelled"))
+        }
+    }
+}
+
+// Turns the responses to a request into its result.  If reading them failed with an I/O or
+// parse error, or the connection was closed before all `expected` responses were read, the
+// responses read before are attached to the error.
+fn collect_responses(
+    mut responses: Vec<RedisResult<Value>>,
+    expected: usize,
+) -> RedisResult<Vec<Value>> {
+    let unrecoverable = responses
+        .iter()
+        .position(|response| matches!(response, Err(err) if err.is_unrecoverable_error()));
+    let err = match unrecoverable {
+        Some(index) => {
+            let err = responses.swap_remove(index).unwrap_err();
+            responses.truncate(index);
+            err
+        }
+        None if responses.len() < expected => {
+            RedisError::from(io::Error::from(io::ErrorKind::BrokenPipe))
+        }
+        None => return responses.into_iter().collect(),
+    };
+    Err(err.with_partial_responses(responses))
+}
+
+// Estimates the number of bytes a response takes in memory, for the response budget.
+fn response_size(response: &RedisResult<Value>) -> usize {
+    fn value_size(value: &Value) -> usize {
+        std::mem::size_of::<Value>()
+            + match value {
+                Value::Data(data) => data.len(),
+                Value::Bulk(items) => items.iter().map(value_size).sum(),
+                #[cfg(feature = "resp3")]
+                Value::Set(items) | Value::Push(items) => items.iter().map(value_size).sum(),
+                #[cfg(feature = "resp3")]
+                Value::Attribute { data, attributes } => {
+                    value_size(data)
+                        + attributes
+                            .iter()
+                            .map(|(key, value)| value_size(key) + value_size(value))
+                            .sum::<usize>()
+                }
+                Value::Status(status) => status.len(),
+                #[cfg(feature = "resp3")]
+                Value::VerbatimString { text, .. } => text.len(),
+                #[cfg(feature = "resp3")]
+                Value::BigNumber(digits) => digits.len(),
+                #[cfg(feature = "resp3")]
+                Value::Map(entries) => entries
+                    .iter()
+                    .map(|(key, value)| value_size(key) + value_size(value))
+                    .sum(),
+                _ => 0,
+            }
+    }
+    match response {
+        Ok(value) => value_size(value),
+        Err(_) => std::mem::size_of::<RedisError>(),
+    }
+}
+
+// The names of the commands of a pipeline, as reported to the slow command hook.
+fn pipeline_command_names(pipeline: &crate::Pipeline) -> String {
+    let names: Vec<_> = pipeline.cmd_iter().map(command_name).collect();
+    names.join(", ")
+}
+
+// The error returned for requests that couldn't be sent because the driver shut down.
+fn closed_error(closed_reason: &Mutex<Option<RedisError>>) -> RedisError {
+    match *closed_reason.lock().unwrap() {
+        Some(ref reason) => reason.clone_mostly("The connection was closed"),
+        None => RedisError::from(io::Error::from(io::ErrorKind::BrokenPipe)),
+    }
 }
 
 impl ConnectionLike for MultiplexedConnection {
@@ -414,7 +1805,619 @@ impl ConnectionLike for MultiplexedConnection {
         (async move { self.send_packed_commands(cmd, offset, count).await }).boxed()
     }
 
+    fn req_raw<'a>(
+        &'a mut self,
+        bytes: &'a [u8],
+        expected_responses: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        (async move { self.send_raw(bytes, expected_responses).await }).boxed()
+    }
+
     fn get_db(&self) -> i64 {
         self.db
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_responses() {
+        let responses = vec![Ok(Value::Okay), Ok(Value::Int(1))];
+        assert_eq!(
+            collect_responses(responses, 2),
+            Ok(vec![Value::Okay, Value::Int(1)])
+        );
+
+        let server_error = RedisError::from((ErrorKind::ResponseError, "WRONGTYPE"));
+        let responses = vec![Err(server_error), Ok(Value::Int(1))];
+        let err = collect_responses(responses, 2).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseError);
+        assert!(err.partial_responses().is_none());
+
+        let io_error = RedisError::from(io::Error::from(io::ErrorKind::UnexpectedEof));
+        let responses = vec![Ok(Value::Okay), Err(io_error)];
+        let err = collect_responses(responses, 3).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IoError);
+        assert_eq!(err.partial_responses(), Some(&[Ok(Value::Okay)][..]));
+
+        let responses = vec![Ok(Value::Okay)];
+        let err = collect_responses(responses, 2).unwrap_err();
+        assert!(err.is_connection_dropped());
+        assert_eq!(err.partial_responses(), Some(&[Ok(Value::Okay)][..]));
+    }
+
+    // A connection without handshake, whose server side is driven by the test.
+    #[cfg(feature = "tokio-comp")]
+    fn test_connection() -> (MultiplexedConnection, tokio::io::DuplexStream) {
+        let (stream, server) = tokio::io::duplex(1024);
+        let buffer_pool = Arc::new(BufferPool::default());
+        let codec = ValueCodec::default()
+            .buffer_pool(buffer_pool.clone())
+            .framed(stream)
+            .and_then(|msg| async move { msg });
+        let response_budget = Arc::new(ResponseBudget::default());
+        let (pipeline, driver) = Pipeline::new(
+            codec,
+            collect_responses,
+            response_budget.clone(),
+            response_size,
+        );
+        tokio::spawn(driver);
+        let now = Instant::now();
+        let con = MultiplexedConnection {
+            pipeline,
+            db: 0,
+            connection_info: Arc::default(),
+            protocol: ProtocolVersion::RESP2,
+            response_timeout: None,
+            closed_reason: Arc::default(),
+            client: None,
+            slow_command_hook: None,
+            id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+            created_at: now,
+            bound_db: None,
+            last_used: Arc::new(Mutex::new(now)),
+            response_budget,
+            blocking_command_policy: BlockingCommandPolicy::default(),
+            idle_ping: Arc::default(),
+            buffer_pool,
+            tracking: Arc::default(),
+            coalesced_commands: Arc::default(),
+            coalesced_requests: Arc::default(),
+            command_table: None,
+        };
+        (con, server)
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_cancelled_request_response_is_skipped() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut con, mut server) = test_connection();
+
+        let token = CancellationToken::new();
+        let first = crate::cmd("GET").arg("first").clone();
+        let (result, ()) = futures::future::join(
+            con.clone().send_packed_command_cancellable(&first, &token),
+            async {
+                let mut request = vec![0; first.get_packed_command().len()];
+                server.read_exact(&mut request).await.unwrap();
+                token.cancel();
+            },
+        )
+        .await;
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::Cancelled);
+
+        let result = con.send_packed_command_cancellable(&first, &token).await;
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::Cancelled);
+
+        let second = crate::cmd("GET").arg("second").clone();
+        let (result, ()) = futures::future::join(con.send_packed_command(&second), async {
+            let mut request = vec![0; second.get_packed_command().len()];
+            server.read_exact(&mut request).await.unwrap();
+            server
+                .write_all(b"$5\r\nfirst\r\n$6\r\nsecond\r\n")
+                .await
+                .unwrap();
+        })
+        .await;
+        assert_eq!(result.unwrap(), Value::Data(b"second".to_vec()));
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_slow_command_hook() {
+        use tokio::io::AsyncWriteExt;
+
+        let (mut con, mut server) = test_connection();
+        let slow = Arc::new(Mutex::new(Vec::new()));
+        con.set_slow_command_hook(Duration::from_millis(50), {
+            let slow = slow.clone();
+            move |name, elapsed| slow.lock().unwrap().push((name.to_string(), elapsed))
+        });
+
+        server.write_all(b"+PONG\r\n").await.unwrap();
+        con.send_packed_command(&crate::cmd("PING")).await.unwrap();
+        assert!(slow.lock().unwrap().is_empty());
+
+        let (result, ()) = futures::future::join(
+            con.send_packed_commands(crate::pipe().cmd("KEYS").arg("*").cmd("PING"), 0, 2),
+            async {
+                tokio::time::sleep(Duration::from_millis(60)).await;
+                server.write_all(b"*0\r\n+PONG\r\n").await.unwrap();
+            },
+        )
+        .await;
+        result.unwrap();
+        let slow = slow.lock().unwrap();
+        assert_eq!(slow.len(), 1);
+        assert_eq!(slow[0].0, "KEYS, PING");
+        assert!(slow[0].1 >= Duration::from_millis(50));
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_send_raw() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut con, mut server) = test_connection();
+        let bytes = b"*1\r\n$4\r\nPING\r\n*2\r\n$4\r\nECHO\r\n$2\r\nhi\r\n";
+        server.write_all(b"+PONG\r\n$2\r\nhi\r\n").await.unwrap();
+        let values = con.req_raw(bytes, 2).await.unwrap();
+        assert_eq!(
+            values,
+            vec![
+                Value::Status("PONG".to_string()),
+                Value::Data(b"hi".to_vec())
+            ]
+        );
+
+        let mut written = vec![0; bytes.len()];
+        server.read_exact(&mut written).await.unwrap();
+        assert_eq!(&written[..], &bytes[..]);
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_with_db() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (con, mut server) = test_connection();
+        server.write_all(b"+OK\r\n+OK\r\n").await.unwrap();
+        let mut bound = con.with_db(2).await.unwrap();
+        assert_eq!(bound.get_db(), 2);
+        assert_eq!(con.get_db(), 0);
+
+        let get = crate::cmd("GET").arg("key").clone();
+        server
+            .write_all(b"+OK\r\n$5\r\nvalue\r\n+OK\r\n")
+            .await
+            .unwrap();
+        let value = bound.send_packed_command(&get).await.unwrap();
+        assert_eq!(value, Value::Data(b"value".to_vec()));
+
+        let mut expected = crate::cmd("SELECT").arg(2).get_packed_command();
+        expected.extend(crate::cmd("SELECT").arg(0).get_packed_command());
+        expected.extend(crate::cmd("SELECT").arg(2).get_packed_command());
+        expected.extend(get.get_packed_command());
+        expected.extend(crate::cmd("SELECT").arg(0).get_packed_command());
+        let mut written = vec![0; expected.len()];
+        server.read_exact(&mut written).await.unwrap();
+        assert_eq!(written, expected);
+
+        assert_eq!(bound.with_db(0).await.unwrap().bound_db, None);
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_with_db_out_of_range() {
+        use tokio::io::AsyncWriteExt;
+
+        let (con, mut server) = test_connection();
+        server
+            .write_all(b"-ERR DB index is out of range\r\n+OK\r\n")
+            .await
+            .unwrap();
+        let err = con.with_db(100).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseError);
+        assert_eq!(err.detail(), Some("DB index is out of range"));
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_coalesced_commands() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut con, mut server) = test_connection();
+        con.set_coalesced_commands(["get"]);
+        let get = crate::cmd("GET").arg("key").clone();
+        let packed = get.get_packed_command();
+
+        let (first, second, ()) = futures::future::join3(
+            con.clone().send_packed_command(&get),
+            con.clone().send_packed_command(&get),
+            async {
+                let mut request = vec![0; packed.len()];
+                server.read_exact(&mut request).await.unwrap();
+                server.write_all(b"$5\r\nvalue\r\n").await.unwrap();
+            },
+        )
+        .await;
+        assert_eq!(first.unwrap(), Value::Data(b"value".to_vec()));
+        assert_eq!(second.unwrap(), Value::Data(b"value".to_vec()));
+        assert!(con.coalesced_requests.lock().unwrap().is_empty());
+
+        // Once the response arrived, the command is sent again.
+        let (value, ()) = futures::future::join(con.send_packed_command(&get), async {
+            let mut request = vec![0; packed.len()];
+            server.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, packed);
+            server.write_all(b"$3\r\nnew\r\n").await.unwrap();
+        })
+        .await;
+        assert_eq!(value.unwrap(), Value::Data(b"new".to_vec()));
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_request_without_responses() {
+        use tokio::io::AsyncWriteExt;
+
+        let (mut con, mut server) = test_connection();
+        let values = con.send_raw(b"", 0).await.unwrap();
+        assert!(values.is_empty());
+
+        server.write_all(b"+PONG\r\n").await.unwrap();
+        let ping = crate::cmd("PING");
+        let value = tokio::time::timeout(Duration::from_secs(1), con.send_packed_command(&ping))
+            .await
+            .unwrap();
+        assert_eq!(value, Ok(Value::Status("PONG".to_string())));
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_response_memory_budget() {
+        use tokio::io::AsyncWriteExt;
+
+        let (mut con, mut server) = test_connection();
+        con.set_response_memory_budget(Some(1));
+        let mut responses = con
+            .send_packed_commands_stream(crate::pipe().get("a").get("b"), 0, 2)
+            .await
+            .unwrap();
+        server
+            .write_all(b"$1\r\na\r\n$1\r\nb\r\n+PONG\r\n")
+            .await
+            .unwrap();
+
+        // The unreceived responses exceed the budget, so the response to `PING` isn't read
+        let ping = crate::cmd("PING");
+        let mut ping_con = con.clone();
+        let ping = ping_con.send_packed_command(&ping);
+        futures_util::pin_mut!(ping);
+        let timeout = tokio::time::timeout(Duration::from_millis(50), &mut ping);
+        assert!(timeout.await.is_err());
+
+        assert_eq!(
+            responses.next().await.unwrap(),
+            Ok(Value::Data(b"a".to_vec()))
+        );
+        assert_eq!(
+            responses.next().await.unwrap(),
+            Ok(Value::Data(b"b".to_vec()))
+        );
+        assert_eq!(ping.await, Ok(Value::Status("PONG".to_string())));
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_blocking_command_policy() {
+        use tokio::io::AsyncWriteExt;
+
+        let (mut con, mut server) = test_connection();
+        con.set_blocking_command_policy(BlockingCommandPolicy::Reject);
+        let blpop = crate::cmd("BLPOP").arg("list").arg(0).clone();
+        let err = con.send_packed_command(&blpop).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidClientConfig);
+        assert_eq!(
+            err.detail(),
+            Some("BLPOP would hold up all other requests on the connection")
+        );
+
+        let xread = crate::cmd("XREAD")
+            .arg("BLOCK")
+            .arg(0)
+            .arg("STREAMS")
+            .arg("stream")
+            .arg("$")
+            .clone();
+        assert!(con.send_packed_command(&xread).await.is_err());
+        let subscribe = crate::cmd("SUBSCRIBE").arg("channel").clone();
+        assert!(con.send_packed_command(&subscribe).await.is_err());
+
+        let mut pipeline = crate::pipe();
+        pipeline.cmd("GET").arg("key").add_command(blpop.clone());
+        let err = con.send_packed_commands(&pipeline, 0, 2).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidClientConfig);
+
+        server.write_all(b"*-1\r\n").await.unwrap();
+        let xread = crate::cmd("XREAD")
+            .arg("STREAMS")
+            .arg("block")
+            .arg("$")
+            .clone();
+        assert_eq!(con.send_packed_command(&xread).await, Ok(Value::Nil));
+
+        server.write_all(b"*-1\r\n*-1\r\n").await.unwrap();
+        let warning = format!(
+            "Blocking command on multiplexed connection {}: BLPOP would hold up all other \
+             requests on the connection",
+            con.id
+        );
+        test_logger::init();
+        con.set_blocking_command_policy(BlockingCommandPolicy::Warn);
+        assert_eq!(con.send_packed_command(&blpop).await, Ok(Value::Nil));
+        assert_eq!(test_logger::count(&warning), 1);
+        con.set_blocking_command_policy(BlockingCommandPolicy::Allow);
+        assert_eq!(con.send_packed_command(&blpop).await, Ok(Value::Nil));
+        assert_eq!(test_logger::count(&warning), 1);
+    }
+
+    // Captures warnings, so that tests can check they were logged.
+    mod test_logger {
+        use std::sync::{Mutex, Once};
+
+        static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        struct Logger;
+
+        impl log::Log for Logger {
+            fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+                metadata.level() <= log::Level::Warn
+            }
+
+            fn log(&self, record: &log::Record<'_>) {
+                if self.enabled(record.metadata()) {
+                    WARNINGS.lock().unwrap().push(record.args().to_string());
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        pub(super) fn init() {
+            static INIT: Once = Once::new();
+            INIT.call_once(|| {
+                log::set_logger(&Logger).unwrap();
+                log::set_max_level(log::LevelFilter::Warn);
+            });
+        }
+
+        // Returns how often the given warning was logged.
+        pub(super) fn count(warning: &str) -> usize {
+            let warnings = WARNINGS.lock().unwrap();
+            warnings.iter().filter(|logged| *logged == warning).count()
+        }
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_pipeline_guard_sends_on_flush_only() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut con, mut server) = test_connection();
+        con.bound_db = Some(1);
+        let expected = crate::pipe()
+            .cmd("SELECT")
+            .arg(1)
+            .cmd("INCR")
+            .arg("a")
+            .cmd("INCR")
+            .arg("b")
+            .cmd("SELECT")
+            .arg(0)
+            .get_packed_pipeline();
+
+        let discarded = {
+            let mut guard = con.pipeline_guard();
+            guard.send_packed_command(crate::cmd("GET").arg("discarded"))
+        };
+        let err = discarded.await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Cancelled);
+
+        let mut guard = con.pipeline_guard();
+        let a = guard.send_packed_command(crate::cmd("INCR").arg("a"));
+        let b = guard.send_packed_command(crate::cmd("INCR").arg("b"));
+        guard.flush().await.unwrap();
+        let mut written = vec![0; expected.len()];
+        tokio::time::timeout(Duration::from_secs(1), server.read_exact(&mut written))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(written, expected);
+        server
+            .write_all(b"+OK\r\n:1\r\n:2\r\n+OK\r\n")
+            .await
+            .unwrap();
+        assert_eq!(a.await.unwrap(), Value::Int(1));
+        assert_eq!(b.await.unwrap(), Value::Int(2));
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_reset_is_a_single_request() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut con, mut server) = test_connection();
+        let setup = crate::cmd("CLIENT").arg("NO-EVICT").arg("on").clone();
+        con.connection_info = Arc::new(RedisConnectionConfig {
+            db: 2,
+            password: Some("secret".to_string()),
+            setup_commands: vec![setup.clone()],
+            ..Default::default()
+        });
+        let expected = crate::pipe()
+            .add_command(crate::cmd("RESET"))
+            .cmd("AUTH")
+            .arg("secret")
+            .cmd("SELECT")
+            .arg(2)
+            .add_command(setup)
+            .get_packed_pipeline();
+
+        // All commands are written before any of them is answered.
+        let serve = async {
+            let mut written = vec![0; expected.len()];
+            tokio::time::timeout(Duration::from_secs(1), server.read_exact(&mut written))
+                .await
+                .unwrap()
+                .unwrap();
+            server
+                .write_all(b"+RESET\r\n+OK\r\n+OK\r\n+OK\r\n")
+                .await
+                .unwrap();
+            written
+        };
+        let (result, written) = tokio::join!(con.reset(), serve);
+        result.unwrap();
+        assert_eq!(written, expected);
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_idle_ping() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut con, mut server) = test_connection();
+        let created_at = con.created_at();
+        con.set_idle_ping_interval(Some(Duration::from_millis(10)));
+
+        let ping = crate::cmd("PING").get_packed_command();
+        let mut written = vec![0; ping.len()];
+        tokio::time::timeout(Duration::from_secs(1), server.read_exact(&mut written))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(written, ping);
+        server.write_all(b"+PONG\r\n").await.unwrap();
+        assert_eq!(con.last_used(), created_at);
+
+        // Dropping the last clone stops the pings and the driver, which closes the stream.
+        drop(con);
+        let mut rest = Vec::new();
+        tokio::time::timeout(Duration::from_secs(1), server.read_to_end(&mut rest))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(rest.len() <= ping.len());
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_request_buffers_are_reused() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut con, mut server) = test_connection();
+        let get = crate::cmd("GET").arg("key").clone();
+        let packed = get.get_packed_command();
+        for _ in 0..3 {
+            server.write_all(b"$5\r\nvalue\r\n").await.unwrap();
+            let value = con.send_packed_command(&get).await.unwrap();
+            assert_eq!(value, Value::Data(b"value".to_vec()));
+            assert_eq!(con.buffer_pool.len(), 1);
+
+            let mut written = vec![0; packed.len()];
+            server.read_exact(&mut written).await.unwrap();
+            assert_eq!(written, packed);
+        }
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_pending_requests() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (con, mut server) = test_connection();
+        let get = crate::cmd("GET").arg("key").clone();
+        let mut other = con.clone();
+        let request = tokio::spawn(async move { other.send_packed_command(&get).await });
+
+        let mut written = vec![0; crate::cmd("GET").arg("key").get_packed_command().len()];
+        server.read_exact(&mut written).await.unwrap();
+        assert_eq!(con.pipeline.pending_requests(), 1);
+
+        server.write_all(b"$5\r\nvalue\r\n").await.unwrap();
+        request.await.unwrap().unwrap();
+        assert_eq!(con.pipeline.pending_requests(), 0);
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_connection_id() {
+        let (first, _server) = test_connection();
+        let (second, _server) = test_connection();
+        assert!(second.connection_id() > first.connection_id());
+        assert_eq!(first.clone().connection_id(), first.connection_id());
+        let debug = format!("{first:?}");
+        assert!(
+            debug.contains(&format!("id: {}", first.connection_id())),
+            "{debug}"
+        );
+        assert!(debug.contains("pending_requests: 0"), "{debug}");
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_last_used() {
+        use tokio::io::AsyncWriteExt;
+
+        let (mut con, mut server) = test_connection();
+        let created_at = con.created_at();
+        assert_eq!(con.last_used(), created_at);
+
+        server.write_all(b"-ERR failed\r\n").await.unwrap();
+        con.send_packed_command(&crate::cmd("PING"))
+            .await
+            .unwrap_err();
+        assert_eq!(con.last_used(), created_at);
+
+        server.write_all(b"+PONG\r\n").await.unwrap();
+        con.clone()
+            .send_packed_command(&crate::cmd("PING"))
+            .await
+            .unwrap();
+        assert!(con.last_used() > created_at);
+        assert_eq!(con.created_at(), created_at);
+    }
+
+    #[cfg(feature = "tokio-comp")]
+    #[tokio::test]
+    async fn test_borrowed_stream() {
+        use tokio::io::AsyncWriteExt;
+
+        let (mut stream, mut server) = tokio::io::duplex(1024);
+        // The replies to `CLIENT SETINFO` and `PING`
+        server.write_all(b"+OK\r\n+OK\r\n+PONG\r\n").await.unwrap();
+        {
+            let (mut con, driver) =
+                MultiplexedConnection::new(&RedisConnectionInfo::default(), &mut stream)
+                    .await
+                    .unwrap();
+            let ping = crate::cmd("PING");
+            let request = con.send_packed_command(&ping);
+            futures::pin_mut!(request, driver);
+            match futures::future::select(request, driver).await {
+                futures::future::Either::Left((result, _)) => {
+                    assert_eq!(result.unwrap(), Value::Status("PONG".to_string()));
+                }
+                futures::future::Either::Right(_) => panic!("the driver shut down"),
+            }
+        }
+        // The connection doesn't borrow the stream anymore
+        stream.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+use crate::cmd::Cmd;
+use crate::parser::ValueCodec;
+use crate::types::{RedisResult, Value};
+use ::tokio::io::{AsyncRead, AsyncWrite};
+use futures_util::{sink::Sink, stream::Stream};
+use std::pin::Pin;
+use std::task::{self, Poll};
+use tokio_util::codec::{Decoder, Framed};
+
+/// A low-level connection that is driven by polling it directly, without spawning a driver
+/// task or going through a channel.
+///
+/// This is meant for embedding into custom event loops that don't run a Tokio or async-std
+/// runtime.  Sending works like a [`Sink`]: wait for [`poll_ready`](Self::poll_ready), queue
+/// requests with [`start_send`](Self::start_send) and write them with
+/// [`poll_flush`](Self::poll_flush).  Responses are then read one by one with
+/// [`poll_recv`](Self::poll_recv), in the order the requests were sent.
+///
+/// Unlike the other connections, no handshake is performed: authentication and selecting a
+/// database have to be done by sending the respective commands.  Tracking how many responses
+/// are outstanding is also up to the caller; a pipeline produces one response per command.
+pub struct PollConnection<C> {
+    framed: Framed<C, ValueCodec>,
+}
+
+impl<C> PollConnection<C>
+where
+    C: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Wraps an established connection to a server.
+    pub fn new(stream: C) -> Self {
+        PollConnection {
+            framed: ValueCodec::default().framed(stream),
+        }
+    }
+
+    /// Polls whether the connection is ready to queue another request.
+    pub fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> Poll<RedisResult<()>> {
+        Pin::new(&mut self.framed).poll_ready(cx)
+    }
+
+    /// Queues a command to be written by the next call to [`poll_flush`](Self::poll_flush).
+    ///
+    /// [`poll_ready`](Self::poll_ready) must have returned `Ready(Ok(()))` before.
+    pub fn start_send(&mut self, cmd: &Cmd) -> RedisResult<()> {
+        Pin::new(&mut self.framed).start_send(cmd.get_packed_command())
+    }
+
+    /// Queues all commands of a pipeline to be written by the next call to
+    /// [`poll_flush`](Self::poll_flush).
+    ///
+    /// [`poll_ready`](Self::poll_ready) must have returned `Ready(Ok(()))` before.
+    pub fn start_send_pipeline(&mut self, pipeline: &crate::Pipeline) -> RedisResult<()> {
+        Pin::new(&mut self.framed).start_send(pipeline.get_packed_pipeline())
+    }
+
+    /// Writes all queued requests to the connection.
+    pub fn poll_flush(&mut self, cx: &mut task::Context<'_>) -> Poll<RedisResult<()>> {
+        Pin::new(&mut self.framed).poll_flush(cx)
+    }
+
+    /// Polls for the next response.
+    ///
+    /// Returns `Ready(None)` once the connection was closed by the server.  Errors returned by
+    /// the server are yielded as `Ready(Some(Err(_)))`, after which the connection can still be
+    /// used.
+    pub fn poll_recv(&mut self, cx: &mut task::Context<'_>) -> Poll<Option<RedisResult<Value>>> {
+        Pin::new(&mut self.framed)
+            .poll_next(cx)
+            .map(|item| item.map(|result| result.and_then(|response| response)))
+    }
+
+    /// Returns the underlying connection.
+    ///
+    /// Queued requests that were not flushed and buffered data that was not read yet are lost.
+    pub fn into_inner(self) -> C {
+        self.framed.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ErrorKind;
+    use futures::executor::block_on;
+    use futures::future::poll_fn;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_poll_connection() {
+        block_on(async {
+            let (client, mut server) = tokio::io::duplex(1024);
+            let mut con = PollConnection::new(client);
+
+            poll_fn(|cx| con.poll_ready(cx)).await.unwrap();
+            con.start_send(crate::cmd("GET").arg("foo")).unwrap();
+            con.start_send(crate::cmd("INCR").arg("foo")).unwrap();
+            poll_fn(|cx| con.poll_flush(cx)).await.unwrap();
+
+            let expected = b"*2\r\n$3\r\nGET\r\n$3\r\nfoo\r\n*2\r\n$4\r\nINCR\r\n$3\r\nfoo\r\n";
+            let mut request = vec![0; expected.len()];
+            server.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request[..], &expected[..]);
+
+            server
+                .write_all(b"$3\r\nbar\r\n-ERR not an integer\r\n")
+                .await
+                .unwrap();
+            assert_eq!(
+                poll_fn(|cx| con.poll_recv(cx)).await,
+                Some(Ok(Value::Data(b"bar".to_vec())))
+            );
+            let err = poll_fn(|cx| con.poll_recv(cx)).await.unwrap().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ResponseError);
+
+            drop(server);
+            assert_eq!(poll_fn(|cx| con.poll_recv(cx)).await, None);
+        });
+    }
+}
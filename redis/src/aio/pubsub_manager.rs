@@ -0,0 +1,231 @@
+use super::{ConnectionManager, PubSub};
+use crate::connection::Msg;
+use crate::types::{HashSet, RedisResult, ToRedisArgs};
+use crate::Client;
+use futures_util::stream::{self, Stream};
+use tokio_retry::strategy::{jitter, ExponentialBackoff};
+use tokio_retry::Retry;
+
+/// An event produced by a [`PubSubManager`].
+#[derive(Debug)]
+pub enum PubSubEvent {
+    /// A message was received on one of the subscribed channels or patterns.
+    Message(Msg),
+    /// The connection was re-established and all channels and patterns were subscribed again.
+    ///
+    /// Messages published while the connection was down are lost, so this marks a possible gap
+    /// in the message stream.
+    Resubscribed,
+}
+
+#[derive(Clone, Copy)]
+enum SubscriptionCommand {
+    Subscribe,
+    PSubscribe,
+    Unsubscribe,
+    PUnsubscribe,
+}
+
+/// A `PubSubManager` wraps a [`PubSub`] connection and automatically reconnects to the server
+/// when necessary.
+///
+/// ## Behavior
+///
+/// - The manager remembers every channel and pattern it was asked to subscribe to (and forgets
+///   them again when unsubscribing, or all of them when unsubscribing without arguments).
+/// - When reading the next event fails with an I/O error, the connection is re-established with
+///   the same exponential backoff used by [`ConnectionManager`], and all remembered channels and
+///   patterns are subscribed again.
+/// - Only once the re-subscription completed, [`PubSubEvent::Resubscribed`] is returned, after
+///   which messages are delivered again.
+/// - If reconnecting fails, the error is returned and the next call to
+///   [`next_event`](PubSubManager::next_event) will try again.
+pub struct PubSubManager {
+    client: Client,
+    pubsub: Option<PubSub>,
+    channels: HashSet<Vec<u8>>,
+    patterns: HashSet<Vec<u8>>,
+    retry_strategy: ExponentialBackoff,
+    number_of_retries: usize,
+}
+
+impl PubSubManager {
+    /// Connect to the server and store the connection inside the returned `PubSubManager`.
+    ///
+    /// This requires the `connection-manager` feature, which will also pull in
+    /// the Tokio executor.
+    pub async fn new(client: Client) -> RedisResult<Self> {
+        Self::new_with_backoff(
+            client,
+            ConnectionManager::DEFAULT_CONNECTION_RETRY_EXPONENT_BASE,
+            ConnectionManager::DEFAULT_CONNECTION_RETRY_FACTOR,
+            ConnectionManager::DEFAULT_NUMBER_OF_CONNECTION_RETRIESE,
+        )
+        .await
+    }
+
+    /// Connect to the server and store the connection inside the returned `PubSubManager`.
+    ///
+    /// This requires the `connection-manager` feature, which will also pull in
+    /// the Tokio executor.
+    ///
+    /// In case of reconnection issues, the manager will retry reconnection
+    /// number_of_retries times, with an exponentially increasing delay, calculated as
+    /// rand(0 .. factor * (exponent_base ^ current-try)).
+    pub async fn new_with_backoff(
+        client: Client,
+        exponent_base: u64,
+        factor: u64,
+        number_of_retries: usize,
+    ) -> RedisResult<Self> {
+        let retry_strategy = ExponentialBackoff::from_millis(exponent_base).factor(factor);
+        let pubsub =
+            Self::new_pubsub(client.clone(), retry_strategy.clone(), number_of_retries).await?;
+        Ok(Self {
+            client,
+            pubsub: Some(pubsub),
+            channels: HashSet::new(),
+            patterns: HashSet::new(),
+            retry_strategy,
+            number_of_retries,
+        })
+    }
+
+    async fn new_pubsub(
+        client: Client,
+        exponential_backoff: ExponentialBackoff,
+        number_of_retries: usize,
+    ) -> RedisResult<PubSub> {
+        let retry_strategy = exponential_backoff.map(jitter).take(number_of_retries);
+        let con = Retry::start(retry_strategy, || client.get_async_connection()).await?;
+        Ok(con.into_pubsub())
+    }
+
+    /// Reconnect and subscribe to all remembered channels and patterns again.
+    async fn resubscribe(&mut self) -> RedisResult<()> {
+        self.pubsub = None;
+        let mut pubsub = Self::new_pubsub(
+            self.client.clone(),
+            self.retry_strategy.clone(),
+            self.number_of_retries,
+        )
+        .await?;
+        if !self.channels.is_empty() {
+            pubsub
+                .subscribe(self.channels.iter().collect::<Vec<_>>())
+                .await?;
+        }
+        if !self.patterns.is_empty() {
+            pubsub
+                .psubscribe(self.patterns.iter().collect::<Vec<_>>())
+                .await?;
+        }
+        self.pubsub = Some(pubsub);
+        Ok(())
+    }
+
+    /// Runs a (un)subscribe command on the current connection, if there is one.
+    ///
+    /// A dropped connection is discarded so that the next call to `next_event` reconnects.
+    async fn send_subscription_command(
+        &mut self,
+        command: SubscriptionCommand,
+        args: &[Vec<u8>],
+    ) -> RedisResult<()> {
+        let pubsub = match self.pubsub {
+            Some(ref mut pubsub) => pubsub,
+            None => return Ok(()),
+        };
+        let result = match command {
+            SubscriptionCommand::Subscribe => pubsub.subscribe(args).await,
+            SubscriptionCommand::PSubscribe => pubsub.psubscribe(args).await,
+            SubscriptionCommand::Unsubscribe => pubsub.unsubscribe(args).await,
+            SubscriptionCommand::PUnsubscribe => pubsub.punsubscribe(args).await,
+        };
+        if let Err(ref e) = result {
+            if e.is_io_error() {
+                self.pubsub = None;
+            }
+        }
+        result
+    }
+
+    /// Subscribes to a new channel.
+    ///
+    /// The channel is remembered even if the command fails because of a dropped connection, and
+    /// will be subscribed once the connection is re-established.
+    pub async fn subscribe<T: ToRedisArgs>(&mut self, channel: T) -> RedisResult<()> {
+        let args = channel.to_redis_args();
+        self.channels.extend(args.iter().cloned());
+        self.send_subscription_command(SubscriptionCommand::Subscribe, &args)
+            .await
+    }
+
+    /// Subscribes to a new channel with a pattern.
+    ///
+    /// The pattern is remembered even if the command fails because of a dropped connection, and
+    /// will be subscribed once the connection is re-established.
+    pub async fn psubscribe<T: ToRedisArgs>(&mut self, pchannel: T) -> RedisResult<()> {
+        let args = pchannel.to_redis_args();
+        self.patterns.extend(args.iter().cloned());
+        self.send_subscription_command(SubscriptionCommand::PSubscribe, &args)
+            .await
+    }
+
+    /// Unsubscribes from a channel.
+    ///
+    /// Without any channel given, this unsubscribes from all channels and forgets all of them.
+    pub async fn unsubscribe<T: ToRedisArgs>(&mut self, channel: T) -> RedisResult<()> {
+        let args = channel.to_redis_args();
+        if args.is_empty() {
+            self.channels.clear();
+        }
+        for arg in &args {
+            self.channels.remove(arg);
+        }
+        self.send_subscription_command(SubscriptionCommand::Unsubscribe, &args)
+            .await
+    }
+
+    /// Unsubscribes from a channel with a pattern.
+    ///
+    /// Without any pattern given, this unsubscribes from all patterns and forgets all of them.
+    pub async fn punsubscribe<T: ToRedisArgs>(&mut self, pchannel: T) -> RedisResult<()> {
+        let args = pchannel.to_redis_args();
+        if args.is_empty() {
+            self.patterns.clear();
+        }
+        for arg in &args {
+            self.patterns.remove(arg);
+        }
+        self.send_subscription_command(SubscriptionCommand::PUnsubscribe, &args)
+            .await
+    }
+
+    /// Waits for the next [`PubSubEvent`].
+    ///
+    /// If the connection was lost, this reconnects, subscribes to all remembered channels and
+    /// patterns again and returns [`PubSubEvent::Resubscribed`].
+    pub async fn next_event(&mut self) -> RedisResult<PubSubEvent> {
+        if let Some(ref mut pubsub) = self.pubsub {
+            match pubsub.next_message().await {
+                Ok(msg) => return Ok(PubSubEvent::Message(msg)),
+                Err(e) if e.is_io_error() => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.resubscribe().await?;
+        Ok(PubSubEvent::Resubscribed)
+    }
+
+    /// Returns a [`Stream`] of [`PubSubEvent`]s consuming this manager.
+    ///
+    /// The stream never ends on its own; errors are yielded and the stream can be polled again
+    /// afterwards.
+    pub fn into_event_stream(self) -> impl Stream<Item = RedisResult<PubSubEvent>> {
+        stream::unfold(self, |mut manager| async move {
+            let event = manager.next_event().await;
+            Some((event, manager))
+        })
+    }
+}
@@ -3,7 +3,9 @@ use super::async_std;
 #[cfg(feature = "tokio-comp")]
 use super::tokio;
 use super::RedisRuntime;
+use crate::types::{RedisError, RedisResult};
 use futures_util::Future;
+use std::{io, time::Duration};
 
 #[derive(Clone, Debug)]
 pub(crate) enum Runtime {
@@ -40,6 +42,34 @@ impl Runtime {
         }
     }
 
+    /// Runs `future`, failing with a `TimedOut` I/O error if it doesn't complete within `duration`.
+    pub(crate) async fn timeout<F: Future>(
+        &self,
+        duration: Duration,
+        future: F,
+    ) -> RedisResult<F::Output> {
+        match self {
+            #[cfg(feature = "tokio-comp")]
+            Runtime::Tokio => ::tokio::time::timeout(duration, future)
+                .await
+                .map_err(|_| RedisError::from(io::Error::from(io::ErrorKind::TimedOut))),
+            #[cfg(feature = "async-std-comp")]
+            Runtime::AsyncStd => ::async_std::future::timeout(duration, future)
+                .await
+                .map_err(|_| RedisError::from(io::Error::from(io::ErrorKind::TimedOut))),
+        }
+    }
+
+    /// Waits until `duration` has passed.
+    pub(crate) async fn sleep(&self, duration: Duration) {
+        match self {
+            #[cfg(feature = "tokio-comp")]
+            Runtime::Tokio => ::tokio::time::sleep(duration).await,
+            #[cfg(feature = "async-std-comp")]
+            Runtime::AsyncStd => ::async_std::task::sleep(duration).await,
+        }
+    }
+
     #[allow(dead_code)]
     pub(super) fn spawn(&self, f: impl Future<Output = ()> + Send + 'static) {
         match self {
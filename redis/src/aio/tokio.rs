@@ -1,4 +1,5 @@
 use super::{AsyncStream, RedisResult, RedisRuntime, SocketAddr};
+use crate::connection::bind_error;
 use async_trait::async_trait;
 use std::{
     future::Future,
@@ -10,7 +11,7 @@ use std::{
 use tokio::net::UnixStream as UnixStreamTokio;
 use tokio::{
     io::{AsyncRead, AsyncWrite, ReadBuf},
-    net::TcpStream as TcpStreamTokio,
+    net::{TcpSocket, TcpStream as TcpStreamTokio},
 };
 
 #[cfg(all(feature = "tls-native-tls", not(feature = "tls-rustls")))]
@@ -18,6 +19,8 @@ use native_tls::TlsConnector;
 
 #[cfg(feature = "tls-rustls")]
 use crate::connection::create_rustls_config;
+#[cfg(any(feature = "tls-native-tls", feature = "tls-rustls"))]
+use crate::connection::TlsCertificateCheck;
 #[cfg(feature = "tls-rustls")]
 use std::{convert::TryInto, sync::Arc};
 #[cfg(feature = "tls-rustls")]
@@ -30,8 +33,24 @@ use tokio_native_tls::TlsStream;
 use super::Path;
 
 #[inline(always)]
-async fn connect_tcp(addr: &SocketAddr) -> io::Result<TcpStreamTokio> {
-    let socket = TcpStreamTokio::connect(addr).await?;
+async fn connect_tcp(
+    addr: &SocketAddr,
+    local_addr: Option<SocketAddr>,
+) -> io::Result<TcpStreamTokio> {
+    let socket = match local_addr {
+        Some(local_addr) => {
+            let socket = if addr.is_ipv4() {
+                TcpSocket::new_v4()?
+            } else {
+                TcpSocket::new_v6()?
+            };
+            socket
+                .bind(local_addr)
+                .map_err(|err| bind_error(local_addr, err))?;
+            socket.connect(*addr).await?
+        }
+        None => TcpStreamTokio::connect(addr).await?,
+    };
     #[cfg(feature = "tcp_nodelay")]
     socket.set_nodelay(true)?;
     #[cfg(feature = "keep-alive")]
@@ -116,15 +135,22 @@ impl AsyncRead for Tokio {
 
 #[async_trait]
 impl RedisRuntime for Tokio {
-    async fn connect_tcp(socket_addr: SocketAddr) -> RedisResult<Self> {
-        Ok(connect_tcp(&socket_addr).await.map(Tokio::Tcp)?)
+    async fn connect_tcp(
+        socket_addr: SocketAddr,
+        local_addr: Option<SocketAddr>,
+    ) -> RedisResult<Self> {
+        Ok(connect_tcp(&socket_addr, local_addr)
+            .await
+            .map(Tokio::Tcp)?)
     }
 
     #[cfg(all(feature = "tls-native-tls", not(feature = "tls-rustls")))]
     async fn connect_tcp_tls(
         hostname: &str,
         socket_addr: SocketAddr,
+        local_addr: Option<SocketAddr>,
         insecure: bool,
+        certificate_check: Option<&TlsCertificateCheck>,
     ) -> RedisResult<Self> {
         let tls_connector: tokio_native_tls::TlsConnector = if insecure {
             TlsConnector::builder()
@@ -136,25 +162,43 @@ impl RedisRuntime for Tokio {
             TlsConnector::new()?
         }
         .into();
-        Ok(tls_connector
-            .connect(hostname, connect_tcp(&socket_addr).await?)
-            .await
-            .map(|con| Tokio::TcpTls(Box::new(con)))?)
+        let con = tls_connector
+            .connect(hostname, connect_tcp(&socket_addr, local_addr).await?)
+            .await?;
+        if let Some(check) = certificate_check {
+            let certificate = con.get_ref().peer_certificate()?;
+            check.verify(
+                certificate
+                    .map(|cert| cert.to_der())
+                    .transpose()?
+                    .as_deref(),
+            )?;
+        }
+        Ok(Tokio::TcpTls(Box::new(con)))
     }
 
     #[cfg(feature = "tls-rustls")]
     async fn connect_tcp_tls(
         hostname: &str,
         socket_addr: SocketAddr,
+        local_addr: Option<SocketAddr>,
         insecure: bool,
+        certificate_check: Option<&TlsCertificateCheck>,
     ) -> RedisResult<Self> {
         let config = create_rustls_config(insecure)?;
         let tls_connector = TlsConnector::from(Arc::new(config));
 
-        Ok(tls_connector
-            .connect(hostname.try_into()?, connect_tcp(&socket_addr).await?)
-            .await
-            .map(|con| Tokio::TcpTls(Box::new(con)))?)
+        let con = tls_connector
+            .connect(
+                hostname.try_into()?,
+                connect_tcp(&socket_addr, local_addr).await?,
+            )
+            .await?;
+        if let Some(check) = certificate_check {
+            let certificate = con.get_ref().1.peer_certificates().and_then(|c| c.first());
+            check.verify(certificate.map(|cert| cert.0.as_slice()))?;
+        }
+        Ok(Tokio::TcpTls(Box::new(con)))
     }
 
     #[cfg(unix)]
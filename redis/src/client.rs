@@ -1,10 +1,15 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 #[cfg(feature = "aio")]
 use std::pin::Pin;
 
 use crate::{
-    connection::{connect, Connection, ConnectionInfo, ConnectionLike, IntoConnectionInfo},
+    connection::{
+        aggregate_connect_errors, connect, Connection, ConnectionConfig, ConnectionInfo,
+        ConnectionLike, IntoConnectionInfo,
+    },
     types::{RedisResult, Value},
 };
 
@@ -12,6 +17,11 @@ use crate::{
 #[derive(Debug, Clone)]
 pub struct Client {
     connection_info: ConnectionInfo,
+    // The connection info together with the options set by a `ConnectionBuilder` or URL.
+    config: ConnectionConfig,
+    // The index of the address that was last connected to, counting the fallback addresses
+    // after the main one.  Shared by all clones.
+    last_connected: Arc<AtomicUsize>,
 }
 
 /// The client acts as connector to the redis server.  By itself it does not
@@ -35,8 +45,11 @@ impl Client {
     /// actually open a connection yet but it does perform some basic
     /// checks on the URL that might make the operation fail.
     pub fn open<T: IntoConnectionInfo>(params: T) -> RedisResult<Client> {
+        let config = params.into_connection_builder()?.into_config()?;
         Ok(Client {
-            connection_info: params.into_connection_info()?,
+            connection_info: config.info(),
+            config,
+            last_connected: Arc::default(),
         })
     }
 
@@ -46,7 +59,7 @@ impl Client {
     /// (like unreachable host) so it's important that you handle those
     /// errors.
     pub fn get_connection(&self) -> RedisResult<Connection> {
-        connect(&self.connection_info, None)
+        self.connect_any(|info| connect(info, None))
     }
 
     /// Instructs the client to actually connect to redis with specified
@@ -55,13 +68,75 @@ impl Client {
     /// a variety of errors (like unreachable host) so it's important
     /// that you handle those errors.
     pub fn get_connection_with_timeout(&self, timeout: Duration) -> RedisResult<Connection> {
-        connect(&self.connection_info, Some(timeout))
+        self.connect_any(|info| connect(info, Some(timeout)))
     }
 
     /// Returns a reference of client connection info object.
     pub fn get_connection_info(&self) -> &ConnectionInfo {
         &self.connection_info
     }
+
+    // The connection info for each address, starting with the one that was last connected to,
+    // together with its index.
+    fn candidates(&self) -> impl Iterator<Item = (usize, ConnectionConfig)> + '_ {
+        let info = &self.config;
+        let count = 1 + info.fallback_addrs.len();
+        let first = self.last_connected.load(Ordering::Relaxed) % count;
+        (first..count).chain(0..first).map(move |index| {
+            let mut candidate = info.clone();
+            if index > 0 {
+                candidate.addr = info.fallback_addrs[index - 1].clone();
+            }
+            candidate.fallback_addrs = Vec::new();
+            (index, candidate)
+        })
+    }
+
+    // Connects to the first address where `connect` succeeds, and remembers it.
+    fn connect_any<T>(
+        &self,
+        connect: impl Fn(&ConnectionConfig) -> RedisResult<T>,
+    ) -> RedisResult<T> {
+        let mut errors = Vec::new();
+        for (index, info) in self.candidates() {
+            match connect(&info) {
+                Ok(con) => {
+                    self.last_connected.store(index, Ordering::Relaxed);
+                    return Ok(con);
+                }
+                Err(err) => errors.push((info.addr, err)),
+            }
+        }
+        Err(aggregate_connect_errors(errors))
+    }
+
+    // Like `connect_any`, but for connecting asynchronously.
+    #[cfg(feature = "aio")]
+    async fn connect_any_async<T, F, Fut>(&self, connect: F) -> RedisResult<T>
+    where
+        F: Fn(ConnectionConfig) -> Fut,
+        Fut: std::future::Future<Output = RedisResult<T>>,
+    {
+        let mut errors = Vec::new();
+        for (index, info) in self.candidates() {
+            let addr = info.addr.clone();
+            let result = match info.handshake_timeout {
+                Some(timeout) => Runtime::locate()
+                    .timeout(timeout, connect(info))
+                    .await
+                    .and_then(|result| result),
+                None => connect(info).await,
+            };
+            match result {
+                Ok(con) => {
+                    self.last_connected.store(index, Ordering::Relaxed);
+                    return Ok(con);
+                }
+                Err(err) => errors.push((addr, err)),
+            }
+        }
+        Err(aggregate_connect_errors(errors))
+    }
 }
 
 /// To enable async support you need to chose one of the supported runtimes and active its
@@ -72,20 +147,21 @@ impl Client {
     /// Returns an async connection from the client.
     #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
     pub async fn get_async_connection(&self) -> RedisResult<crate::aio::Connection> {
-        let con = match Runtime::locate() {
-            #[cfg(feature = "tokio-comp")]
-            Runtime::Tokio => {
-                self.get_simple_async_connection::<crate::aio::tokio::Tokio>()
-                    .await?
-            }
-            #[cfg(feature = "async-std-comp")]
-            Runtime::AsyncStd => {
-                self.get_simple_async_connection::<crate::aio::async_std::AsyncStd>()
-                    .await?
-            }
-        };
-
-        crate::aio::Connection::new(&self.connection_info.redis, con).await
+        self.connect_any_async(|info| async move {
+            let con = match Runtime::locate() {
+                #[cfg(feature = "tokio-comp")]
+                Runtime::Tokio => {
+                    get_simple_async_connection::<crate::aio::tokio::Tokio>(&info).await?
+                }
+                #[cfg(feature = "async-std-comp")]
+                Runtime::AsyncStd => {
+                    get_simple_async_connection::<crate::aio::async_std::AsyncStd>(&info).await?
+                }
+            };
+
+            crate::aio::Connection::with_config(&info.redis, con).await
+        })
+        .await
     }
 
     /// Returns an async connection from the client.
@@ -93,11 +169,12 @@ impl Client {
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-comp")))]
     pub async fn get_tokio_connection(&self) -> RedisResult<crate::aio::Connection> {
         use crate::aio::RedisRuntime;
-        Ok(
-            crate::aio::connect::<crate::aio::tokio::Tokio>(&self.connection_info)
+        self.connect_any_async(|info| async move {
+            Ok(crate::aio::connect::<crate::aio::tokio::Tokio>(&info)
                 .await?
-                .map(RedisRuntime::boxed),
-        )
+                .map(RedisRuntime::boxed))
+        })
+        .await
     }
 
     /// Returns an async connection from the client.
@@ -105,11 +182,14 @@ impl Client {
     #[cfg_attr(docsrs, doc(cfg(feature = "async-std-comp")))]
     pub async fn get_async_std_connection(&self) -> RedisResult<crate::aio::Connection> {
         use crate::aio::RedisRuntime;
-        Ok(
-            crate::aio::connect::<crate::aio::async_std::AsyncStd>(&self.connection_info)
-                .await?
-                .map(RedisRuntime::boxed),
-        )
+        self.connect_any_async(|info| async move {
+            Ok(
+                crate::aio::connect::<crate::aio::async_std::AsyncStd>(&info)
+                    .await?
+                    .map(RedisRuntime::boxed),
+            )
+        })
+        .await
     }
 
     /// Returns an async connection from the client.
@@ -327,25 +407,74 @@ impl Client {
     where
         T: crate::aio::RedisRuntime,
     {
-        let con = self.get_simple_async_connection::<T>().await?;
-        crate::aio::MultiplexedConnection::new(&self.connection_info.redis, con).await
+        let (mut connection, driver) = self
+            .connect_any_async(|info| async move {
+                let con = get_simple_async_connection::<T>(&info).await?;
+                crate::aio::MultiplexedConnection::with_config(&info.redis, con).await
+            })
+            .await?;
+        connection.set_response_timeout(self.config.response_timeout);
+        connection.set_client(self.clone());
+        Ok((connection, driver))
     }
 
-    async fn get_simple_async_connection<T>(
+    /// Returns a multiplexed connection and its driver, leaving it to the caller to spawn the
+    /// driver on the located runtime.
+    #[cfg(feature = "aio")]
+    pub(crate) async fn create_multiplexed_async_connection(
         &self,
-    ) -> RedisResult<Pin<Box<dyn crate::aio::AsyncStream + Send + Sync>>>
-    where
-        T: crate::aio::RedisRuntime,
-    {
-        Ok(crate::aio::connect_simple::<T>(&self.connection_info)
-            .await?
-            .boxed())
+    ) -> RedisResult<(
+        crate::aio::MultiplexedConnection,
+        futures_util::future::BoxFuture<'static, ()>,
+    )> {
+        use futures_util::FutureExt;
+        match Runtime::locate() {
+            #[cfg(feature = "tokio-comp")]
+            Runtime::Tokio => {
+                let (connection, driver) = self
+                    .create_multiplexed_async_connection_inner::<crate::aio::tokio::Tokio>()
+                    .await?;
+                Ok((connection, driver.boxed()))
+            }
+            #[cfg(feature = "async-std-comp")]
+            Runtime::AsyncStd => {
+                let (connection, driver) = self
+                    .create_multiplexed_async_connection_inner::<crate::aio::async_std::AsyncStd>()
+                    .await?;
+                Ok((connection, driver.boxed()))
+            }
+        }
     }
 
     #[cfg(feature = "connection-manager")]
-    pub(crate) fn connection_info(&self) -> &ConnectionInfo {
-        &self.connection_info
+    pub(crate) fn config(&self) -> &ConnectionConfig {
+        &self.config
     }
+
+    /// Returns a client connecting with the given password instead, which shares the address
+    /// last connected to with this one.
+    #[cfg(feature = "connection-manager")]
+    pub(crate) fn with_password(&self, password: String) -> Client {
+        let mut config = self.config.clone();
+        config.redis.password = Some(password);
+        Client {
+            connection_info: config.info(),
+            config,
+            last_connected: self.last_connected.clone(),
+        }
+    }
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+async fn get_simple_async_connection<T>(
+    connection_info: &ConnectionConfig,
+) -> RedisResult<Pin<Box<dyn crate::aio::AsyncStream + Send + Sync>>>
+where
+    T: crate::aio::RedisRuntime,
+{
+    Ok(crate::aio::connect_simple::<T>(connection_info)
+        .await?
+        .boxed())
 }
 
 #[cfg(feature = "aio")]
@@ -395,4 +524,32 @@ mod test {
     fn regression_293_parse_ipv6_with_interface() {
         assert!(Client::open(("fe80::cafe:beef%eno1", 6379)).is_ok());
     }
+
+    #[test]
+    fn test_fallback_addrs_start_with_last_connected() {
+        let client = crate::ConnectionBuilder::tcp("first", 6379)
+            .fallback_addr(crate::ConnectionAddr::Tcp("second".to_string(), 6379))
+            .build()
+            .unwrap();
+        let hosts = |client: &Client| {
+            client
+                .candidates()
+                .map(|(_, info)| {
+                    assert!(info.fallback_addrs.is_empty());
+                    info.addr.to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(hosts(&client), ["first:6379", "second:6379"]);
+
+        let result = client.connect_any(|info| match info.addr.to_string().as_str() {
+            "second:6379" => Ok(()),
+            _ => Err(crate::RedisError::from((
+                crate::ErrorKind::IoError,
+                "unreachable",
+            ))),
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(hosts(&client.clone()), ["second:6379", "first:6379"]);
+    }
 }
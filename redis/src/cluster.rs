@@ -188,7 +188,7 @@ impl Connect for Connection {
     where
         T: IntoConnectionInfo,
     {
-        connect(&info.into_connection_info()?, timeout)
+        connect(&info.into_connection_builder()?.into_config()?, timeout)
     }
 
     fn send_packed_command(&mut self, cmd: &[u8]) -> RedisResult<()> {
@@ -271,6 +271,27 @@ where
     Ok(())
 }
 
+/// Parses the arguments of a packed command off the front of `bytes`.
+pub(crate) fn parse_packed_command<'a>(bytes: &mut &'a [u8]) -> Option<Vec<&'a [u8]>> {
+    let count = parse_length(bytes, b'*')?;
+    (0..count)
+        .map(|_| {
+            let len = parse_length(bytes, b'$')?;
+            let arg = bytes.get(..len)?;
+            *bytes = bytes.get(len..)?.strip_prefix(b"\r\n")?;
+            Some(arg)
+        })
+        .collect()
+}
+
+fn parse_length(bytes: &mut &[u8], prefix: u8) -> Option<usize> {
+    let rest = bytes.strip_prefix(&[prefix])?;
+    let end = rest.windows(2).position(|window| window == b"\r\n")?;
+    let len = std::str::from_utf8(&rest[..end]).ok()?.parse().ok()?;
+    *bytes = &rest[end + 2..];
+    Some(len)
+}
+
 impl RedisWrite for Cmd {
     fn write_arg(&mut self, arg: &[u8]) {
         self.data.extend_from_slice(arg);
@@ -610,6 +631,7 @@ pub fn pipe() -> Pipeline {
 #[cfg(feature = "cluster")]
 mod tests {
     use super::Cmd;
+    use crate::IterArgs;
 
     #[test]
     fn test_cmd_arg_idx() {
@@ -626,4 +648,20 @@ mod tests {
         assert_eq!(c.arg_idx(3), None);
         assert_eq!(c.arg_idx(4), None);
     }
+
+    #[test]
+    fn test_cmd_iter_args() {
+        let keys = ["a", "b", "c"];
+        let mut c = Cmd::new();
+        c.arg("DEL")
+            .arg(IterArgs(keys.iter().filter(|key| **key != "b")));
+        assert_eq!(
+            c.get_packed_command(),
+            b"*3\r\n$3\r\nDEL\r\n$1\r\na\r\n$1\r\nc\r\n"
+        );
+
+        let mut c = Cmd::new();
+        c.arg("DEL").arg(IterArgs(keys.iter().skip(3)));
+        assert_eq!(c.get_packed_command(), b"*1\r\n$3\r\nDEL\r\n");
+    }
 }
@@ -0,0 +1,285 @@
+//! Client-side validation of commands against the `COMMAND` reply of the server, enabled per
+//! connection with [`ConnectionBuilder::validate_commands`](crate::ConnectionBuilder::validate_commands).
+
+use std::collections::HashMap;
+
+use crate::cmd::{cmd, parse_packed_command, Cmd};
+use crate::types::{ErrorKind, RedisError, RedisResult, Value};
+
+/// The arity and key positions of the commands known to a server.
+pub(crate) struct CommandTable {
+    commands: HashMap<String, CommandSpec>,
+}
+
+struct CommandSpec {
+    /// The number of arguments including the command name, or its negated minimum.
+    arity: i64,
+    first_key: i64,
+    last_key: i64,
+    step: i64,
+    /// Specs of subcommands like `config|get`, which servers before 7.0 do not report.
+    subcommands: HashMap<String, CommandSpec>,
+}
+
+/// Returns the command whose reply is parsed by [`CommandTable::from_reply`].
+pub(crate) fn command_table_cmd() -> Cmd {
+    cmd("COMMAND")
+}
+
+/// Builds the table from the result of sending [`command_table_cmd`] while setting up a
+/// connection.
+///
+/// If the server refuses `COMMAND`, e.g. because it is renamed or the server is a proxy, or its
+/// reply can't be parsed, validation is unavailable and `None` is returned instead of failing
+/// the connection.  Only errors that leave the connection unusable are returned.
+pub(crate) fn load_command_table(result: RedisResult<Value>) -> RedisResult<Option<CommandTable>> {
+    let table = result.and_then(|reply| CommandTable::from_reply(&reply));
+    match table {
+        Ok(table) => Ok(Some(table)),
+        Err(err) if err.is_unrecoverable_error() => Err(err),
+        Err(_err) => {
+            #[cfg(feature = "debug-logging")]
+            log::debug!("Command validation is unavailable: {_err}");
+            Ok(None)
+        }
+    }
+}
+
+impl CommandTable {
+    /// Parses the reply to `COMMAND`.
+    pub(crate) fn from_reply(reply: &Value) -> RedisResult<CommandTable> {
+        match reply {
+            Value::Bulk(items) => Ok(CommandTable {
+                commands: parse_specs(items)?,
+            }),
+            _ => Err(invalid_reply()),
+        }
+    }
+
+    /// Checks each command packed into `bytes`, as it is written to the connection.
+    ///
+    /// Bytes that can't be parsed as packed commands, e.g. inline commands passed to
+    /// `send_raw`, are passed from there on.
+    pub(crate) fn validate_packed(&self, mut bytes: &[u8]) -> RedisResult<()> {
+        while !bytes.is_empty() {
+            match parse_packed_command(&mut bytes) {
+                Some(args) => self.validate(&args)?,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the arity and key positions of a command, passing commands the server did not
+    /// report, e.g. ones that are renamed or loaded from modules later on.
+    fn validate(&self, args: &[&[u8]]) -> RedisResult<()> {
+        let name = match args.first() {
+            Some(name) => String::from_utf8_lossy(name).to_lowercase(),
+            None => return Ok(()),
+        };
+        let mut spec = match self.commands.get(&name) {
+            Some(spec) => spec,
+            None => return Ok(()),
+        };
+        let mut full_name = name.to_uppercase();
+        if let Some(subcommand) = args.get(1) {
+            let subcommand = String::from_utf8_lossy(subcommand).to_lowercase();
+            if let Some(sub) = spec.subcommands.get(&format!("{name}|{subcommand}")) {
+                spec = sub;
+                full_name = format!("{full_name} {}", subcommand.to_uppercase());
+            }
+        }
+        spec.validate(&full_name, args.len() as i64)
+    }
+}
+
+impl CommandSpec {
+    fn validate(&self, name: &str, len: i64) -> RedisResult<()> {
+        let expected = if self.arity >= 0 && len != self.arity {
+            format!("exactly {}", self.arity)
+        } else if self.arity < 0 && len < -self.arity {
+            format!("at least {}", -self.arity)
+        } else {
+            String::new()
+        };
+        if !expected.is_empty() {
+            return Err(RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "Wrong number of arguments",
+                format!(
+                    "{name} takes {expected} arguments including the command name, but got {len}"
+                ),
+            )));
+        }
+
+        // A negative last key counts from the end, so only fixed positions can be missing.
+        if self.first_key > 0 && self.last_key >= self.first_key && self.step > 0 {
+            let mut position = self.first_key;
+            while position <= self.last_key {
+                if position >= len {
+                    return Err(RedisError::from((
+                        ErrorKind::InvalidClientConfig,
+                        "Missing key argument",
+                        format!(
+                            "{name} expects a key at argument {position}, but got {len} arguments"
+                        ),
+                    )));
+                }
+                position += self.step;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_specs(items: &[Value]) -> RedisResult<HashMap<String, CommandSpec>> {
+    items
+        .iter()
+        .map(|item| parse_spec(item).ok_or_else(invalid_reply))
+        .collect()
+}
+
+/// Parses one command from a `COMMAND` reply: its name, arity, flags, first key, last key and
+/// step, followed by further details on newer servers, of which only the subcommands are used.
+fn parse_spec(item: &Value) -> Option<(String, CommandSpec)> {
+    let fields = match item {
+        Value::Bulk(fields) => fields,
+        _ => return None,
+    };
+    let name = match fields.first()? {
+        Value::Data(name) => String::from_utf8_lossy(name).to_lowercase(),
+        Value::Status(name) => name.to_lowercase(),
+        _ => return None,
+    };
+    let int = |index: usize| match fields.get(index)? {
+        Value::Int(value) => Some(*value),
+        _ => None,
+    };
+    let subcommands = match fields.get(9) {
+        Some(Value::Bulk(items)) => parse_specs(items).ok()?,
+        _ => HashMap::new(),
+    };
+    Some((
+        name,
+        CommandSpec {
+            arity: int(1)?,
+            first_key: int(3)?,
+            last_key: int(4)?,
+            step: int(5)?,
+            subcommands,
+        },
+    ))
+}
+
+fn invalid_reply() -> RedisError {
+    RedisError::from((
+        ErrorKind::TypeError,
+        "Response was of incompatible type",
+        "unexpected reply to COMMAND".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, arity: i64, keys: (i64, i64, i64), subcommands: Vec<Value>) -> Value {
+        Value::Bulk(vec![
+            Value::Data(name.as_bytes().to_vec()),
+            Value::Int(arity),
+            Value::Bulk(vec![]),
+            Value::Int(keys.0),
+            Value::Int(keys.1),
+            Value::Int(keys.2),
+            Value::Bulk(vec![]),
+            Value::Bulk(vec![]),
+            Value::Bulk(vec![]),
+            Value::Bulk(subcommands),
+        ])
+    }
+
+    fn check(table: &CommandTable, command: &Cmd) -> RedisResult<()> {
+        table.validate_packed(&command.get_packed_command())
+    }
+
+    #[test]
+    fn test_validate_commands() {
+        let table = CommandTable::from_reply(&Value::Bulk(vec![
+            spec("get", 2, (1, 1, 1), vec![]),
+            spec("mset", -3, (1, -1, 2), vec![]),
+            spec("custom", -1, (1, 1, 1), vec![]),
+            spec(
+                "config",
+                -2,
+                (0, 0, 0),
+                vec![spec("config|get", -3, (0, 0, 0), vec![])],
+            ),
+        ]))
+        .unwrap();
+
+        assert!(check(&table, cmd("GET").arg("key")).is_ok());
+        assert!(check(&table, cmd("MSET").arg("a").arg(1).arg("b").arg(2)).is_ok());
+        assert!(check(&table, cmd("CONFIG").arg("GET").arg("maxmemory")).is_ok());
+        assert!(check(&table, &cmd("UNKNOWN")).is_ok());
+
+        let err = check(&table, cmd("get").arg("a").arg("b")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidClientConfig);
+        assert_eq!(
+            err.detail(),
+            Some("GET takes exactly 2 arguments including the command name, but got 3")
+        );
+        let err = check(&table, &cmd("MSET")).unwrap_err();
+        assert_eq!(
+            err.detail(),
+            Some("MSET takes at least 3 arguments including the command name, but got 1")
+        );
+        let err = check(&table, cmd("CONFIG").arg("GET")).unwrap_err();
+        assert_eq!(
+            err.detail(),
+            Some("CONFIG GET takes at least 3 arguments including the command name, but got 2")
+        );
+
+        let err = check(&table, &cmd("CUSTOM")).unwrap_err();
+        assert_eq!(
+            err.detail(),
+            Some("CUSTOM expects a key at argument 1, but got 1 arguments")
+        );
+
+        assert!(CommandTable::from_reply(&Value::Okay).is_err());
+    }
+
+    #[test]
+    fn test_validate_packed_pipeline() {
+        let table = CommandTable::from_reply(&Value::Bulk(vec![spec("get", 2, (1, 1, 1), vec![])]))
+            .unwrap();
+
+        let valid = crate::pipe().get("a").get("b").get_packed_pipeline();
+        assert!(table.validate_packed(&valid).is_ok());
+        let invalid = crate::pipe()
+            .get("a")
+            .add_command(cmd("GET"))
+            .get_packed_pipeline();
+        let err = table.validate_packed(&invalid).unwrap_err();
+        assert_eq!(
+            err.detail(),
+            Some("GET takes exactly 2 arguments including the command name, but got 1")
+        );
+
+        // Inline commands can't be checked.
+        assert!(table.validate_packed(b"GET\r\n").is_ok());
+    }
+
+    #[test]
+    fn test_load_command_table() {
+        let reply = Value::Bulk(vec![spec("get", 2, (1, 1, 1), vec![])]);
+        assert!(load_command_table(Ok(reply)).unwrap().is_some());
+
+        // A refused or unexpected reply only makes validation unavailable.
+        let refused = RedisError::from((ErrorKind::ResponseError, "An error was signalled"));
+        assert!(load_command_table(Err(refused)).unwrap().is_none());
+        assert!(load_command_table(Ok(Value::Okay)).unwrap().is_none());
+
+        let io_error = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        assert!(load_command_table(Err(io_error.into())).is_err());
+    }
+}
@@ -46,6 +46,42 @@ macro_rules! implement_commands {
                     { Cmd::$name($($argname),*).query(self) }
             )*
 
+            /// Delete all keys yielded by an iterator and return how many were removed.
+            ///
+            /// Large key sets are split into several `DEL` commands sent in a single
+            /// pipeline.  No command is sent if the iterator is empty.
+            #[inline]
+            fn del_all<I: IntoIterator<Item = K>, K: ToRedisArgs>(&mut self, keys: I) -> RedisResult<usize> {
+                match crate::commands::del_all_pipeline(keys) {
+                    Some(pipeline) => {
+                        let counts: Vec<usize> = pipeline.query(self)?;
+                        Ok(counts.into_iter().sum())
+                    }
+                    None => Ok(0),
+                }
+            }
+
+            /// Wait until at least `num_replicas` replicas acknowledged all writes previously
+            /// sent on this connection, using `WAIT`, and return how many did.
+            ///
+            /// Fails with a `ResponseError` if fewer replicas acknowledged the writes within
+            /// `timeout`; a zero `timeout` blocks forever.  The server may block for up to
+            /// `timeout` before replying, so the read timeout of the connection has to be
+            /// longer than that, otherwise the request fails with a `TimedOut` I/O error first.
+            #[inline]
+            fn wait(&mut self, num_replicas: usize, timeout: std::time::Duration) -> RedisResult<usize> {
+                let acked: usize = crate::commands::wait_cmd(num_replicas, timeout).query(self)?;
+                crate::commands::replicas_acked(num_replicas, acked)
+            }
+
+            /// Kill the client connections matching `filter` with `CLIENT KILL`, and return how
+            /// many were killed.
+            #[inline]
+            fn client_kill(&mut self, filter: crate::commands::ClientKillFilter) -> RedisResult<usize> {
+                let reply = crate::commands::client_kill_cmd(filter).query(self)?;
+                crate::commands::killed_clients(reply)
+            }
+
             /// Incrementally iterate the keys space.
             #[inline]
             fn scan<RV: FromRedisValue>(&mut self) -> RedisResult<Iter<'_, RV>> {
@@ -62,6 +98,14 @@ macro_rules! implement_commands {
                 c.iter(self)
             }
 
+            /// Incrementally iterate the keys space with the given [`ScanOptions`](crate::ScanOptions).
+            #[inline]
+            fn scan_options<RV: FromRedisValue>(&mut self, opts: crate::commands::ScanOptions) -> RedisResult<Iter<'_, RV>> {
+                let mut c = cmd("SCAN");
+                c.cursor_arg(0).arg(opts);
+                c.iter(self)
+            }
+
             /// Incrementally iterate hash fields and associated values.
             #[inline]
             fn hscan<K: ToRedisArgs, RV: FromRedisValue>(&mut self, key: K) -> RedisResult<Iter<'_, RV>> {
@@ -70,6 +114,19 @@ macro_rules! implement_commands {
                 c.iter(self)
             }
 
+            /// Incrementally iterate hash fields and associated values as
+            /// `(field, value)` pairs.
+            ///
+            /// Every batch returned by the server holds whole pairs; a batch
+            /// with an odd number of elements ends the iteration.
+            #[inline]
+            fn hscan_pairs<K: ToRedisArgs, F: FromRedisValue, V: FromRedisValue>
+                    (&mut self, key: K) -> RedisResult<Iter<'_, (F, V)>> {
+                let mut c = cmd("HSCAN");
+                c.arg(key).cursor_arg(0);
+                c.iter(self)
+            }
+
             /// Incrementally iterate hash fields and associated values for
             /// field names matching a pattern.
             #[inline]
@@ -170,6 +227,54 @@ macro_rules! implement_commands {
                 }
             )*
 
+            /// Delete all keys yielded by an iterator and return how many were removed.
+            ///
+            /// Large key sets are split into several `DEL` commands sent in a single
+            /// pipeline.  No command is sent if the iterator is empty.
+            #[inline]
+            fn del_all<I: IntoIterator<Item = K>, K: ToRedisArgs>(&mut self, keys: I) -> crate::types::RedisFuture<'_, usize> {
+                let pipeline = crate::commands::del_all_pipeline(keys);
+                Box::pin(async move {
+                    match pipeline {
+                        Some(pipeline) => {
+                            let counts: Vec<usize> = pipeline.query_async(self).await?;
+                            Ok(counts.into_iter().sum())
+                        }
+                        None => Ok(0),
+                    }
+                })
+            }
+
+            /// Wait until at least `num_replicas` replicas acknowledged all writes previously
+            /// sent on this connection, using `WAIT`, and return how many did.
+            ///
+            /// Fails with a `ResponseError` if fewer replicas acknowledged the writes within
+            /// `timeout`; a zero `timeout` blocks forever.  The server may block for up to
+            /// `timeout` before replying, so the response timeout of the connection has to be
+            /// longer than that, otherwise the request fails with a `TimedOut` I/O error first.
+            /// Note that on a multiplexed connection, the writes of all its clones are waited for.
+            #[inline]
+            fn wait(&mut self, num_replicas: usize, timeout: std::time::Duration) -> crate::types::RedisFuture<'_, usize> {
+                Box::pin(async move {
+                    let acked: usize = crate::commands::wait_cmd(num_replicas, timeout)
+                        .query_async(self)
+                        .await?;
+                    crate::commands::replicas_acked(num_replicas, acked)
+                })
+            }
+
+            /// Kill the client connections matching `filter` with `CLIENT KILL`, and return how
+            /// many were killed.
+            #[inline]
+            fn client_kill(&mut self, filter: crate::commands::ClientKillFilter) -> crate::types::RedisFuture<'_, usize> {
+                Box::pin(async move {
+                    let reply = crate::commands::client_kill_cmd(filter)
+                        .query_async(self)
+                        .await?;
+                    crate::commands::killed_clients(reply)
+                })
+            }
+
             /// Incrementally iterate the keys space.
             #[inline]
             fn scan<RV: FromRedisValue>(&mut self) -> crate::types::RedisFuture<crate::cmd::AsyncIter<'_, RV>> {
@@ -186,6 +291,14 @@ macro_rules! implement_commands {
                 Box::pin(async move { c.iter_async(self).await })
             }
 
+            /// Incrementally iterate the keys space with the given [`ScanOptions`](crate::ScanOptions).
+            #[inline]
+            fn scan_options<RV: FromRedisValue>(&mut self, opts: crate::commands::ScanOptions) -> crate::types::RedisFuture<'_, crate::cmd::AsyncIter<'_, RV>> {
+                let mut c = cmd("SCAN");
+                c.cursor_arg(0).arg(opts);
+                Box::pin(async move { c.iter_async(self).await })
+            }
+
             /// Incrementally iterate hash fields and associated values.
             #[inline]
             fn hscan<K: ToRedisArgs, RV: FromRedisValue>(&mut self, key: K) -> crate::types::RedisFuture<crate::cmd::AsyncIter<'_, RV>> {
@@ -194,6 +307,19 @@ macro_rules! implement_commands {
                 Box::pin(async move {c.iter_async(self).await })
             }
 
+            /// Incrementally iterate hash fields and associated values as
+            /// `(field, value)` pairs.
+            ///
+            /// Every batch returned by the server holds whole pairs; a batch
+            /// with an odd number of elements ends the iteration.
+            #[inline]
+            fn hscan_pairs<K: ToRedisArgs, F: FromRedisValue, V: FromRedisValue>
+                    (&mut self, key: K) -> crate::types::RedisFuture<'_, crate::cmd::AsyncIter<'_, (F, V)>> {
+                let mut c = cmd("HSCAN");
+                c.arg(key).cursor_arg(0);
+                Box::pin(async move {c.iter_async(self).await })
+            }
+
             /// Incrementally iterate hash fields and associated values for
             /// field names matching a pattern.
             #[inline]
@@ -3,7 +3,7 @@
 use crate::cmd::{cmd, Cmd, Iter};
 use crate::connection::{Connection, ConnectionLike, Msg};
 use crate::pipeline::Pipeline;
-use crate::types::{FromRedisValue, NumericBehavior, RedisResult, ToRedisArgs, RedisWrite, Expiry, SetExpiry, ExistenceCheck};
+use crate::types::{FromRedisValue, NumericBehavior, RedisResult, ToRedisArgs, RedisWrite, Expiry, SetExpiry, ExistenceCheck, UpdateCheck};
 
 #[macro_use]
 mod macros;
@@ -30,6 +30,66 @@ use crate::streams;
 #[cfg(feature = "acl")]
 use crate::acl;
 
+/// The maximum number of keys sent in a single `DEL` by `del_all`.
+const DEL_ALL_CHUNK_SIZE: usize = 1000;
+
+/// Builds a pipeline deleting the given keys in chunks, or `None` if there are no keys.
+pub(crate) fn del_all_pipeline<I: IntoIterator<Item = K>, K: ToRedisArgs>(keys: I) -> Option<Pipeline> {
+    let mut pipeline = Pipeline::new();
+    let mut chunk = cmd("DEL");
+    let mut chunk_len = 0;
+    let mut empty = true;
+    for key in keys {
+        chunk.arg(key);
+        chunk_len += 1;
+        empty = false;
+        if chunk_len == DEL_ALL_CHUNK_SIZE {
+            pipeline.add_command(std::mem::replace(&mut chunk, cmd("DEL")));
+            chunk_len = 0;
+        }
+    }
+    if chunk_len > 0 {
+        pipeline.add_command(chunk);
+    }
+    (!empty).then_some(pipeline)
+}
+
+/// Builds the `WAIT` command used by `wait`.  A zero timeout blocks forever.
+pub(crate) fn wait_cmd(num_replicas: usize, timeout: std::time::Duration) -> Cmd {
+    let mut c = cmd("WAIT");
+    c.arg(num_replicas).arg(timeout.as_millis() as u64);
+    c
+}
+
+/// Builds the `CLIENT KILL` command used by `client_kill`.
+pub(crate) fn client_kill_cmd(filter: ClientKillFilter) -> Cmd {
+    let mut c = cmd("CLIENT");
+    c.arg("KILL").arg(filter);
+    c
+}
+
+/// Converts the reply to `CLIENT KILL` into the number of killed clients.  Servers reply with
+/// the count to filters, but with `OK` to the old form taking only an address.
+pub(crate) fn killed_clients(reply: crate::types::Value) -> RedisResult<usize> {
+    match reply {
+        crate::types::Value::Okay => Ok(1),
+        reply => crate::types::from_redis_value(&reply),
+    }
+}
+
+/// Checks the reply to `WAIT`, failing if fewer than `num_replicas` replicas acknowledged the
+/// writes.
+pub(crate) fn replicas_acked(num_replicas: usize, acked: usize) -> RedisResult<usize> {
+    if acked < num_replicas {
+        fail!((
+            crate::types::ErrorKind::ResponseError,
+            "Not enough replicas acknowledged the writes",
+            format!("{acked} of {num_replicas} replicas acknowledged")
+        ));
+    }
+    Ok(acked)
+}
+
 #[cfg(feature = "cluster")]
 pub(crate) fn is_readonly_cmd(cmd: &[u8]) -> bool {
     matches!(
@@ -38,6 +98,34 @@ pub(crate) fn is_readonly_cmd(cmd: &[u8]) -> bool {
     )
 }
 
+/// Commands that may wait for data, or for replicas to acknowledge writes, before replying, and
+/// commands after which the connection no longer receives regular replies.
+#[cfg(feature = "aio")]
+const BLOCKING_COMMANDS: &[&[u8]] = &[
+    b"BLMOVE", b"BLMPOP", b"BLPOP", b"BRPOP", b"BRPOPLPUSH", b"BZMPOP", b"BZPOPMAX", b"BZPOPMIN",
+    b"WAIT", b"WAITAOF", b"SUBSCRIBE", b"PSUBSCRIBE", b"SSUBSCRIBE", b"MONITOR",
+];
+
+/// Returns true for commands that may block the connection until data is available, including
+/// `XREAD` and `XREADGROUP` with the `BLOCK` option, and for commands that switch the
+/// connection into a mode in which it only receives messages, like `SUBSCRIBE` and `MONITOR`.
+#[cfg(feature = "aio")]
+pub(crate) fn is_blocking_cmd(cmd: &Cmd) -> bool {
+    let mut args = cmd.args_iter().filter_map(|arg| match arg {
+        crate::cmd::Arg::Simple(arg) => Some(arg.to_ascii_uppercase()),
+        crate::cmd::Arg::Cursor => None,
+    });
+    let name = match args.next() {
+        Some(name) => name,
+        None => return false,
+    };
+    if name == b"XREAD" || name == b"XREADGROUP" {
+        // Options come before the keys, which follow `STREAMS`.
+        return args.take_while(|arg| arg != b"STREAMS").any(|arg| arg == b"BLOCK");
+    }
+    BLOCKING_COMMANDS.contains(&&name[..])
+}
+
 implement_commands! {
     'a
     // most common operations
@@ -105,11 +193,13 @@ implement_commands! {
     }
 
     /// Get a range of bytes/substring from the value of a key. Negative values provide an offset from the end of the value.
+    /// Read it as `Vec<u8>` if the range may not be valid UTF-8.
     fn getrange<K: ToRedisArgs>(key: K, from: isize, to: isize) {
         cmd("GETRANGE").arg(key).arg(from).arg(to)
     }
 
-    /// Overwrite the part of the value stored in key at the specified offset.
+    /// Overwrite the part of the value stored in key at the specified offset, returning the new
+    /// length of the value.
     fn setrange<K: ToRedisArgs, V: ToRedisArgs>(key: K, offset: isize, value: V) {
         cmd("SETRANGE").arg(key).arg(offset).arg(value)
     }
@@ -125,6 +215,8 @@ implement_commands! {
     }
 
     /// Determine the type of a key.
+    ///
+    /// The reply can be converted into a [`KeyType`](crate::KeyType).
     fn key_type<K: ToRedisArgs>(key: K) {
         cmd("TYPE").arg(key)
     }
@@ -134,36 +226,91 @@ implement_commands! {
         cmd("EXPIRE").arg(key).arg(seconds)
     }
 
+    /// Set a key's time to live in seconds, only if `condition` holds.  Returns whether the
+    /// time to live was set.
+    ///
+    /// Conditions require Redis 7.0; with `None` no condition is sent, so older servers work
+    /// as well.
+    fn expire_options<K: ToRedisArgs>(key: K, seconds: usize, condition: Option<ExpireCondition>) {
+        cmd("EXPIRE").arg(key).arg(seconds).arg(condition)
+    }
+
     /// Set the expiration for a key as a UNIX timestamp.
     fn expire_at<K: ToRedisArgs>(key: K, ts: usize) {
         cmd("EXPIREAT").arg(key).arg(ts)
     }
 
+    /// Set the expiration for a key as a UNIX timestamp, only if `condition` holds.  Returns
+    /// whether the expiration was set.
+    ///
+    /// Conditions require Redis 7.0; with `None` no condition is sent.
+    fn expire_at_options<K: ToRedisArgs>(key: K, ts: usize, condition: Option<ExpireCondition>) {
+        cmd("EXPIREAT").arg(key).arg(ts).arg(condition)
+    }
+
     /// Set a key's time to live in milliseconds.
     fn pexpire<K: ToRedisArgs>(key: K, ms: usize) {
         cmd("PEXPIRE").arg(key).arg(ms)
     }
 
+    /// Set a key's time to live in milliseconds, only if `condition` holds.  Returns whether
+    /// the time to live was set.
+    ///
+    /// Conditions require Redis 7.0; with `None` no condition is sent, so older servers work
+    /// as well.
+    fn pexpire_options<K: ToRedisArgs>(key: K, ms: usize, condition: Option<ExpireCondition>) {
+        cmd("PEXPIRE").arg(key).arg(ms).arg(condition)
+    }
+
     /// Set the expiration for a key as a UNIX timestamp in milliseconds.
     fn pexpire_at<K: ToRedisArgs>(key: K, ts: usize) {
         cmd("PEXPIREAT").arg(key).arg(ts)
     }
 
-    /// Remove the expiration from a key.
+    /// Set the expiration for a key as a UNIX timestamp in milliseconds, only if `condition`
+    /// holds.  Returns whether the expiration was set.
+    ///
+    /// Conditions require Redis 7.0; with `None` no condition is sent.
+    fn pexpire_at_options<K: ToRedisArgs>(key: K, ts: usize, condition: Option<ExpireCondition>) {
+        cmd("PEXPIREAT").arg(key).arg(ts).arg(condition)
+    }
+
+    /// Remove the expiration from a key.  Returns whether an expiration was removed.
     fn persist<K: ToRedisArgs>(key: K) {
         cmd("PERSIST").arg(key)
     }
 
     /// Get the expiration time of a key.
+    ///
+    /// Read it as [`Ttl`](crate::Ttl) to tell a key without expiry and a missing key apart,
+    /// instead of checking for `-1` and `-2`.
     fn ttl<K: ToRedisArgs>(key: K) {
         cmd("TTL").arg(key)
     }
 
     /// Get the expiration time of a key in milliseconds.
+    ///
+    /// Read it as `Ttl<Millis>` to tell a key without expiry and a missing key apart.
     fn pttl<K: ToRedisArgs>(key: K) {
         cmd("PTTL").arg(key)
     }
 
+    /// Get the time a key expires at, as a UNIX timestamp in seconds.
+    ///
+    /// Read it as `Ttl<Seconds<SystemTime>>` to tell a key without expiry and a missing key
+    /// apart.  This requires Redis 7.0.
+    fn expire_time<K: ToRedisArgs>(key: K) {
+        cmd("EXPIRETIME").arg(key)
+    }
+
+    /// Get the time a key expires at, as a UNIX timestamp in milliseconds.
+    ///
+    /// Read it as `Ttl<Millis<SystemTime>>` to tell a key without expiry and a missing key
+    /// apart.  This requires Redis 7.0.
+    fn pexpire_time<K: ToRedisArgs>(key: K) {
+        cmd("PEXPIRETIME").arg(key)
+    }
+
     /// Get the value of a key and set expiration
     fn get_ex<K: ToRedisArgs>(key: K, expire_at: Expiry) {
         let (option, time_arg) = match expire_at {
@@ -177,6 +324,12 @@ implement_commands! {
         cmd("GETEX").arg(key).arg(option).arg(time_arg)
     }
 
+    /// Get the value of a key with `GETEX` but without any option, which leaves its expiration
+    /// as it is and only returns the value, just like `GET` does.
+    fn get_ex_unchanged<K: ToRedisArgs>(key: K) {
+        cmd("GETEX").arg(key)
+    }
+
     /// Get the value of a key and delete it
     fn get_del<K: ToRedisArgs>(key: K) {
         cmd("GETDEL").arg(key)
@@ -192,6 +345,26 @@ implement_commands! {
         cmd("RENAMENX").arg(key).arg(new_key)
     }
 
+    /// Copy the value of a key to another key, returning whether it was copied.
+    fn copy<S: ToRedisArgs, D: ToRedisArgs>(source: S, destination: D, options: CopyOptions) {
+        cmd("COPY").arg(source).arg(destination).arg(options)
+    }
+
+    /// Serialize the value of a key, e.g. to move it to another instance with `restore`.
+    ///
+    /// The serialization is binary, so read it as `Option<Vec<u8>>`, which is `None` if the key
+    /// does not exist.
+    fn dump<K: ToRedisArgs>(key: K) {
+        cmd("DUMP").arg(key)
+    }
+
+    /// Create a key from a value serialized with `dump`, with a time to live in milliseconds,
+    /// or without one if `ttl` is `0`.  Fails with a `BUSYKEY` error if the key exists, unless
+    /// [`RestoreOptions::replace`] is set.
+    fn restore<K: ToRedisArgs, V: ToRedisArgs>(key: K, ttl: usize, serialized: V, options: RestoreOptions) {
+        cmd("RESTORE").arg(key).arg(ttl).arg(serialized).arg(options)
+    }
+
     /// Unlink one or more keys.
     fn unlink<K: ToRedisArgs>(key: K) {
         cmd("UNLINK").arg(key)
@@ -199,7 +372,7 @@ implement_commands! {
 
     // common string operations
 
-    /// Append a value to a key.
+    /// Append a value to a key, returning the new length of the value.
     fn append<K: ToRedisArgs, V: ToRedisArgs>(key: K, value: V) {
         cmd("APPEND").arg(key).arg(value)
     }
@@ -239,6 +412,17 @@ implement_commands! {
         cmd("BITCOUNT").arg(key).arg(start).arg(end)
     }
 
+    /// Count set bits in a string in a range of bytes or bits, which may count from the end.
+    fn bitcount_in<K: ToRedisArgs>(key: K, range: BitRange) {
+        cmd("BITCOUNT").arg(key).arg(range)
+    }
+
+    /// Returns the position of the first bit set to `bit` in a string, optionally only looking
+    /// at a range of bytes or bits.  Returns `-1` if there is no such bit.
+    fn bitpos<K: ToRedisArgs>(key: K, bit: bool, range: Option<BitRange>) {
+        cmd("BITPOS").arg(key).arg(i32::from(bit)).arg(range)
+    }
+
     /// Perform a bitwise AND between multiple keys (containing string values)
     /// and store the result in the destination key.
     fn bit_and<D: ToRedisArgs, S: ToRedisArgs>(dstkey: D, srckeys: S) {
@@ -344,11 +528,21 @@ implement_commands! {
     }
 
     /// Remove and get the first element in a list, or block until one is available.
+    ///
+    /// The reply is nil if the timeout elapses, so read it as e.g. `Option<(String, V)>`.
+    /// A response timeout shorter than `timeout` fails the request instead; see
+    /// [`Connection::blpop_timeout`](crate::Connection::blpop_timeout) and its counterpart on
+    /// `aio::Connection`.
     fn blpop<K: ToRedisArgs>(key: K, timeout: usize) {
         cmd("BLPOP").arg(key).arg(timeout)
     }
 
     /// Remove and get the last element in a list, or block until one is available.
+    ///
+    /// The reply is nil if the timeout elapses, so read it as e.g. `Option<(String, V)>`.
+    /// A response timeout shorter than `timeout` fails the request instead; see
+    /// [`Connection::brpop_timeout`](crate::Connection::brpop_timeout) and its counterpart on
+    /// `aio::Connection`.
     fn brpop<K: ToRedisArgs>(key: K, timeout: usize) {
         cmd("BRPOP").arg(key).arg(timeout)
     }
@@ -549,6 +743,19 @@ implement_commands! {
         cmd("ZADD").arg(key).arg(items)
     }
 
+    /// Add one member to a sorted set, or update its score, with options.
+    ///
+    /// With [`SortedSetAddOptions::increment`](crate::SortedSetAddOptions::increment), the new
+    /// score of the member is returned, or nil if the update was skipped.
+    fn zadd_options<K: ToRedisArgs, S: ToRedisArgs, M: ToRedisArgs>(key: K, member: M, score: S, options: SortedSetAddOptions) {
+        cmd("ZADD").arg(key).arg(options).arg(score).arg(member)
+    }
+
+    /// Add multiple members to a sorted set, or update their scores, with options.
+    fn zadd_multiple_options<K: ToRedisArgs, S: ToRedisArgs, M: ToRedisArgs>(key: K, items: &'a [(S, M)], options: SortedSetAddOptions) {
+        cmd("ZADD").arg(key).arg(options).arg(items)
+    }
+
     /// Get the number of members in a sorted set.
     fn zcard<K: ToRedisArgs>(key: K) {
         cmd("ZCARD").arg(key)
@@ -676,6 +883,9 @@ implement_commands! {
     }
 
     /// Return a range of members in a sorted set, by index with scores.
+    ///
+    /// The members and their scores can be received as `Vec<ScoredMember>`, see
+    /// [`ScoredMember`](crate::ScoredMember), or as `Vec<(String, f64)>` with RESP2.
     fn zrange_withscores<K: ToRedisArgs>(key: K, start: isize, stop: isize) {
         cmd("ZRANGE").arg(key).arg(start).arg(stop).arg("WITHSCORES")
     }
@@ -710,6 +920,9 @@ implement_commands! {
     }
 
     /// Return a range of members in a sorted set, by score with scores.
+    ///
+    /// The members and their scores can be received as `Vec<ScoredMember>`, see
+    /// [`ScoredMember`](crate::ScoredMember), or as `Vec<(String, f64)>` with RESP2.
     fn zrangebyscore_withscores<K: ToRedisArgs, M: ToRedisArgs, MM: ToRedisArgs>(key: K, min: M, max: MM) {
         cmd("ZRANGEBYSCORE").arg(key).arg(min).arg(max).arg("WITHSCORES")
     }
@@ -870,6 +1083,8 @@ implement_commands! {
     // Object commands
 
     /// Returns the encoding of a key.
+    ///
+    /// The reply can be converted into an [`ObjectEncoding`](crate::ObjectEncoding).
     fn object_encoding<K: ToRedisArgs>(key: K) {
         cmd("OBJECT").arg("ENCODING").arg(key)
     }
@@ -880,6 +1095,8 @@ implement_commands! {
     }
 
     /// Returns the logarithmic access frequency counter of a key.
+    ///
+    /// This fails with the server's error unless an LFU `maxmemory-policy` is configured.
     fn object_freq<K: ToRedisArgs>(key: K) {
         cmd("OBJECT").arg("FREQ").arg(key)
     }
@@ -889,6 +1106,55 @@ implement_commands! {
         cmd("OBJECT").arg("REFCOUNT").arg(key)
     }
 
+    // Memory commands
+
+    /// Returns the number of bytes a key and its value take in memory, or nil if the key doesn't
+    /// exist.  For nested types, `samples` limits how many elements are sampled to estimate the
+    /// size; `Some(0)` samples all of them.
+    fn memory_usage<K: ToRedisArgs>(key: K, samples: Option<usize>) {
+        cmd("MEMORY").arg("USAGE").arg(key).arg(samples.map(|samples| ("SAMPLES", samples)))
+    }
+
+    // Server commands
+
+    /// Returns information and statistics about the server.  The reply can be parsed
+    /// into an [`InfoDict`](crate::InfoDict), which splits it into sections and has
+    /// typed accessors for common fields like `used_memory`.
+    fn info<>() {
+        &mut cmd("INFO")
+    }
+
+    /// Like [`info`](Self::info), but only returns the given sections, e.g. `"memory"`.
+    fn info_sections<S: ToRedisArgs>(sections: S) {
+        cmd("INFO").arg(sections)
+    }
+
+    /// Returns a random key of the current database, or nil if it is empty.
+    fn random_key<>() {
+        &mut cmd("RANDOMKEY")
+    }
+
+    /// Returns the number of keys in the current database.
+    fn db_size<>() {
+        &mut cmd("DBSIZE")
+    }
+
+    /// Swaps the contents of two databases, which all connections using them see at once.
+    fn swap_db<>(db1: i64, db2: i64) {
+        cmd("SWAPDB").arg(db1).arg(db2)
+    }
+
+    /// Suspends the commands of all clients for the given number of milliseconds, or only the
+    /// ones that may write with [`ClientPauseMode::Write`](crate::ClientPauseMode::Write).  The mode requires Redis 6.2.
+    fn client_pause<>(milliseconds: usize, mode: Option<ClientPauseMode>) {
+        cmd("CLIENT").arg("PAUSE").arg(milliseconds).arg(mode)
+    }
+
+    /// Resumes the clients paused with `CLIENT PAUSE` before the pause ends.
+    fn client_unpause<>() {
+        cmd("CLIENT").arg("UNPAUSE")
+    }
+
     // ACL commands
 
     /// When Redis is configured to use an ACL file (with the aclfile
@@ -1211,6 +1477,36 @@ implement_commands! {
             .arg(options)
     }
 
+    /// Search for members within an area, given by its center and shape.
+    ///
+    /// Every item in the result can be read with [`geo::SearchResult`], which contains the
+    /// distance, geohash and coordinate of the member if they were requested in the options.
+    ///
+    /// ```rust,no_run
+    /// use redis::{Commands, RedisResult};
+    /// use redis::geo::{SearchCenter, SearchOptions, SearchResult, SearchShape, Unit};
+    ///
+    /// fn search(con: &mut redis::Connection) -> RedisResult<Vec<SearchResult>> {
+    ///     let center = SearchCenter::member("Palermo");
+    ///     let shape = SearchShape::Radius(200.0, Unit::Kilometers);
+    ///     con.geo_search("my_gis", center, shape, SearchOptions::default().with_coord())
+    /// }
+    /// ```
+    #[cfg(feature = "geospatial")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "geospatial")))]
+    fn geo_search<K: ToRedisArgs>(
+        key: K,
+        center: geo::SearchCenter,
+        shape: geo::SearchShape,
+        options: geo::SearchOptions
+    ) {
+        cmd("GEOSEARCH")
+            .arg(key)
+            .arg(center)
+            .arg(shape)
+            .arg(options)
+    }
+
     //
     // streams commands
     //
@@ -1981,6 +2277,64 @@ impl PubSubCommands for Connection {
     }
 }
 
+/// A range of a string for the [BITCOUNT](https://redis.io/commands/bitcount) and
+/// [BITPOS](https://redis.io/commands/bitpos) commands
+///
+/// Both ends are inclusive, and negative indices count from the end of the string.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use redis::{BitRange, Commands, RedisResult};
+/// fn flags_in_last_byte(con: &mut redis::Connection, key: &str) -> RedisResult<usize> {
+///     con.bitcount_in(key, BitRange::bytes(-1, -1))
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitRange {
+    start: i64,
+    end: i64,
+    bits: bool,
+}
+
+impl BitRange {
+    /// A range of bytes.
+    pub fn bytes(start: i64, end: i64) -> Self {
+        BitRange {
+            start,
+            end,
+            bits: false,
+        }
+    }
+
+    /// A range of bits.  This requires Redis 7.0.
+    pub fn bits(start: i64, end: i64) -> Self {
+        BitRange {
+            start,
+            end,
+            bits: true,
+        }
+    }
+}
+
+impl ToRedisArgs for BitRange {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        out.write_arg_fmt(self.start);
+        out.write_arg_fmt(self.end);
+        // Byte ranges leave the unit out, as older servers don't accept it.
+        if self.bits {
+            out.write_arg(b"BIT");
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
+
 /// Options for the [LPOS](https://redis.io/commands/lpos) command
 ///
 /// # Example
@@ -2055,6 +2409,291 @@ impl ToRedisArgs for LposOptions {
     }
 }
 
+/// Options for the [SCAN](https://redis.io/commands/scan) command
+///
+/// The options are sent with every cursor iteration, not just the first one.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use redis::{Commands, RedisResult, ScanOptions};
+/// fn fetch_user_keys(con: &mut redis::Connection) -> RedisResult<Vec<String>> {
+///     let opts = ScanOptions::default()
+///         .match_pattern("user:*")
+///         .count(500)
+///         .key_type("hash");
+///     let iter = con.scan_options::<String>(opts)?;
+///     Ok(iter.collect())
+/// }
+/// ```
+#[derive(Default)]
+pub struct ScanOptions {
+    pattern: Option<Vec<u8>>,
+    count: Option<usize>,
+    key_type: Option<Vec<u8>>,
+}
+
+impl ScanOptions {
+    /// Only return keys matching the given glob-style pattern.
+    pub fn match_pattern<P: ToRedisArgs>(mut self, pattern: P) -> Self {
+        self.pattern = pattern.to_redis_args().into_iter().next();
+        self
+    }
+
+    /// Hint the server to do about N units of work per cursor iteration.
+    pub fn count(mut self, n: usize) -> Self {
+        self.count = Some(n);
+        self
+    }
+
+    /// Only return keys of the given type, such as `string`, `list` or `hash`.
+    pub fn key_type<T: ToRedisArgs>(mut self, key_type: T) -> Self {
+        self.key_type = key_type.to_redis_args().into_iter().next();
+        self
+    }
+}
+
+impl ToRedisArgs for ScanOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        if let Some(ref pattern) = self.pattern {
+            out.write_arg(b"MATCH");
+            out.write_arg(pattern);
+        }
+
+        if let Some(n) = self.count {
+            out.write_arg(b"COUNT");
+            out.write_arg_fmt(n);
+        }
+
+        if let Some(ref key_type) = self.key_type {
+            out.write_arg(b"TYPE");
+            out.write_arg(key_type);
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
+
+/// Options for the [COPY](https://redis.io/commands/copy) command
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use redis::{Commands, RedisResult, CopyOptions};
+/// fn backup(con: &mut redis::Connection, key: &str) -> RedisResult<bool> {
+///     let opts = CopyOptions::default().db(1).replace(true);
+///     con.copy(key, key, opts)
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CopyOptions {
+    db: Option<i64>,
+    replace: bool,
+}
+
+impl CopyOptions {
+    /// Copy the key into the given database instead of the current one.
+    pub fn db(mut self, db: i64) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// Overwrite the destination key if it exists.
+    pub fn replace(mut self, replace: bool) -> Self {
+        self.replace = replace;
+        self
+    }
+}
+
+impl ToRedisArgs for CopyOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        if let Some(db) = self.db {
+            out.write_arg(b"DB");
+            out.write_arg_fmt(db);
+        }
+
+        if self.replace {
+            out.write_arg(b"REPLACE");
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
+
+/// Options for the [RESTORE](https://redis.io/commands/restore) command
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use redis::{Commands, RedisResult, RestoreOptions};
+/// fn migrate(
+///     from: &mut redis::Connection,
+///     to: &mut redis::Connection,
+///     key: &str,
+/// ) -> RedisResult<()> {
+///     let serialized: Option<Vec<u8>> = from.dump(key)?;
+///     if let Some(serialized) = serialized {
+///         let opts = RestoreOptions::default().replace(true);
+///         to.restore::<_, _, ()>(key, 0, serialized, opts)?;
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RestoreOptions {
+    replace: bool,
+    abs_ttl: bool,
+    eviction: Option<Eviction>,
+}
+
+impl RestoreOptions {
+    /// Overwrite the key if it exists.
+    pub fn replace(mut self, replace: bool) -> Self {
+        self.replace = replace;
+        self
+    }
+
+    /// Read the time to live as the UNIX timestamp in milliseconds the key expires at.
+    pub fn abs_ttl(mut self, abs_ttl: bool) -> Self {
+        self.abs_ttl = abs_ttl;
+        self
+    }
+
+    /// Set the idle time or access frequency of the key, used by the eviction policies.
+    pub fn eviction(mut self, eviction: Eviction) -> Self {
+        self.eviction = Some(eviction);
+        self
+    }
+}
+
+impl ToRedisArgs for RestoreOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        if self.replace {
+            out.write_arg(b"REPLACE");
+        }
+
+        if self.abs_ttl {
+            out.write_arg(b"ABSTTL");
+        }
+
+        if let Some(ref eviction) = self.eviction {
+            eviction.write_redis_args(out);
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
+
+/// The eviction information of a key restored with [RESTORE](https://redis.io/commands/restore)
+///
+/// Only one of them can be sent, matching the eviction policy the server is configured with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Eviction {
+    /// IDLETIME -- The idle time of the key in seconds, used by the LRU eviction policies.
+    IdleTime(u64),
+    /// FREQ -- The access frequency of the key, used by the LFU eviction policies.
+    Freq(u8),
+}
+
+impl ToRedisArgs for Eviction {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        match *self {
+            Eviction::IdleTime(seconds) => {
+                out.write_arg(b"IDLETIME");
+                out.write_arg_fmt(seconds);
+            }
+            Eviction::Freq(freq) => {
+                out.write_arg(b"FREQ");
+                out.write_arg_fmt(freq);
+            }
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
+
+/// The condition under which `EXPIRE`, `PEXPIRE`, `EXPIREAT` and `PEXPIREAT` set a time to live
+///
+/// `XX` can be combined with `GT` or `LT` through their `xx` field, while `NX` is mutually
+/// exclusive with all of them, so there is no way to combine it with another condition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpireCondition {
+    /// NX -- Only set the time to live if the key has none.
+    NX,
+    /// XX -- Only set the time to live if the key already has one.
+    XX,
+    /// GT -- Only set the time to live if it is greater than the current one.  A key without
+    /// time to live counts as infinite, so it is never updated.
+    ///
+    /// With `xx`, `XX` is sent as well.
+    GT {
+        /// Only set the time to live if the key already has one.
+        xx: bool,
+    },
+    /// LT -- Only set the time to live if it is less than the current one.  A key without time
+    /// to live counts as infinite, so it is always updated.
+    ///
+    /// With `xx`, `XX` is sent as well, which leaves keys without time to live untouched.
+    LT {
+        /// Only set the time to live if the key already has one.
+        xx: bool,
+    },
+}
+
+impl ToRedisArgs for ExpireCondition {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        match *self {
+            ExpireCondition::NX => out.write_arg(b"NX"),
+            ExpireCondition::XX => out.write_arg(b"XX"),
+            ExpireCondition::GT { xx } => {
+                if xx {
+                    out.write_arg(b"XX");
+                }
+                out.write_arg(b"GT");
+            }
+            ExpireCondition::LT { xx } => {
+                if xx {
+                    out.write_arg(b"XX");
+                }
+                out.write_arg(b"LT");
+            }
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        matches!(
+            self,
+            ExpireCondition::NX
+                | ExpireCondition::XX
+                | ExpireCondition::GT { xx: false }
+                | ExpireCondition::LT { xx: false }
+        )
+    }
+}
+
 /// Enum for the LEFT | RIGHT args used by some commands
 pub enum Direction {
     /// Targets the first element (head) of the list
@@ -2076,6 +2715,92 @@ impl ToRedisArgs for Direction {
     }
 }
 
+/// Options for the [ZADD](https://redis.io/commands/zadd) command
+///
+/// # Example
+/// ```rust,no_run
+/// use redis::{Commands, ExistenceCheck, RedisResult, SortedSetAddOptions, UpdateCheck};
+/// fn raise_score(con: &mut redis::Connection, member: &str, score: f64) -> RedisResult<usize> {
+///     let opts = SortedSetAddOptions::default()
+///         .conditional_set(ExistenceCheck::XX)
+///         .conditional_update(UpdateCheck::GT)
+///         .include_changed(true);
+///     con.zadd_options("leaderboard", member, score, opts)
+/// }
+/// ```
+#[derive(Default)]
+pub struct SortedSetAddOptions {
+    conditional_set: Option<ExistenceCheck>,
+    conditional_update: Option<UpdateCheck>,
+    include_changed: bool,
+    increment: bool,
+}
+
+impl SortedSetAddOptions {
+    /// Set the existence check for the ZADD command
+    pub fn conditional_set(mut self, existence_check: ExistenceCheck) -> Self {
+        self.conditional_set = Some(existence_check);
+        self
+    }
+
+    /// Set the update check for the scores of existing members
+    pub fn conditional_update(mut self, update_check: UpdateCheck) -> Self {
+        self.conditional_update = Some(update_check);
+        self
+    }
+
+    /// Set the CH option, which returns the number of added and updated members instead of
+    /// only the added ones
+    pub fn include_changed(mut self, include_changed: bool) -> Self {
+        self.include_changed = include_changed;
+        self
+    }
+
+    /// Set the INCR option, which increments the score of a single member instead of setting it
+    pub fn increment(mut self, increment: bool) -> Self {
+        self.increment = increment;
+        self
+    }
+}
+
+impl ToRedisArgs for SortedSetAddOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        if let Some(ref conditional_set) = self.conditional_set {
+            match conditional_set {
+                ExistenceCheck::NX => {
+                    out.write_arg(b"NX");
+                }
+                ExistenceCheck::XX => {
+                    out.write_arg(b"XX");
+                }
+            }
+        }
+        if let Some(ref conditional_update) = self.conditional_update {
+            match conditional_update {
+                UpdateCheck::GT => {
+                    out.write_arg(b"GT");
+                }
+                UpdateCheck::LT => {
+                    out.write_arg(b"LT");
+                }
+            }
+        }
+        if self.include_changed {
+            out.write_arg(b"CH");
+        }
+        if self.increment {
+            out.write_arg(b"INCR");
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
+
 /// Options for the [SET](https://redis.io/commands/set) command
 ///
 /// # Example
@@ -2163,3 +2888,225 @@ impl ToRedisArgs for SetOptions {
         }
     }
 }
+
+/// The clients suspended by `CLIENT PAUSE`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClientPauseMode {
+    /// WRITE -- Only suspend clients sending commands that may write.
+    Write,
+    /// ALL -- Suspend all clients.
+    All,
+}
+
+impl ToRedisArgs for ClientPauseMode {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        let s: &[u8] = match self {
+            ClientPauseMode::Write => b"WRITE",
+            ClientPauseMode::All => b"ALL",
+        };
+        out.write_arg(s);
+    }
+}
+
+/// The type of client connections, as used by `CLIENT KILL` and `CLIENT LIST`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClientType {
+    /// Regular clients.
+    Normal,
+    /// Replicas connected to this server.
+    Replica,
+    /// The connection to the master of this server.
+    Master,
+    /// Clients subscribed to channels.
+    PubSub,
+}
+
+impl ToRedisArgs for ClientType {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        let s: &[u8] = match self {
+            ClientType::Normal => b"normal",
+            ClientType::Replica => b"replica",
+            ClientType::Master => b"master",
+            ClientType::PubSub => b"pubsub",
+        };
+        out.write_arg(s);
+    }
+}
+
+/// Filters for the [CLIENT KILL](https://redis.io/commands/client-kill) command
+///
+/// Clients matching all of the given filters are killed.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use redis::{ClientKillFilter, ClientType, Commands, RedisResult};
+/// fn kill_pubsub_clients(con: &mut redis::Connection) -> RedisResult<usize> {
+///     let filter = ClientKillFilter::default()
+///         .client_type(ClientType::PubSub)
+///         .skip_me(true);
+///     con.client_kill(filter)
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ClientKillFilter {
+    id: Option<u64>,
+    client_type: Option<ClientType>,
+    addr: Option<String>,
+    laddr: Option<String>,
+    skip_me: Option<bool>,
+}
+
+impl ClientKillFilter {
+    /// Kill the client with the given id, as returned by `CLIENT ID`.
+    pub fn id(mut self, id: u64) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Kill clients of the given type.
+    pub fn client_type(mut self, client_type: ClientType) -> Self {
+        self.client_type = Some(client_type);
+        self
+    }
+
+    /// Kill the client connected from the given `ip:port` address.
+    pub fn addr<T: Into<String>>(mut self, addr: T) -> Self {
+        self.addr = Some(addr.into());
+        self
+    }
+
+    /// Kill clients connected to the given local `ip:port` address of the server.
+    pub fn laddr<T: Into<String>>(mut self, laddr: T) -> Self {
+        self.laddr = Some(laddr.into());
+        self
+    }
+
+    /// Whether to kill the client sending the command if it matches.  The server skips it
+    /// unless told otherwise.
+    pub fn skip_me(mut self, skip_me: bool) -> Self {
+        self.skip_me = Some(skip_me);
+        self
+    }
+}
+
+impl ToRedisArgs for ClientKillFilter {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        if let Some(id) = self.id {
+            out.write_arg(b"ID");
+            out.write_arg_fmt(id);
+        }
+
+        if let Some(client_type) = self.client_type {
+            out.write_arg(b"TYPE");
+            client_type.write_redis_args(out);
+        }
+
+        if let Some(ref addr) = self.addr {
+            out.write_arg(b"ADDR");
+            out.write_arg(addr.as_bytes());
+        }
+
+        if let Some(ref laddr) = self.laddr {
+            out.write_arg(b"LADDR");
+            out.write_arg(laddr.as_bytes());
+        }
+
+        if let Some(skip_me) = self.skip_me {
+            out.write_arg(b"SKIPME");
+            out.write_arg(if skip_me { b"yes" } else { b"no" });
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
+
+/// Options for the [CLIENT TRACKING](https://redis.io/commands/client-tracking) command, as
+/// used by [`MultiplexedConnection::enable_tracking`](crate::aio::MultiplexedConnection::enable_tracking)
+///
+/// By default, the server tracks the keys read by the connection and sends invalidations when
+/// they are modified.
+#[derive(Clone, Debug, Default)]
+pub struct TrackingOptions {
+    bcast: bool,
+    prefixes: Vec<Vec<u8>>,
+    optin: bool,
+    optout: bool,
+    noloop: bool,
+}
+
+impl TrackingOptions {
+    /// Sends invalidations for all modified keys matching the prefixes, instead of tracking the
+    /// keys read by the connection.
+    pub fn bcast(mut self) -> Self {
+        self.bcast = true;
+        self
+    }
+
+    /// Adds a key prefix to broadcast invalidations for.  Only used with `bcast`.
+    pub fn prefix<T: ToRedisArgs>(mut self, prefix: T) -> Self {
+        self.prefixes.push(prefix.to_redis_args().concat());
+        self
+    }
+
+    /// Only tracks the keys read by the command following `CLIENT CACHING yes`.
+    pub fn optin(mut self) -> Self {
+        self.optin = true;
+        self
+    }
+
+    /// Doesn't track the keys read by the command following `CLIENT CACHING no`.
+    pub fn optout(mut self) -> Self {
+        self.optout = true;
+        self
+    }
+
+    /// Doesn't send invalidations for keys modified by the connection itself.
+    pub fn noloop(mut self) -> Self {
+        self.noloop = true;
+        self
+    }
+}
+
+impl ToRedisArgs for TrackingOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        if self.bcast {
+            out.write_arg(b"BCAST");
+        }
+
+        for prefix in &self.prefixes {
+            out.write_arg(b"PREFIX");
+            out.write_arg(prefix);
+        }
+
+        if self.optin {
+            out.write_arg(b"OPTIN");
+        }
+
+        if self.optout {
+            out.write_arg(b"OPTOUT");
+        }
+
+        if self.noloop {
+            out.write_arg(b"NOLOOP");
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
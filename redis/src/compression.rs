@@ -0,0 +1,407 @@
+//! Transparent compression of large values written with `SET`-like commands, see
+//! [`CompressingConnection`].
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::cmd::{pack_command, parse_packed_command};
+#[cfg(feature = "aio")]
+use crate::cmd::{Arg, Cmd};
+use crate::connection::ConnectionLike;
+use crate::types::{RedisResult, Value};
+
+/// Tags compressed values, so that they can be told apart from values written uncompressed.
+const HEADER: &[u8] = b"\0RSZ";
+
+/// A compression algorithm for [`CompressingConnection`], e.g. implemented with a crate like
+/// `lz4_flex` or `zstd`.
+pub trait Compressor: Send + Sync {
+    /// Compresses a value.
+    fn compress(&self, data: &[u8]) -> Vec<u8>;
+
+    /// Restores a value compressed with [`compress`](Self::compress).
+    fn decompress(&self, data: &[u8]) -> RedisResult<Vec<u8>>;
+}
+
+/// A connection which compresses large values written with `SET`-like commands, and
+/// decompresses them when they are read.
+///
+/// Values of `SET`, `SETNX`, `SETEX`, `PSETEX`, `GETSET`, `MSET`, `MSETNX`, `HSET`, `HSETNX` and
+/// `HMSET` which are at least `threshold` bytes long are compressed, unless that doesn't make
+/// them smaller.  Compressed values are prefixed with a short header, and values with this
+/// header are decompressed in all responses, wherever they occur.  Other values pass through
+/// untouched, so values written by clients which don't compress can be read as well.  Values
+/// of other commands, like `APPEND` or `SETRANGE`, are never compressed, since the server needs
+/// to understand them.
+///
+/// This works with both synchronous and asynchronous connections:
+///
+/// ```rust,no_run
+/// # fn do_something() -> redis::RedisResult<()> {
+/// use redis::{Commands, CompressingConnection, Compressor, RedisResult};
+///
+/// struct Lz4;
+///
+/// impl Compressor for Lz4 {
+///     fn compress(&self, data: &[u8]) -> Vec<u8> {
+///         // e.g. lz4_flex::compress_prepend_size(data)
+///         # data.to_vec()
+///     }
+///
+///     fn decompress(&self, data: &[u8]) -> RedisResult<Vec<u8>> {
+///         // e.g. lz4_flex::decompress_size_prepended(data)
+///         # Ok(data.to_vec())
+///     }
+/// }
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = CompressingConnection::new(client.get_connection()?, Lz4, 1024);
+/// let _: () = con.set("document", "x".repeat(4096))?;
+/// let document: String = con.get("document")?;
+/// # Ok(()) }
+/// ```
+pub struct CompressingConnection<C> {
+    inner: C,
+    compressor: Arc<dyn Compressor>,
+    threshold: usize,
+}
+
+impl<C> CompressingConnection<C> {
+    /// Wraps a connection, compressing values of at least `threshold` bytes with `compressor`.
+    pub fn new<T: Compressor + 'static>(inner: C, compressor: T, threshold: usize) -> Self {
+        CompressingConnection {
+            inner,
+            compressor: Arc::new(compressor),
+            threshold,
+        }
+    }
+
+    /// Returns a reference to the wrapped connection.
+    pub fn get_ref(&self) -> &C {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped connection.  Values sent through it directly
+    /// are not compressed.
+    pub fn get_mut(&mut self) -> &mut C {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped connection.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    /// Returns the arguments of a command with its values compressed, or `None` if no value was
+    /// compressed.
+    fn compress_args(&self, args: &[&[u8]]) -> Option<Vec<Vec<u8>>> {
+        let name = args.first()?.to_ascii_uppercase();
+        let mut compressed = false;
+        let args = args
+            .iter()
+            .enumerate()
+            .map(|(index, arg)| {
+                if arg.len() >= self.threshold && is_value(&name, index) {
+                    let mut value = HEADER.to_vec();
+                    value.extend(self.compressor.compress(arg));
+                    if value.len() < arg.len() {
+                        compressed = true;
+                        return value;
+                    }
+                }
+                arg.to_vec()
+            })
+            .collect();
+        compressed.then_some(args)
+    }
+
+    /// Compresses the values of packed commands, leaving them as they are if nothing was
+    /// compressed or they can't be parsed.
+    fn compress_packed<'a>(&self, bytes: &'a [u8]) -> Cow<'a, [u8]> {
+        let mut rest = bytes;
+        let mut packed = Vec::with_capacity(bytes.len());
+        let mut compressed = false;
+        while !rest.is_empty() {
+            let start = rest;
+            let args = match parse_packed_command(&mut rest) {
+                Some(args) => args,
+                None => return Cow::Borrowed(bytes),
+            };
+            match self.compress_args(&args) {
+                Some(args) => {
+                    compressed = true;
+                    packed.extend(pack_command(&args));
+                }
+                None => packed.extend_from_slice(&start[..start.len() - rest.len()]),
+            }
+        }
+        if compressed {
+            Cow::Owned(packed)
+        } else {
+            Cow::Borrowed(bytes)
+        }
+    }
+
+    /// Returns the command with its values compressed, or `None` if nothing was compressed.
+    #[cfg(feature = "aio")]
+    fn compress_cmd(&self, cmd: &Cmd) -> Option<Cmd> {
+        let args = cmd
+            .args_iter()
+            .map(|arg| match arg {
+                Arg::Simple(arg) => Some(arg),
+                Arg::Cursor => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let mut compressed = Cmd::new();
+        for arg in self.compress_args(&args)? {
+            compressed.arg(arg);
+        }
+        Some(compressed)
+    }
+
+    /// Decompresses all values with the header in a response.
+    fn decompress(&self, value: Value) -> RedisResult<Value> {
+        Ok(match value {
+            Value::Data(data) => match data.strip_prefix(HEADER) {
+                Some(compressed) => Value::Data(self.compressor.decompress(compressed)?),
+                None => Value::Data(data),
+            },
+            Value::Bulk(items) => Value::Bulk(
+                items
+                    .into_iter()
+                    .map(|item| self.decompress(item))
+                    .collect::<RedisResult<_>>()?,
+            ),
+            value => value,
+        })
+    }
+}
+
+/// Returns whether the argument at `index` of the command `name` is a value that may be stored
+/// compressed.
+fn is_value(name: &[u8], index: usize) -> bool {
+    match name {
+        b"SET" | b"SETNX" | b"GETSET" => index == 2,
+        b"SETEX" | b"PSETEX" | b"HSETNX" => index == 3,
+        b"MSET" | b"MSETNX" => index >= 2 && index % 2 == 0,
+        b"HSET" | b"HMSET" => index >= 3 && index % 2 == 1,
+        _ => false,
+    }
+}
+
+impl<C: ConnectionLike> ConnectionLike for CompressingConnection<C> {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        let cmd = self.compress_packed(cmd);
+        let value = self.inner.req_packed_command(&cmd)?;
+        self.decompress(value)
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        let cmd = self.compress_packed(cmd);
+        let values = self.inner.req_packed_commands(&cmd, offset, count)?;
+        values
+            .into_iter()
+            .map(|value| self.decompress(value))
+            .collect()
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.get_db()
+    }
+
+    fn supports_pipelining(&self) -> bool {
+        self.inner.supports_pipelining()
+    }
+
+    fn check_connection(&mut self) -> bool {
+        self.inner.check_connection()
+    }
+
+    fn is_open(&self) -> bool {
+        self.inner.is_open()
+    }
+}
+
+#[cfg(feature = "aio")]
+impl<C: crate::aio::ConnectionLike + Send> crate::aio::ConnectionLike for CompressingConnection<C> {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> crate::RedisFuture<'a, Value> {
+        Box::pin(async move {
+            let value = match self.compress_cmd(cmd) {
+                Some(cmd) => self.inner.req_packed_command(&cmd).await?,
+                None => self.inner.req_packed_command(cmd).await?,
+            };
+            self.decompress(value)
+        })
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a crate::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> crate::RedisFuture<'a, Vec<Value>> {
+        Box::pin(async move {
+            let mut pipeline = cmd.clone();
+            for cmd in pipeline.commands_mut() {
+                if let Some(compressed) = self.compress_cmd(cmd) {
+                    *cmd = compressed;
+                }
+            }
+            let values = self
+                .inner
+                .req_packed_commands(&pipeline, offset, count)
+                .await?;
+            values
+                .into_iter()
+                .map(|value| self.decompress(value))
+                .collect()
+        })
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.get_db()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{cmd, pipe};
+
+    // Run-length encodes bytes as pairs of a count and the repeated byte.
+    struct RunLength;
+
+    impl Compressor for RunLength {
+        fn compress(&self, data: &[u8]) -> Vec<u8> {
+            let mut out = Vec::new();
+            for &byte in data {
+                match out.len() {
+                    len if len >= 2 && out[len - 1] == byte && out[len - 2] < u8::MAX => {
+                        out[len - 2] += 1;
+                    }
+                    _ => out.extend([1, byte]),
+                }
+            }
+            out
+        }
+
+        fn decompress(&self, data: &[u8]) -> RedisResult<Vec<u8>> {
+            Ok(data
+                .chunks(2)
+                .flat_map(|pair| std::iter::repeat(pair[1]).take(pair[0] as usize))
+                .collect())
+        }
+    }
+
+    // Records the requests and replies with the last value that was set.
+    #[derive(Default)]
+    struct Recorder {
+        requests: Vec<Vec<u8>>,
+    }
+
+    impl ConnectionLike for Recorder {
+        fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+            self.requests.push(cmd.to_vec());
+            let mut rest = cmd;
+            let args = parse_packed_command(&mut rest).unwrap();
+            Ok(Value::Data(args.last().unwrap().to_vec()))
+        }
+
+        fn req_packed_commands(
+            &mut self,
+            cmd: &[u8],
+            _offset: usize,
+            count: usize,
+        ) -> RedisResult<Vec<Value>> {
+            self.requests.push(cmd.to_vec());
+            Ok(vec![Value::Okay; count])
+        }
+
+        fn get_db(&self) -> i64 {
+            0
+        }
+
+        fn check_connection(&mut self) -> bool {
+            true
+        }
+
+        fn is_open(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_compression() {
+        let mut con = CompressingConnection::new(Recorder::default(), RunLength, 8);
+        let large = "a".repeat(100);
+
+        // The value is compressed on the way out, and decompressed on the way back.
+        let value: String = cmd("SET").arg("key").arg(&large).query(&mut con).unwrap();
+        assert_eq!(value, large);
+        let mut expected = HEADER.to_vec();
+        expected.extend([100, b'a']);
+        assert_eq!(
+            con.get_ref().requests[0],
+            cmd("SET").arg("key").arg(&expected).get_packed_command()
+        );
+
+        // Small values, values which don't shrink and other arguments are left as they are.
+        let incompressible = "abcdefghijklmnop";
+        for command in [
+            cmd("SET").arg("key").arg("small").clone(),
+            cmd("SET").arg("key").arg(incompressible).clone(),
+            cmd("APPEND").arg("key").arg(&large).clone(),
+            cmd("SET").arg(&large).arg("small").clone(),
+        ] {
+            let _: Value = command.query(&mut con).unwrap();
+            assert_eq!(
+                con.get_ref().requests.last().unwrap(),
+                &command.get_packed_command()
+            );
+        }
+
+        let _: () = pipe()
+            .cmd("MSET")
+            .arg("a")
+            .arg("small")
+            .arg("b")
+            .arg(&large)
+            .cmd("HSET")
+            .arg("hash")
+            .arg(&large)
+            .arg(&large)
+            .query(&mut con)
+            .unwrap();
+        assert_eq!(
+            con.get_ref().requests.last().unwrap(),
+            &pipe()
+                .cmd("MSET")
+                .arg("a")
+                .arg("small")
+                .arg("b")
+                .arg(&expected)
+                .cmd("HSET")
+                .arg("hash")
+                .arg(&large)
+                .arg(&expected)
+                .get_packed_pipeline()
+        );
+
+        let nested = Value::Bulk(vec![
+            Value::Data(expected.clone()),
+            Value::Data(b"plain".to_vec()),
+        ]);
+        assert_eq!(
+            con.decompress(nested).unwrap(),
+            Value::Bulk(vec![
+                Value::Data(large.into_bytes()),
+                Value::Data(b"plain".to_vec())
+            ])
+        );
+    }
+}
@@ -4,16 +4,17 @@ use std::net::{self, SocketAddr, TcpStream, ToSocketAddrs};
 use std::ops::DerefMut;
 use std::path::PathBuf;
 use std::str::{from_utf8, FromStr};
+use std::sync::Arc;
 use std::time::Duration;
 
-use crate::cmd::{cmd, pipe, Cmd};
+use crate::cmd::{cmd, pipe, Arg, Cmd};
+use crate::command_table::{command_table_cmd, load_command_table, CommandTable};
 use crate::parser::Parser;
 use crate::pipeline::Pipeline;
 use crate::types::{
     from_redis_value, ErrorKind, FromRedisValue, RedisError, RedisResult, ToRedisArgs, Value,
 };
 
-#[cfg(unix)]
 use crate::types::HashMap;
 #[cfg(unix)]
 use std::os::unix::net::UnixStream;
@@ -24,7 +25,7 @@ use native_tls::{TlsConnector, TlsStream};
 #[cfg(feature = "tls-rustls")]
 use rustls::{RootCertStore, StreamOwned};
 #[cfg(feature = "tls-rustls")]
-use std::{convert::TryInto, sync::Arc};
+use std::convert::TryInto;
 
 #[cfg(feature = "tls-rustls-webpki-roots")]
 use rustls::OwnedTrustAnchor;
@@ -37,8 +38,16 @@ use rustls_native_certs::load_native_certs;
 static DEFAULT_PORT: u16 = 6379;
 
 #[inline(always)]
-fn connect_tcp(addr: (&str, u16)) -> io::Result<TcpStream> {
-    let socket = TcpStream::connect(addr)?;
+fn connect_tcp(
+    addr: &SocketAddr,
+    timeout: Option<Duration>,
+    local_addr: Option<SocketAddr>,
+) -> io::Result<TcpStream> {
+    let socket = match (local_addr, timeout) {
+        (Some(local_addr), _) => connect_tcp_from(addr, local_addr, timeout)?,
+        (None, Some(timeout)) => TcpStream::connect_timeout(addr, timeout)?,
+        (None, None) => TcpStream::connect(addr)?,
+    };
     #[cfg(feature = "tcp_nodelay")]
     socket.set_nodelay(true)?;
     #[cfg(feature = "keep-alive")]
@@ -56,23 +65,79 @@ fn connect_tcp(addr: (&str, u16)) -> io::Result<TcpStream> {
     }
 }
 
-#[inline(always)]
-fn connect_tcp_timeout(addr: &SocketAddr, timeout: Duration) -> io::Result<TcpStream> {
-    let socket = TcpStream::connect_timeout(addr, timeout)?;
-    #[cfg(feature = "tcp_nodelay")]
-    socket.set_nodelay(true)?;
-    #[cfg(feature = "keep-alive")]
-    {
-        //For now rely on system defaults
-        const KEEP_ALIVE: socket2::TcpKeepalive = socket2::TcpKeepalive::new();
-        //these are useless error that not going to happen
-        let socket2: socket2::Socket = socket.into();
-        socket2.set_tcp_keepalive(&KEEP_ALIVE)?;
-        Ok(socket2.into())
+/// Connects from the given local address, binding the socket before connecting.
+fn connect_tcp_from(
+    addr: &SocketAddr,
+    local_addr: SocketAddr,
+    timeout: Option<Duration>,
+) -> io::Result<TcpStream> {
+    let socket = socket2::Socket::new(
+        socket2::Domain::for_address(*addr),
+        socket2::Type::STREAM,
+        Some(socket2::Protocol::TCP),
+    )?;
+    socket
+        .bind(&local_addr.into())
+        .map_err(|err| bind_error(local_addr, err))?;
+    match timeout {
+        Some(timeout) => socket.connect_timeout(&(*addr).into(), timeout)?,
+        None => socket.connect(&(*addr).into())?,
+    }
+    Ok(socket.into())
+}
+
+/// Adds the local address to an error returned when binding a socket to it.
+pub(crate) fn bind_error(local_addr: SocketAddr, err: io::Error) -> io::Error {
+    io::Error::new(
+        err.kind(),
+        format!("failed to bind to local address {local_addr}: {err}"),
+    )
+}
+
+/// Tries each address the host resolves to in turn, returning the first successful connection.
+fn connect_tcp_host(
+    host: &str,
+    port: u16,
+    timeout: Option<Duration>,
+    local_addr: Option<SocketAddr>,
+) -> RedisResult<TcpStream> {
+    let mut errors = Vec::new();
+    for addr in (host, port).to_socket_addrs()? {
+        match connect_tcp(&addr, timeout, local_addr) {
+            Ok(tcp) => return Ok(tcp),
+            Err(e) => errors.push((addr, e.into())),
+        }
     }
-    #[cfg(not(feature = "keep-alive"))]
-    {
-        Ok(socket)
+    Err(aggregate_connect_errors(errors))
+}
+
+/// Combines the errors of connecting to each resolved address into a single error.
+///
+/// The error keeps the kind of the last failed attempt, and lists all of them in its message.
+pub(crate) fn aggregate_connect_errors<A: fmt::Display>(
+    mut errors: Vec<(A, RedisError)>,
+) -> RedisError {
+    if errors.len() <= 1 {
+        return match errors.pop() {
+            Some((_, err)) => err,
+            None => RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "could not resolve to any addresses",
+            )),
+        };
+    }
+    let attempts = errors
+        .iter()
+        .map(|(addr, err)| format!("{addr}: {err}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let (_, last) = errors.pop().unwrap();
+    match last.as_io_error() {
+        Some(err) => RedisError::from(io::Error::new(
+            err.kind(),
+            format!("failed to connect to any address ({attempts})"),
+        )),
+        None => RedisError::from((last.kind(), "failed to connect to any address", attempts)),
     }
 }
 
@@ -91,7 +156,7 @@ pub fn parse_redis_url(input: &str) -> Option<url::Url> {
 
 /// TlsMode indicates use or do not use verification of certification.
 /// Check [ConnectionAddr](ConnectionAddr::TcpTls::insecure) for more.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum TlsMode {
     /// Secure verify certification.
     Secure,
@@ -159,6 +224,9 @@ impl fmt::Display for ConnectionAddr {
 }
 
 /// Holds the connection information that redis should use for connecting.
+///
+/// Options beyond the address and the credentials, like timeouts, are set with a
+/// [`ConnectionBuilder`] or the query parameters of a connection URL.
 #[derive(Clone, Debug)]
 pub struct ConnectionInfo {
     /// A connection address for where to connect to.
@@ -168,8 +236,106 @@ pub struct ConnectionInfo {
     pub redis: RedisConnectionInfo,
 }
 
+/// A [`ConnectionInfo`] together with the options that go beyond it.
+#[derive(Clone, Debug)]
+pub(crate) struct ConnectionConfig {
+    pub(crate) addr: ConnectionAddr,
+    pub(crate) redis: RedisConnectionConfig,
+    /// The default timeout for establishing a connection.  Timeouts passed explicitly take
+    /// precedence.
+    pub(crate) connect_timeout: Option<Duration>,
+    /// The timeout for the whole setup of a connection, see
+    /// [`ConnectionBuilder::handshake_timeout`].
+    pub(crate) handshake_timeout: Option<Duration>,
+    /// The default timeout for waiting on a response.
+    pub(crate) response_timeout: Option<Duration>,
+    /// The default timeout for writing a request to a synchronous connection.
+    pub(crate) write_timeout: Option<Duration>,
+    /// The name used for SNI and to verify the server certificate instead of the host.
+    pub(crate) tls_server_name: Option<String>,
+    /// The local address that TCP connections are made from.
+    pub(crate) local_addr: Option<SocketAddr>,
+    /// An additional check of the server certificate of TLS connections.
+    pub(crate) tls_certificate_check: Option<TlsCertificateCheck>,
+    /// Further addresses to try in order if connecting to `addr` fails.
+    pub(crate) fallback_addrs: Vec<ConnectionAddr>,
+}
+
+impl From<ConnectionInfo> for ConnectionConfig {
+    fn from(info: ConnectionInfo) -> ConnectionConfig {
+        ConnectionConfig {
+            addr: info.addr,
+            redis: info.redis.into(),
+            connect_timeout: None,
+            handshake_timeout: None,
+            response_timeout: None,
+            write_timeout: None,
+            tls_server_name: None,
+            local_addr: None,
+            tls_certificate_check: None,
+            fallback_addrs: Vec::new(),
+        }
+    }
+}
+
+impl ConnectionConfig {
+    /// The address and credentials, leaving out the other options.
+    pub(crate) fn info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            addr: self.addr.clone(),
+            redis: self.redis.info(),
+        }
+    }
+}
+
+/// A check of the certificate presented by a TLS server, which runs after the certificate chain
+/// and server name were verified, unless the connection uses [`TlsMode::Insecure`].
+///
+/// The closure receives the DER-encoded end-entity certificate and returns whether to accept
+/// it, e.g. after comparing the hash of its public key to a pinned value or inspecting its
+/// subject alternative names.  Combined with [`TlsMode::Insecure`], this allows pinning
+/// self-signed certificates.  Connections to servers whose certificate is rejected fail with
+/// an `IoError`.
+#[derive(Clone)]
+pub struct TlsCertificateCheck(Arc<CertificateCheckFn>);
+
+type CertificateCheckFn = dyn Fn(&[u8]) -> bool + Send + Sync;
+
+impl TlsCertificateCheck {
+    /// Creates a check from a closure.
+    pub fn new<F>(check: F) -> Self
+    where
+        F: Fn(&[u8]) -> bool + Send + Sync + 'static,
+    {
+        TlsCertificateCheck(Arc::new(check))
+    }
+
+    /// Runs the check on the certificate of the server, if it presented one.
+    #[cfg_attr(
+        not(any(feature = "tls-native-tls", feature = "tls-rustls")),
+        allow(dead_code)
+    )]
+    pub(crate) fn verify(&self, certificate: Option<&[u8]>) -> RedisResult<()> {
+        match certificate {
+            Some(certificate) if (self.0)(certificate) => Ok(()),
+            _ => Err(RedisError::from((
+                ErrorKind::IoError,
+                "SSL Handshake error",
+                "the server certificate was rejected".to_string(),
+            ))),
+        }
+    }
+}
+
+impl fmt::Debug for TlsCertificateCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsCertificateCheck")
+            .finish_non_exhaustive()
+    }
+}
+
 /// Redis specific/connection independent information used to establish a connection to redis.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct RedisConnectionInfo {
     /// The database number to use.  This is usually `0`.
     pub db: i64,
@@ -179,6 +345,471 @@ pub struct RedisConnectionInfo {
     pub password: Option<String>,
 }
 
+impl fmt::Debug for RedisConnectionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Only whether credentials are set is printed, so that they don't end up in logs.
+        f.debug_struct("RedisConnectionInfo")
+            .field("db", &self.db)
+            .field("username", &redacted(&self.username))
+            .field("password", &redacted(&self.password))
+            .finish()
+    }
+}
+
+fn redacted(credential: &Option<String>) -> Option<&'static str> {
+    credential.as_ref().map(|_| "<redacted>")
+}
+
+/// A [`RedisConnectionInfo`] together with the options for setting up new connections.
+#[derive(Clone, Default)]
+pub(crate) struct RedisConnectionConfig {
+    pub(crate) db: i64,
+    pub(crate) username: Option<String>,
+    pub(crate) password: Option<String>,
+    /// The handshake used to authenticate new connections.
+    pub(crate) auth_strategy: AuthStrategy,
+    /// Whether `SELECT` is sent while setting up new connections.
+    pub(crate) select_strategy: SelectStrategy,
+    /// The protocol version requested while setting up new connections.
+    pub(crate) protocol: ProtocolVersion,
+    /// Commands sent after authenticating and selecting the database, which must reply with
+    /// `OK`.
+    pub(crate) setup_commands: Vec<Cmd>,
+    /// Whether all commands and responses are logged at trace level.
+    pub(crate) log_commands: bool,
+    /// Whether commands are checked against the `COMMAND` reply of the server.
+    pub(crate) validate_commands: bool,
+    /// The role the server must report with `ROLE` for new connections to be accepted.
+    pub(crate) required_role: RequiredRole,
+}
+
+impl From<RedisConnectionInfo> for RedisConnectionConfig {
+    fn from(info: RedisConnectionInfo) -> RedisConnectionConfig {
+        RedisConnectionConfig {
+            db: info.db,
+            username: info.username,
+            password: info.password,
+            ..Default::default()
+        }
+    }
+}
+
+/// Describes whether a new connection sends `SELECT` to switch to the configured database.
+///
+/// Servers in cluster mode, as well as some Redis-compatible servers, reject `SELECT` or only
+/// support a single database.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SelectStrategy {
+    /// Send `SELECT` only if the database is not `0`.  This is the default.
+    #[default]
+    IfNonZero,
+    /// Always send `SELECT`, even for database `0`.
+    Always,
+    /// Never send `SELECT`, even if the database is not `0`.
+    Never,
+}
+
+/// The role a server must have for new connections to it to be accepted.
+///
+/// Any other role fails the connection while setting it up, which catches connections to the
+/// wrong server before the first write is rejected with `READONLY`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RequiredRole {
+    /// Accept servers with any role, without sending `ROLE`.  This is the default.
+    #[default]
+    Any,
+    /// Only accept masters.
+    Master,
+    /// Only accept replicas.
+    Replica,
+}
+
+/// The version of the Redis serialization protocol (RESP) spoken on a connection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProtocolVersion {
+    /// RESP2, which is supported by every server.  This is the default.
+    #[default]
+    RESP2,
+    /// RESP3, which is requested with `HELLO 3` and requires the `resp3` feature.
+    ///
+    /// Servers that do not know `HELLO`, as well as proxies that answer it without switching
+    /// protocols, keep the connection on RESP2.
+    RESP3,
+}
+
+impl RedisConnectionConfig {
+    /// The database and credentials, leaving out the other options.
+    pub(crate) fn info(&self) -> RedisConnectionInfo {
+        RedisConnectionInfo {
+            db: self.db,
+            username: self.username.clone(),
+            password: self.password.clone(),
+        }
+    }
+
+    /// Whether `SELECT` should be sent while setting up a new connection.
+    pub(crate) fn should_select(&self) -> bool {
+        match self.select_strategy {
+            SelectStrategy::IfNonZero => self.db != 0,
+            SelectStrategy::Always => true,
+            SelectStrategy::Never => false,
+        }
+    }
+}
+
+impl fmt::Debug for RedisConnectionConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let setup_commands: Vec<_> = self.setup_commands.iter().map(command_name).collect();
+        f.debug_struct("RedisConnectionConfig")
+            .field("db", &self.db)
+            .field("username", &redacted(&self.username))
+            .field("password", &redacted(&self.password))
+            .field("auth_strategy", &self.auth_strategy)
+            .field("select_strategy", &self.select_strategy)
+            .field("protocol", &self.protocol)
+            .field("setup_commands", &setup_commands)
+            .field("log_commands", &self.log_commands)
+            .field("validate_commands", &self.validate_commands)
+            .field("required_role", &self.required_role)
+            .finish()
+    }
+}
+
+/// Returns the name of a command, leaving out its arguments, which might contain credentials.
+pub(crate) fn command_name(command: &Cmd) -> String {
+    match command.args_iter().next() {
+        Some(Arg::Simple(name)) => String::from_utf8_lossy(name).into_owned(),
+        _ => String::new(),
+    }
+}
+
+/// Describes how a new connection authenticates itself with the server.
+///
+/// Authentication always happens before the database is selected.
+#[derive(Clone, Default)]
+#[non_exhaustive]
+pub enum AuthStrategy {
+    /// Send `AUTH [username] password` if a password is configured, falling back to
+    /// `AUTH password` for servers that don't support usernames.  This is the default.
+    #[default]
+    Auth,
+    /// Run the given commands, in order, instead of `AUTH`.  The password and username of the
+    /// [`RedisConnectionInfo`] are ignored, and an error reply to any of the commands fails the
+    /// connection.
+    ///
+    /// This is useful for Redis-compatible servers that require a different handshake.
+    Commands(Vec<Cmd>),
+}
+
+impl fmt::Debug for AuthStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The commands are not printed, as they usually contain credentials.
+        match self {
+            AuthStrategy::Auth => f.write_str("Auth"),
+            AuthStrategy::Commands(commands) => {
+                write!(f, "Commands(<{} commands>)", commands.len())
+            }
+        }
+    }
+}
+
+/// A builder for the connection settings of a [`Client`](crate::Client).
+///
+/// Besides the address and credentials a [`ConnectionInfo`] holds, the builder sets the options
+/// that go beyond them, like timeouts.  The options are validated when calling
+/// [`build`](ConnectionBuilder::build), and the builder can be passed to
+/// [`Client::open`](crate::Client::open) directly.  When converted into a [`ConnectionInfo`],
+/// e.g. for a cluster or sentinel client, only the address and credentials are kept.
+///
+/// ```rust,no_run
+/// # use std::time::Duration;
+/// let client = redis::ConnectionBuilder::tcp("127.0.0.1", 6379)
+///     .db(2)
+///     .username("app")
+///     .password("secret")
+///     .connect_timeout(Duration::from_secs(5))
+///     .build()
+///     .unwrap();
+/// let con = client.get_connection().unwrap();
+/// ```
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct ConnectionBuilder {
+    tls: Option<TlsMode>,
+    config: ConnectionConfig,
+}
+
+impl ConnectionBuilder {
+    /// Creates a builder connecting to the given address.
+    pub fn new(addr: ConnectionAddr) -> Self {
+        ConnectionInfo {
+            addr,
+            redis: RedisConnectionInfo::default(),
+        }
+        .into()
+    }
+
+    /// Creates a builder connecting to the given host and port over TCP.
+    pub fn tcp<T: Into<String>>(host: T, port: u16) -> Self {
+        Self::new(ConnectionAddr::Tcp(host.into(), port))
+    }
+
+    /// Creates a builder connecting to the given unix socket.
+    pub fn unix<P: Into<PathBuf>>(path: P) -> Self {
+        Self::new(ConnectionAddr::Unix(path.into()))
+    }
+
+    /// Connects with TLS, verifying the server certificate unless `mode` is
+    /// [`TlsMode::Insecure`].  This is only valid for TCP addresses.
+    pub fn tls(mut self, mode: TlsMode) -> Self {
+        self.tls = Some(mode);
+        self
+    }
+
+    /// Sets the name used for SNI and to verify the server certificate, instead of the host
+    /// that is connected to.  This requires TLS.
+    ///
+    /// This is useful when connecting through an IP address or a proxy, whose address doesn't
+    /// match the names in the server certificate.
+    pub fn tls_server_name<T: Into<String>>(mut self, name: T) -> Self {
+        self.config.tls_server_name = Some(name.into());
+        self
+    }
+
+    /// Sets an additional check of the server certificate, which runs after the default
+    /// verification.  This requires TLS.
+    pub fn tls_certificate_check(mut self, check: TlsCertificateCheck) -> Self {
+        self.config.tls_certificate_check = Some(check);
+        self
+    }
+
+    /// Adds an address to try if connecting to the previous ones fails, e.g. one of the
+    /// replicas of a small deployment without Sentinel.  TLS applies to the first address
+    /// only, so the fallback address must specify it itself.
+    ///
+    /// A [`Client`](crate::Client) uses the first address where the connection is established
+    /// and set up, starting with the address it last connected to, so reconnecting, e.g. by
+    /// the `ConnectionManager`, tries the address that worked before first.  If all addresses
+    /// fail, the error lists why each of them failed.
+    pub fn fallback_addr(mut self, addr: ConnectionAddr) -> Self {
+        self.config.fallback_addrs.push(addr);
+        self
+    }
+
+    /// Sets the local address to connect from, which is useful on hosts with several network
+    /// interfaces.  This is ignored for Unix sockets.
+    pub fn local_addr(mut self, addr: SocketAddr) -> Self {
+        self.config.local_addr = Some(addr);
+        self
+    }
+
+    /// Sets the database to select.
+    pub fn db(mut self, db: i64) -> Self {
+        self.config.redis.db = db;
+        self
+    }
+
+    /// Sets the username to authenticate with.  This requires a password as well.
+    pub fn username<T: Into<String>>(mut self, username: T) -> Self {
+        self.config.redis.username = Some(username.into());
+        self
+    }
+
+    /// Sets the password to authenticate with.
+    pub fn password<T: Into<String>>(mut self, password: T) -> Self {
+        self.config.redis.password = Some(password.into());
+        self
+    }
+
+    /// Sets the handshake used to authenticate new connections.
+    pub fn auth_strategy(mut self, auth_strategy: AuthStrategy) -> Self {
+        self.config.redis.auth_strategy = auth_strategy;
+        self
+    }
+
+    /// Sets whether `SELECT` is sent while setting up new connections.
+    pub fn select_strategy(mut self, select_strategy: SelectStrategy) -> Self {
+        self.config.redis.select_strategy = select_strategy;
+        self
+    }
+
+    /// Sets the protocol version requested while setting up new connections.
+    ///
+    /// The version that was actually negotiated is reported by the connection's
+    /// `protocol_version` method.
+    pub fn protocol(mut self, protocol: ProtocolVersion) -> Self {
+        self.config.redis.protocol = protocol;
+        self
+    }
+
+    /// Adds a command sent after authenticating and selecting the database while setting up
+    /// new connections, e.g. `CLIENT NO-EVICT on`.  The command must reply with `OK`.
+    pub fn setup_command(mut self, command: Cmd) -> Self {
+        self.config.redis.setup_commands.push(command);
+        self
+    }
+
+    /// Sets whether all commands and responses are logged at trace level, with credentials
+    /// redacted.  This requires the `debug-logging` feature and is ignored otherwise.
+    pub fn log_commands(mut self, log_commands: bool) -> Self {
+        self.config.redis.log_commands = log_commands;
+        self
+    }
+
+    /// Sets whether commands are checked against the arity and key positions the server
+    /// reports for them with `COMMAND`, which is fetched once while setting up new connections.
+    ///
+    /// Commands with the wrong number of arguments then fail with
+    /// [`ErrorKind::InvalidClientConfig`](crate::ErrorKind::InvalidClientConfig) before they are
+    /// sent, and pipelines containing such a command fail as a whole.  This is meant for
+    /// development and is ignored in release builds.  It applies to [`Connection`],
+    /// [`aio::Connection`](crate::aio::Connection) and
+    /// [`aio::MultiplexedConnection`](crate::aio::MultiplexedConnection) alike, checking
+    /// commands as they are written, i.e. after any command hook rewrote them.  If the server
+    /// refuses `COMMAND`, the connection is set up without validation.
+    pub fn validate_commands(mut self, validate_commands: bool) -> Self {
+        self.config.redis.validate_commands = validate_commands;
+        self
+    }
+
+    /// Sets the role the server must report with `ROLE` for new connections to be accepted,
+    /// e.g. to avoid writing to a replica after a failover.
+    pub fn required_role(mut self, role: RequiredRole) -> Self {
+        self.config.redis.required_role = role;
+        self
+    }
+
+    /// Sets the default timeout for establishing a connection.
+    ///
+    /// Timeouts passed explicitly, e.g. to
+    /// [`Client::get_connection_with_timeout`](crate::Client::get_connection_with_timeout),
+    /// take precedence.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for the whole setup of a connection, from establishing it through the
+    /// last setup command.
+    ///
+    /// Unlike the connect timeout, this also bounds authenticating, selecting the database and
+    /// the other setup commands, so that a server accepting connections but not responding
+    /// fails them with a `TimedOut` I/O error.  With [fallback addresses](Self::fallback_addr),
+    /// it applies to each address separately.  Synchronous connections can't bound the setup
+    /// as a whole, so there it bounds establishing the connection and each read and write of
+    /// the setup.
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.config.handshake_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the default timeout for waiting on a response.
+    ///
+    /// This applies to synchronous connections, to async connections and to multiplexed
+    /// connections, including the ones created by the `ConnectionManager`.  It can be changed
+    /// per connection with `set_read_timeout` and `set_response_timeout` respectively.
+    ///
+    /// Note that blocking commands like `BLPOP` or `XREAD BLOCK`, as well as waiting for pub/sub
+    /// messages, can legitimately take longer than this timeout and will fail if they do; use a
+    /// separate connection without a response timeout for those.
+    pub fn response_timeout(mut self, timeout: Duration) -> Self {
+        self.config.response_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the default timeout for writing a request to a synchronous connection.  It can be
+    /// changed per connection with `set_write_timeout`.
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.config.write_timeout = Some(timeout);
+        self
+    }
+
+    /// Validates the options and creates a [`Client`](crate::Client) using them, like passing
+    /// the builder to [`Client::open`](crate::Client::open).
+    pub fn build(self) -> RedisResult<crate::Client> {
+        crate::Client::open(self)
+    }
+
+    /// Validates the options and returns the resulting configuration.
+    pub(crate) fn into_config(self) -> RedisResult<ConnectionConfig> {
+        let mut config = self.config;
+        config.addr = match (config.addr, self.tls) {
+            (addr, None) => addr,
+            (ConnectionAddr::Tcp(host, port), Some(mode)) => ConnectionAddr::TcpTls {
+                host,
+                port,
+                insecure: matches!(mode, TlsMode::Insecure),
+            },
+            (ConnectionAddr::TcpTls { host, port, .. }, Some(mode)) => ConnectionAddr::TcpTls {
+                host,
+                port,
+                insecure: matches!(mode, TlsMode::Insecure),
+            },
+            (ConnectionAddr::Unix(_), Some(_)) => fail!((
+                ErrorKind::InvalidClientConfig,
+                "TLS is not supported for unix sockets"
+            )),
+        };
+        if config.redis.username.is_some() && config.redis.password.is_none() {
+            fail!((
+                ErrorKind::InvalidClientConfig,
+                "A username requires a password"
+            ));
+        }
+        let timeouts = [
+            config.connect_timeout,
+            config.handshake_timeout,
+            config.response_timeout,
+            config.write_timeout,
+        ];
+        if timeouts.contains(&Some(Duration::ZERO)) {
+            fail!((ErrorKind::InvalidClientConfig, "Timeouts must not be zero"));
+        }
+        let is_tls = matches!(config.addr, ConnectionAddr::TcpTls { .. });
+        if config.tls_server_name.is_some() && !is_tls {
+            fail!((
+                ErrorKind::InvalidClientConfig,
+                "A TLS server name requires TLS"
+            ));
+        }
+        if config.tls_certificate_check.is_some() && !is_tls {
+            fail!((
+                ErrorKind::InvalidClientConfig,
+                "A TLS certificate check requires TLS"
+            ));
+        }
+        Ok(config)
+    }
+}
+
+impl From<ConnectionInfo> for ConnectionBuilder {
+    fn from(info: ConnectionInfo) -> ConnectionBuilder {
+        ConnectionBuilder {
+            tls: None,
+            config: info.into(),
+        }
+    }
+}
+
+impl From<ConnectionConfig> for ConnectionBuilder {
+    fn from(config: ConnectionConfig) -> ConnectionBuilder {
+        ConnectionBuilder { tls: None, config }
+    }
+}
+
+impl IntoConnectionInfo for ConnectionBuilder {
+    fn into_connection_info(self) -> RedisResult<ConnectionInfo> {
+        self.into_config().map(|config| config.info())
+    }
+
+    fn into_connection_builder(self) -> RedisResult<ConnectionBuilder> {
+        Ok(self)
+    }
+}
+
 impl FromStr for ConnectionInfo {
     type Err = RedisError;
 
@@ -187,12 +818,64 @@ impl FromStr for ConnectionInfo {
     }
 }
 
+impl ConnectionInfo {
+    /// Parses the connection info from the URL in the `REDIS_URL` environment variable, which
+    /// many hosting platforms set for the Redis instances they provide.
+    ///
+    /// Only `REDIS_URL` is read; other variables, like `NO_PROXY`, are not taken into account.
+    /// As with [`FromStr`], the query parameters of the URL that a [`ConnectionInfo`] can't
+    /// hold, like timeouts, are dropped; pass the URL to [`Client::open`](crate::Client::open)
+    /// to keep them.
+    ///
+    /// Fails with [`ErrorKind::InvalidClientConfig`](crate::ErrorKind::InvalidClientConfig) if
+    /// the variable is not set, with the message "Redis URL environment variable is not set", or
+    /// like [`FromStr`] if it holds no valid URL, with the message "Redis URL did not parse".
+    pub fn from_env() -> RedisResult<ConnectionInfo> {
+        ConnectionInfo::from_env_var("REDIS_URL")
+    }
+
+    /// Like [`from_env`](Self::from_env), but reads the URL from the environment variable with
+    /// the given name.
+    pub fn from_env_var(name: &str) -> RedisResult<ConnectionInfo> {
+        connection_info_from_var(name, std::env::var(name))
+    }
+}
+
+// Parses the value of the environment variable `name`, as returned by `std::env::var`.
+fn connection_info_from_var(
+    name: &str,
+    value: Result<String, std::env::VarError>,
+) -> RedisResult<ConnectionInfo> {
+    match value {
+        Ok(url) => url.parse(),
+        Err(std::env::VarError::NotPresent) => fail!((
+            ErrorKind::InvalidClientConfig,
+            "Redis URL environment variable is not set",
+            name.to_string()
+        )),
+        Err(std::env::VarError::NotUnicode(_)) => fail!((
+            ErrorKind::InvalidClientConfig,
+            "Redis URL did not parse",
+            format!("{name} is not valid unicode")
+        )),
+    }
+}
+
 /// Converts an object into a connection info struct.  This allows the
 /// constructor of the client to accept connection information in a
 /// range of different formats.
 pub trait IntoConnectionInfo {
     /// Converts the object into a connection info object.
     fn into_connection_info(self) -> RedisResult<ConnectionInfo>;
+
+    /// Converts the object into a [`ConnectionBuilder`], keeping the options that a
+    /// [`ConnectionInfo`] can't hold, like the query parameters of a URL.
+    fn into_connection_builder(self) -> RedisResult<ConnectionBuilder>
+    where
+        Self: Sized,
+    {
+        self.into_connection_info().map(ConnectionBuilder::from)
+    }
 }
 
 impl IntoConnectionInfo for ConnectionInfo {
@@ -203,10 +886,22 @@ impl IntoConnectionInfo for ConnectionInfo {
 
 impl<'a> IntoConnectionInfo for &'a str {
     fn into_connection_info(self) -> RedisResult<ConnectionInfo> {
-        match parse_redis_url(self) {
-            Some(u) => u.into_connection_info(),
-            None => fail!((ErrorKind::InvalidClientConfig, "Redis URL did not parse")),
-        }
+        url_from_str(self)?.into_connection_info()
+    }
+
+    fn into_connection_builder(self) -> RedisResult<ConnectionBuilder> {
+        url_from_str(self)?.into_connection_builder()
+    }
+}
+
+fn url_from_str(s: &str) -> RedisResult<url::Url> {
+    match url::Url::parse(s) {
+        Ok(u) => Ok(u),
+        Err(err) => fail!((
+            ErrorKind::InvalidClientConfig,
+            "Redis URL did not parse",
+            err.to_string()
+        )),
     }
 }
 
@@ -224,10 +919,11 @@ where
 
 impl IntoConnectionInfo for String {
     fn into_connection_info(self) -> RedisResult<ConnectionInfo> {
-        match parse_redis_url(&self) {
-            Some(u) => u.into_connection_info(),
-            None => fail!((ErrorKind::InvalidClientConfig, "Redis URL did not parse")),
-        }
+        self.as_str().into_connection_info()
+    }
+
+    fn into_connection_builder(self) -> RedisResult<ConnectionBuilder> {
+        self.as_str().into_connection_builder()
     }
 }
 
@@ -348,16 +1044,72 @@ fn url_to_unix_connection_info(_: url::Url) -> RedisResult<ConnectionInfo> {
     ));
 }
 
+/// Applies the connection options given as query parameters of a URL.  Unknown parameters are
+/// ignored.
+fn apply_url_options(config: &mut ConnectionConfig, url: &url::Url) -> RedisResult<()> {
+    for (name, value) in url.query_pairs() {
+        match &*name {
+            "protocol" => {
+                config.redis.protocol = match &*value.to_ascii_lowercase() {
+                    "resp2" => ProtocolVersion::RESP2,
+                    "resp3" => ProtocolVersion::RESP3,
+                    _ => fail!((
+                        ErrorKind::InvalidClientConfig,
+                        "Invalid protocol in URL",
+                        value.into_owned()
+                    )),
+                }
+            }
+            "timeout" => {
+                let timeout = parse_url_timeout(&name, &value)?;
+                config.connect_timeout = Some(timeout);
+                config.response_timeout = Some(timeout);
+            }
+            "connect_timeout" => config.connect_timeout = Some(parse_url_timeout(&name, &value)?),
+            "handshake_timeout" => {
+                config.handshake_timeout = Some(parse_url_timeout(&name, &value)?)
+            }
+            "response_timeout" => config.response_timeout = Some(parse_url_timeout(&name, &value)?),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Parses a positive number of seconds given as a URL query parameter.
+fn parse_url_timeout(name: &str, value: &str) -> RedisResult<Duration> {
+    match value.parse::<f64>() {
+        Ok(secs) if secs > 0.0 && secs < u64::MAX as f64 => Ok(Duration::from_secs_f64(secs)),
+        _ => fail!((
+            ErrorKind::InvalidClientConfig,
+            "Invalid timeout in URL",
+            format!("{name}={value}")
+        )),
+    }
+}
+
+fn url_to_connection_config(url: url::Url) -> RedisResult<ConnectionConfig> {
+    let info = match url.scheme() {
+        "redis" | "rediss" => url_to_tcp_connection_info(url.clone())?,
+        "unix" | "redis+unix" => url_to_unix_connection_info(url.clone())?,
+        scheme => fail!((
+            ErrorKind::InvalidClientConfig,
+            "URL provided is not a redis URL",
+            format!("unsupported scheme {scheme:?}")
+        )),
+    };
+    let mut config = ConnectionConfig::from(info);
+    apply_url_options(&mut config, &url)?;
+    Ok(config)
+}
+
 impl IntoConnectionInfo for url::Url {
     fn into_connection_info(self) -> RedisResult<ConnectionInfo> {
-        match self.scheme() {
-            "redis" | "rediss" => url_to_tcp_connection_info(self),
-            "unix" | "redis+unix" => url_to_unix_connection_info(self),
-            _ => fail!((
-                ErrorKind::InvalidClientConfig,
-                "URL provided is not a redis URL"
-            )),
-        }
+        url_to_connection_config(self).map(|config| config.info())
+    }
+
+    fn into_connection_builder(self) -> RedisResult<ConnectionBuilder> {
+        url_to_connection_config(self).map(ConnectionBuilder::from)
     }
 }
 
@@ -417,6 +1169,11 @@ pub struct Connection {
     con: ActualConnection,
     parser: Parser,
     db: i64,
+    connection_info: RedisConnectionConfig,
+    protocol: ProtocolVersion,
+
+    /// The commands reported by the server, if `validate_commands` is set.
+    command_table: Option<CommandTable>,
 
     /// Flag indicating whether the connection was left in the PubSub state after dropping `PubSub`.
     ///
@@ -425,6 +1182,18 @@ pub struct Connection {
     pubsub: bool,
 }
 
+impl fmt::Debug for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Connection")
+            .field("db", &self.db)
+            .field("protocol", &self.protocol)
+            .field("connection_info", &self.connection_info)
+            .field("pubsub", &self.pubsub)
+            .field("open", &self.is_open())
+            .finish_non_exhaustive()
+    }
+}
+
 /// Represents a pubsub connection.
 pub struct PubSub<'a> {
     con: &'a mut Connection,
@@ -439,40 +1208,13 @@ pub struct Msg {
 }
 
 impl ActualConnection {
-    pub fn new(addr: &ConnectionAddr, timeout: Option<Duration>) -> RedisResult<ActualConnection> {
-        Ok(match *addr {
+    pub fn new(
+        connection_info: &ConnectionConfig,
+        timeout: Option<Duration>,
+    ) -> RedisResult<ActualConnection> {
+        Ok(match connection_info.addr {
             ConnectionAddr::Tcp(ref host, ref port) => {
-                let addr = (host.as_str(), *port);
-                let tcp = match timeout {
-                    None => connect_tcp(addr)?,
-                    Some(timeout) => {
-                        let mut tcp = None;
-                        let mut last_error = None;
-                        for addr in addr.to_socket_addrs()? {
-                            match connect_tcp_timeout(&addr, timeout) {
-                                Ok(l) => {
-                                    tcp = Some(l);
-                                    break;
-                                }
-                                Err(e) => {
-                                    last_error = Some(e);
-                                }
-                            };
-                        }
-                        match (tcp, last_error) {
-                            (Some(tcp), _) => tcp,
-                            (None, Some(e)) => {
-                                fail!(e);
-                            }
-                            (None, None) => {
-                                fail!((
-                                    ErrorKind::InvalidClientConfig,
-                                    "could not resolve to any addresses"
-                                ));
-                            }
-                        }
-                    }
-                };
+                let tcp = connect_tcp_host(host, *port, timeout, connection_info.local_addr)?;
                 ActualConnection::Tcp(TcpConnection {
                     reader: tcp,
                     open: true,
@@ -493,45 +1235,21 @@ impl ActualConnection {
                 } else {
                     TlsConnector::new()?
                 };
-                let addr = (host.as_str(), port);
-                let tls = match timeout {
-                    None => {
-                        let tcp = connect_tcp(addr)?;
-                        match tls_connector.connect(host, tcp) {
-                            Ok(res) => res,
-                            Err(e) => {
-                                fail!((ErrorKind::IoError, "SSL Handshake error", e.to_string()));
-                            }
-                        }
-                    }
-                    Some(timeout) => {
-                        let mut tcp = None;
-                        let mut last_error = None;
-                        for addr in (host.as_str(), port).to_socket_addrs()? {
-                            match connect_tcp_timeout(&addr, timeout) {
-                                Ok(l) => {
-                                    tcp = Some(l);
-                                    break;
-                                }
-                                Err(e) => {
-                                    last_error = Some(e);
-                                }
-                            };
-                        }
-                        match (tcp, last_error) {
-                            (Some(tcp), _) => tls_connector.connect(host, tcp).unwrap(),
-                            (None, Some(e)) => {
-                                fail!(e);
-                            }
-                            (None, None) => {
-                                fail!((
-                                    ErrorKind::InvalidClientConfig,
-                                    "could not resolve to any addresses"
-                                ));
-                            }
-                        }
+                let tcp = connect_tcp_host(host, port, timeout, connection_info.local_addr)?;
+                let server_name = connection_info.tls_server_name.as_deref().unwrap_or(host);
+                let tls = match tls_connector.connect(server_name, tcp) {
+                    Ok(res) => res,
+                    Err(e) => {
+                        fail!((ErrorKind::IoError, "SSL Handshake error", e.to_string()));
                     }
                 };
+                if let Some(check) = &connection_info.tls_certificate_check {
+                    let certificate = tls
+                        .peer_certificate()?
+                        .map(|cert| cert.to_der())
+                        .transpose()?;
+                    check.verify(certificate.as_deref())?;
+                }
                 ActualConnection::TcpNativeTls(Box::new(TcpNativeTlsConnection {
                     reader: tls,
                     open: true,
@@ -543,42 +1261,19 @@ impl ActualConnection {
                 port,
                 insecure,
             } => {
-                let host: &str = host;
+                let server_name = connection_info.tls_server_name.as_deref().unwrap_or(host);
                 let config = create_rustls_config(insecure)?;
-                let conn = rustls::ClientConnection::new(Arc::new(config), host.try_into()?)?;
-                let reader = match timeout {
-                    None => {
-                        let tcp = connect_tcp((host, port))?;
-                        StreamOwned::new(conn, tcp)
+                let conn =
+                    rustls::ClientConnection::new(Arc::new(config), server_name.try_into()?)?;
+                let tcp = connect_tcp_host(host, port, timeout, connection_info.local_addr)?;
+                let mut reader = StreamOwned::new(conn, tcp);
+                if let Some(check) = &connection_info.tls_certificate_check {
+                    while reader.conn.is_handshaking() {
+                        reader.conn.complete_io(&mut reader.sock)?;
                     }
-                    Some(timeout) => {
-                        let mut tcp = None;
-                        let mut last_error = None;
-                        for addr in (host, port).to_socket_addrs()? {
-                            match connect_tcp_timeout(&addr, timeout) {
-                                Ok(l) => {
-                                    tcp = Some(l);
-                                    break;
-                                }
-                                Err(e) => {
-                                    last_error = Some(e);
-                                }
-                            };
-                        }
-                        match (tcp, last_error) {
-                            (Some(tcp), _) => StreamOwned::new(conn, tcp),
-                            (None, Some(e)) => {
-                                fail!(e);
-                            }
-                            (None, None) => {
-                                fail!((
-                                    ErrorKind::InvalidClientConfig,
-                                    "could not resolve to any addresses"
-                                ));
-                            }
-                        }
-                    }
-                };
+                    let certificate = reader.conn.peer_certificates().and_then(|c| c.first());
+                    check.verify(certificate.map(|cert| cert.0.as_slice()))?;
+                }
 
                 ActualConnection::TcpRustls(Box::new(TcpRustlsConnection { reader, open: true }))
             }
@@ -684,27 +1379,85 @@ impl ActualConnection {
         Ok(())
     }
 
+    fn tcp_stream(&self) -> Option<&TcpStream> {
+        match *self {
+            ActualConnection::Tcp(TcpConnection { ref reader, .. }) => Some(reader),
+            #[cfg(all(feature = "tls-native-tls", not(feature = "tls-rustls")))]
+            ActualConnection::TcpNativeTls(ref boxed_tls_connection) => {
+                Some(boxed_tls_connection.reader.get_ref())
+            }
+            #[cfg(feature = "tls-rustls")]
+            ActualConnection::TcpRustls(ref boxed_tls_connection) => {
+                Some(boxed_tls_connection.reader.get_ref())
+            }
+            #[cfg(unix)]
+            ActualConnection::Unix(_) => None,
+        }
+    }
+
     pub fn set_read_timeout(&self, dur: Option<Duration>) -> RedisResult<()> {
         match *self {
             ActualConnection::Tcp(TcpConnection { ref reader, .. }) => {
                 reader.set_read_timeout(dur)?;
             }
             #[cfg(all(feature = "tls-native-tls", not(feature = "tls-rustls")))]
-            ActualConnection::TcpNativeTls(ref boxed_tls_connection) => {
-                let reader = &(boxed_tls_connection.reader);
-                reader.get_ref().set_read_timeout(dur)?;
+            ActualConnection::TcpNativeTls(ref boxed_tls_connection) => {
+                let reader = &(boxed_tls_connection.reader);
+                reader.get_ref().set_read_timeout(dur)?;
+            }
+            #[cfg(feature = "tls-rustls")]
+            ActualConnection::TcpRustls(ref boxed_tls_connection) => {
+                let reader = &(boxed_tls_connection.reader);
+                reader.get_ref().set_read_timeout(dur)?;
+            }
+            #[cfg(unix)]
+            ActualConnection::Unix(UnixConnection { ref sock, .. }) => {
+                sock.set_read_timeout(dur)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_timeout(&self) -> RedisResult<Option<Duration>> {
+        let dur = match *self {
+            ActualConnection::Tcp(TcpConnection { ref reader, .. }) => reader.read_timeout()?,
+            #[cfg(all(feature = "tls-native-tls", not(feature = "tls-rustls")))]
+            ActualConnection::TcpNativeTls(ref boxed_tls_connection) => {
+                boxed_tls_connection.reader.get_ref().read_timeout()?
+            }
+            #[cfg(feature = "tls-rustls")]
+            ActualConnection::TcpRustls(ref boxed_tls_connection) => {
+                boxed_tls_connection.reader.get_ref().read_timeout()?
+            }
+            #[cfg(unix)]
+            ActualConnection::Unix(UnixConnection { ref sock, .. }) => sock.read_timeout()?,
+        };
+        Ok(dur)
+    }
+
+    /// Shuts the socket down and marks the connection as closed.
+    fn shutdown(&mut self) {
+        match *self {
+            ActualConnection::Tcp(ref mut connection) => {
+                let _ = connection.reader.shutdown(net::Shutdown::Both);
+                connection.open = false;
+            }
+            #[cfg(all(feature = "tls-native-tls", not(feature = "tls-rustls")))]
+            ActualConnection::TcpNativeTls(ref mut connection) => {
+                let _ = connection.reader.shutdown();
+                connection.open = false;
             }
             #[cfg(feature = "tls-rustls")]
-            ActualConnection::TcpRustls(ref boxed_tls_connection) => {
-                let reader = &(boxed_tls_connection.reader);
-                reader.get_ref().set_read_timeout(dur)?;
+            ActualConnection::TcpRustls(ref mut connection) => {
+                let _ = connection.reader.get_mut().shutdown(net::Shutdown::Both);
+                connection.open = false;
             }
             #[cfg(unix)]
-            ActualConnection::Unix(UnixConnection { ref sock, .. }) => {
-                sock.set_read_timeout(dur)?;
+            ActualConnection::Unix(ref mut connection) => {
+                let _ = connection.sock.shutdown(net::Shutdown::Both);
+                connection.open = false;
             }
         }
-        Ok(())
     }
 
     pub fn is_open(&self) -> bool {
@@ -764,7 +1517,7 @@ pub(crate) fn create_rustls_config(insecure: bool) -> RedisResult<rustls::Client
     }
 }
 
-fn connect_auth(con: &mut Connection, connection_info: &RedisConnectionInfo) -> RedisResult<()> {
+fn connect_auth(con: &mut Connection, connection_info: &RedisConnectionConfig) -> RedisResult<()> {
     let mut command = cmd("AUTH");
     if let Some(username) = &connection_info.username {
         command.arg(username);
@@ -803,11 +1556,37 @@ fn connect_auth(con: &mut Connection, connection_info: &RedisConnectionInfo) ->
 }
 
 pub fn connect(
-    connection_info: &ConnectionInfo,
+    connection_info: &ConnectionConfig,
     timeout: Option<Duration>,
 ) -> RedisResult<Connection> {
-    let con = ActualConnection::new(&connection_info.addr, timeout)?;
-    setup_connection(con, &connection_info.redis)
+    let handshake_timeout = connection_info.handshake_timeout;
+    let connect_timeout = min_timeout(
+        timeout.or(connection_info.connect_timeout),
+        handshake_timeout,
+    );
+    let con = ActualConnection::new(connection_info, connect_timeout)?;
+    con.set_read_timeout(min_timeout(
+        connection_info.response_timeout,
+        handshake_timeout,
+    ))?;
+    con.set_write_timeout(min_timeout(
+        connection_info.write_timeout,
+        handshake_timeout,
+    ))?;
+    let con = setup_connection(con, &connection_info.redis)?;
+    if handshake_timeout.is_some() {
+        con.set_read_timeout(connection_info.response_timeout)?;
+        con.set_write_timeout(connection_info.write_timeout)?;
+    }
+    Ok(con)
+}
+
+// The shorter of two optional timeouts, where `None` waits indefinitely.
+fn min_timeout(a: Option<Duration>, b: Option<Duration>) -> Option<Duration> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
 }
 
 pub(crate) fn client_set_info_pipeline() -> Pipeline {
@@ -827,37 +1606,281 @@ pub(crate) fn client_set_info_pipeline() -> Pipeline {
     pipeline
 }
 
+// Authenticates and selects the database as configured.
+fn authenticate_and_select(
+    con: &mut Connection,
+    connection_info: &RedisConnectionConfig,
+) -> RedisResult<()> {
+    if let AuthStrategy::Commands(ref commands) = connection_info.auth_strategy {
+        for command in commands {
+            command.query::<Value>(con)?;
+        }
+    }
+
+    con.protocol = handshake(con, connection_info)?;
+
+    if let Some(pipeline) = setup_commands_pipeline(connection_info) {
+        let replies = pipeline.query::<Vec<Value>>(con)?;
+        check_setup_replies(connection_info, &replies)?;
+    }
+    Ok(())
+}
+
+// Sends `AUTH`, `HELLO` and `SELECT` as configured, in a single round trip if possible.
+fn handshake(
+    con: &mut Connection,
+    connection_info: &RedisConnectionConfig,
+) -> RedisResult<ProtocolVersion> {
+    if let Some(pipeline) = handshake_pipeline(connection_info)? {
+        let replies = pipeline.query::<Vec<Value>>(con);
+        if let Some(protocol) = protocol_from_handshake(connection_info, replies) {
+            return Ok(protocol);
+        }
+    }
+
+    if matches!(connection_info.auth_strategy, AuthStrategy::Auth)
+        && connection_info.password.is_some()
+    {
+        connect_auth(con, connection_info)?;
+    }
+
+    let protocol = match connection_info.protocol {
+        ProtocolVersion::RESP2 => ProtocolVersion::RESP2,
+        ProtocolVersion::RESP3 => {
+            check_resp3_supported()?;
+            match cmd("HELLO").arg(3).query::<Value>(con) {
+                Ok(reply) => protocol_from_hello(&reply)?,
+                Err(e) if e.is_unknown_command() => ProtocolVersion::RESP2,
+                Err(e) => return Err(e),
+            }
+        }
+    };
+
+    if connection_info.should_select() {
+        select_db(con, connection_info.db)?;
+    }
+    Ok(protocol)
+}
+
+/// Checks the reply to `ROLE` against the role the connection requires.
+pub(crate) fn check_role(required: RequiredRole, reply: &Value) -> RedisResult<()> {
+    let role = match reply {
+        Value::Bulk(items) => items.first().map(from_redis_value::<String>),
+        _ => None,
+    };
+    let role = match role {
+        Some(Ok(role)) => role.to_ascii_lowercase(),
+        _ => fail!((
+            ErrorKind::TypeError,
+            "ROLE reply did not contain a role",
+            format!("{reply:?}")
+        )),
+    };
+    let (expected, accepted) = match required {
+        RequiredRole::Any => return Ok(()),
+        RequiredRole::Master => ("master", role == "master"),
+        RequiredRole::Replica => ("replica", role == "slave" || role == "replica"),
+    };
+    if accepted {
+        Ok(())
+    } else {
+        fail!((
+            ErrorKind::ResponseError,
+            "Redis server does not have the required role",
+            format!("expected a {expected}, but the server is a {role}")
+        ))
+    }
+}
+
+/// The error returned when a setup command replies with something other than `OK`.
+fn setup_command_refused(command: &Cmd, reply: &Value) -> RedisError {
+    RedisError::from((
+        ErrorKind::ResponseError,
+        "Redis server refused a setup command",
+        format!("{} replied with {reply:?}", command_name(command)),
+    ))
+}
+
+/// Builds a blocking pop command like `BLPOP`.  Whole seconds are sent as an integer, as servers
+/// before Redis 6.0 reject fractional timeouts.
+pub(crate) fn blocking_pop_cmd<K: ToRedisArgs>(name: &str, keys: K, timeout: Duration) -> Cmd {
+    let mut command = cmd(name);
+    command.arg(keys);
+    if timeout.subsec_nanos() == 0 {
+        command.arg(timeout.as_secs());
+    } else {
+        command.arg(timeout.as_secs_f64());
+    }
+    command
+}
+
+/// Builds a pipeline sending the handshake commands in a single round trip: `AUTH`, `HELLO` and
+/// `SELECT`.  Returns `None` if there are fewer than two of them, as pipelining doesn't save
+/// anything then.
+///
+/// Only these commands are pipelined, as they can safely be sent again one at a time if the
+/// pipeline fails.  Custom authentication commands are sent before and the setup commands after
+/// it, and their errors are returned as is.
+pub(crate) fn handshake_pipeline(
+    connection_info: &RedisConnectionConfig,
+) -> RedisResult<Option<Pipeline>> {
+    let mut pipeline = pipe();
+    let len = add_handshake_commands(connection_info, &mut pipeline)?;
+    Ok((len > 1).then_some(pipeline))
+}
+
+/// Adds the handshake commands to `pipeline`, as described for [`handshake_pipeline`], and
+/// returns how many were added.
+pub(crate) fn add_handshake_commands(
+    connection_info: &RedisConnectionConfig,
+    pipeline: &mut Pipeline,
+) -> RedisResult<usize> {
+    let mut len = 0;
+    if matches!(connection_info.auth_strategy, AuthStrategy::Auth) {
+        if let Some(ref password) = connection_info.password {
+            pipeline
+                .cmd("AUTH")
+                .arg(&connection_info.username)
+                .arg(password);
+            len += 1;
+        }
+    }
+    if connection_info.protocol == ProtocolVersion::RESP3 {
+        check_resp3_supported()?;
+        pipeline.cmd("HELLO").arg(3);
+        len += 1;
+    }
+    if connection_info.should_select() {
+        pipeline.cmd("SELECT").arg(connection_info.db);
+        len += 1;
+    }
+    Ok(len)
+}
+
+/// Builds a pipeline sending the setup commands, or `None` if there are none.
+pub(crate) fn setup_commands_pipeline(connection_info: &RedisConnectionConfig) -> Option<Pipeline> {
+    if connection_info.setup_commands.is_empty() {
+        return None;
+    }
+    let mut pipeline = pipe();
+    for command in &connection_info.setup_commands {
+        pipeline.add_command(command.clone());
+    }
+    Some(pipeline)
+}
+
+/// Checks that every setup command replied with `OK`.
+pub(crate) fn check_setup_replies(
+    connection_info: &RedisConnectionConfig,
+    replies: &[Value],
+) -> RedisResult<()> {
+    for (command, reply) in connection_info.setup_commands.iter().zip(replies) {
+        if reply != &Value::Okay {
+            return Err(setup_command_refused(command, reply));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the replies to the [`handshake_pipeline`], returning the negotiated protocol version.
+///
+/// Returns `None` if any command failed.  The handshake is then repeated one command at a time,
+/// which falls back to older forms of the commands where possible, e.g. `AUTH` without a
+/// username, and otherwise reports the exact error.
+pub(crate) fn protocol_from_handshake(
+    connection_info: &RedisConnectionConfig,
+    replies: RedisResult<Vec<Value>>,
+) -> Option<ProtocolVersion> {
+    let replies = replies.ok()?;
+    let mut replies = replies.iter();
+    if matches!(connection_info.auth_strategy, AuthStrategy::Auth)
+        && connection_info.password.is_some()
+        && replies.next()? != &Value::Okay
+    {
+        return None;
+    }
+    let protocol = match connection_info.protocol {
+        ProtocolVersion::RESP2 => ProtocolVersion::RESP2,
+        ProtocolVersion::RESP3 => protocol_from_hello(replies.next()?).ok()?,
+    };
+    if connection_info.should_select() && replies.next()? != &Value::Okay {
+        return None;
+    }
+    Some(protocol)
+}
+
+/// Fails if RESP3 replies can't be parsed because the `resp3` feature is disabled.
+pub(crate) fn check_resp3_supported() -> RedisResult<()> {
+    if cfg!(feature = "resp3") {
+        Ok(())
+    } else {
+        fail!((
+            ErrorKind::InvalidClientConfig,
+            "RESP3 requires the resp3 feature"
+        ))
+    }
+}
+
+/// Determines the negotiated protocol version from the reply to `HELLO`.
+///
+/// The `proto` field of the reply is authoritative: a server that answers `HELLO 3` with
+/// `proto` set to `2` did not switch protocols.
+pub(crate) fn protocol_from_hello(reply: &Value) -> RedisResult<ProtocolVersion> {
+    let fields: HashMap<String, Value> = from_redis_value(reply)?;
+    match fields.get("proto").map(from_redis_value::<i64>) {
+        Some(Ok(2)) => Ok(ProtocolVersion::RESP2),
+        Some(Ok(3)) => Ok(ProtocolVersion::RESP3),
+        _ => fail!((
+            ErrorKind::ResponseError,
+            "HELLO reply did not contain a supported protocol version",
+            format!("{reply:?}")
+        )),
+    }
+}
+
+fn select_db(con: &mut Connection, db: i64) -> RedisResult<()> {
+    match cmd("SELECT").arg(db).query::<Value>(con) {
+        Ok(Value::Okay) => Ok(()),
+        _ => fail!((
+            ErrorKind::ResponseError,
+            "Redis server refused to switch database"
+        )),
+    }
+}
+
 fn setup_connection(
     con: ActualConnection,
-    connection_info: &RedisConnectionInfo,
+    connection_info: &RedisConnectionConfig,
 ) -> RedisResult<Connection> {
     let mut rv = Connection {
         con,
         parser: Parser::new(),
         db: connection_info.db,
+        connection_info: connection_info.clone(),
+        protocol: ProtocolVersion::RESP2,
+        command_table: None,
         pubsub: false,
     };
 
-    if connection_info.password.is_some() {
-        connect_auth(&mut rv, connection_info)?;
+    authenticate_and_select(&mut rv, connection_info)?;
+
+    if connection_info.required_role != RequiredRole::Any {
+        let reply = cmd("ROLE").query(&mut rv)?;
+        check_role(connection_info.required_role, &reply)?;
     }
 
-    if connection_info.db != 0 {
-        match cmd("SELECT")
-            .arg(connection_info.db)
-            .query::<Value>(&mut rv)
-        {
-            Ok(Value::Okay) => {}
-            _ => fail!((
-                ErrorKind::ResponseError,
-                "Redis server refused to switch database"
-            )),
+    // Error replies are ignored, as per the command's instructions, but a timeout leaves the
+    // response unread.
+    // https://redis.io/commands/client-setinfo/
+    if let Err(err) = client_set_info_pipeline().query::<()>(&mut rv) {
+        if err.is_unrecoverable_error() {
+            return Err(err);
         }
     }
 
-    // result is ignored, as per the command's instructions.
-    // https://redis.io/commands/client-setinfo/
-    let _: RedisResult<()> = client_set_info_pipeline().query(&mut rv);
+    if cfg!(debug_assertions) && connection_info.validate_commands {
+        rv.command_table = load_command_table(command_table_cmd().query(&mut rv))?;
+    }
 
     Ok(rv)
 }
@@ -880,6 +1903,11 @@ pub trait ConnectionLike {
     /// Sends multiple already encoded (packed) command into the TCP socket
     /// and reads `count` responses from it.  This is used to implement
     /// pipelining.
+    ///
+    /// Errors returned by the server for individual commands do not stop the
+    /// remaining responses from being read.  If reading a response fails
+    /// because of an I/O or parse error, the connection is closed, as the
+    /// remaining responses can't be read reliably anymore.
     fn req_packed_commands(
         &mut self,
         cmd: &[u8],
@@ -893,6 +1921,17 @@ pub trait ConnectionLike {
         self.req_packed_command(&pcmd)
     }
 
+    /// Writes pre-encoded RESP bytes as they are and reads `expected_responses` responses, e.g.
+    /// to replay captured traffic or to send commands that can't be built with [Cmd].
+    ///
+    /// This shares the read path of [`req_packed_commands`](Self::req_packed_commands).  Nothing
+    /// checks that `expected_responses` matches the number of commands in `bytes`.  If it
+    /// doesn't, the responses get out of step with the requests and all following requests on
+    /// the connection receive the wrong responses.
+    fn req_raw(&mut self, bytes: &[u8], expected_responses: usize) -> RedisResult<Vec<Value>> {
+        self.req_packed_commands(bytes, 0, expected_responses)
+    }
+
     /// Returns the database this connection is bound to.  Note that this
     /// information might be unreliable because it's initially cached and
     /// also might be incorrect if the connection like object is not
@@ -926,13 +1965,42 @@ pub trait ConnectionLike {
 /// You generally do not much with this object other than passing it to
 /// `Cmd` objects.
 impl Connection {
+    /// Creates a connection from a TCP stream that is already connected, e.g. one handed over
+    /// by socket activation, and performs the handshake configured in `connection_info`.
+    ///
+    /// Timeouts are taken from the stream as is; use
+    /// [`set_read_timeout`](Self::set_read_timeout) and
+    /// [`set_write_timeout`](Self::set_write_timeout) to change them.
+    pub fn from_tcp_stream(
+        stream: TcpStream,
+        connection_info: &RedisConnectionInfo,
+    ) -> RedisResult<Connection> {
+        let con = ActualConnection::Tcp(TcpConnection {
+            reader: stream,
+            open: true,
+        });
+        setup_connection(con, &connection_info.clone().into())
+    }
+
+    /// Like [`from_tcp_stream`](Self::from_tcp_stream), but for a Unix socket.
+    #[cfg(unix)]
+    pub fn from_unix_stream(
+        stream: UnixStream,
+        connection_info: &RedisConnectionInfo,
+    ) -> RedisResult<Connection> {
+        let con = ActualConnection::Unix(UnixConnection {
+            sock: stream,
+            open: true,
+        });
+        setup_connection(con, &connection_info.clone().into())
+    }
+
     /// Sends an already encoded (packed) command into the TCP socket and
     /// does not read a response.  This is useful for commands like
     /// `MONITOR` which yield multiple items.  This needs to be used with
     /// care because it changes the state of the connection.
     pub fn send_packed_command(&mut self, cmd: &[u8]) -> RedisResult<()> {
-        self.con.send_bytes(cmd)?;
-        Ok(())
+        self.send_request(cmd)
     }
 
     /// Fetches a single response from the connection.  This is useful
@@ -959,6 +2027,124 @@ impl Connection {
         self.con.set_read_timeout(dur)
     }
 
+    /// Pops an element from the first non-empty list in `keys`, blocking for up to `timeout`
+    /// if all of them are empty.  Returns the name of the list and the element, or `None` if
+    /// the timeout elapsed.  A zero `timeout` blocks indefinitely.
+    ///
+    /// Unlike [`Commands::blpop`](crate::Commands::blpop), this extends the read timeout of
+    /// the connection (e.g. the configured response timeout) by `timeout` for the duration of
+    /// the call, or disables it for a zero `timeout`, so that the client doesn't give up before
+    /// the server does.  The read timeout is restored afterwards.
+    ///
+    /// Blocking commands stall every request queued behind them, so this is only offered on
+    /// dedicated connections, i.e. this one and `aio::Connection`, and
+    /// not on multiplexed connections.
+    ///
+    /// ```rust,no_run
+    /// # fn do_something() -> redis::RedisResult<()> {
+    /// # let client = redis::Client::open("redis://127.0.0.1/")?;
+    /// let mut con = client.get_connection()?;
+    /// let popped: Option<(String, String)> =
+    ///     con.blpop_timeout(&["jobs:high", "jobs:low"], std::time::Duration::from_secs(5))?;
+    /// # Ok(()) }
+    /// ```
+    pub fn blpop_timeout<K: ToRedisArgs, V: FromRedisValue>(
+        &mut self,
+        keys: K,
+        timeout: Duration,
+    ) -> RedisResult<Option<(String, V)>> {
+        self.blocking_pop("BLPOP", keys, timeout)
+    }
+
+    /// Like [`blpop_timeout`](Self::blpop_timeout), but pops from the tail of the lists
+    /// (`BRPOP`).
+    pub fn brpop_timeout<K: ToRedisArgs, V: FromRedisValue>(
+        &mut self,
+        keys: K,
+        timeout: Duration,
+    ) -> RedisResult<Option<(String, V)>> {
+        self.blocking_pop("BRPOP", keys, timeout)
+    }
+
+    fn blocking_pop<K: ToRedisArgs, V: FromRedisValue>(
+        &mut self,
+        name: &str,
+        keys: K,
+        timeout: Duration,
+    ) -> RedisResult<Option<(String, V)>> {
+        let read_timeout = self.con.read_timeout()?;
+        let blocking_read_timeout = if timeout.is_zero() {
+            None
+        } else {
+            read_timeout.map(|dur| dur + timeout)
+        };
+        self.con.set_read_timeout(blocking_read_timeout)?;
+        let result = blocking_pop_cmd(name, keys, timeout).query(self);
+        self.con.set_read_timeout(read_timeout)?;
+        result
+    }
+
+    /// Calls `f` with the underlying TCP socket, e.g. to set socket options that aren't
+    /// configurable otherwise.  For TLS connections this is the socket below the TLS layer.
+    ///
+    /// Returns `None` without calling `f` for Unix socket connections.
+    ///
+    /// This is an advanced API: reading from or writing to the socket directly corrupts the
+    /// connection.  Options should be set right after connecting, before sending commands.
+    pub fn with_tcp_stream<T>(&self, f: impl FnOnce(&TcpStream) -> T) -> Option<T> {
+        self.con.tcp_stream().map(f)
+    }
+
+    /// Returns the protocol version negotiated with the server while setting up the connection.
+    ///
+    /// This may be [`ProtocolVersion::RESP2`] even if RESP3 was requested, when the server
+    /// doesn't support it.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol
+    }
+
+    /// Resets the state of the connection with `RESET`, then authenticates, negotiates the
+    /// protocol and selects the database again as configured when the connection was opened.
+    ///
+    /// `RESET` discards any transaction in progress, unwatches all keys, leaves the pub/sub and
+    /// monitor modes and deauthenticates the connection, which makes it a good fit for recycling
+    /// pooled connections.  Servers before Redis 6.2 don't know `RESET`; for those only the
+    /// configured database is selected again.
+    pub fn reset(&mut self) -> RedisResult<()> {
+        match cmd("RESET").query::<Value>(self) {
+            Ok(Value::Status(ref status)) if status == "RESET" => {
+                let connection_info = self.connection_info.clone();
+                authenticate_and_select(self, &connection_info)
+            }
+            Ok(_) => fail!((
+                ErrorKind::ResponseError,
+                "Redis server refused to reset the connection"
+            )),
+            Err(e) if e.is_unknown_command() => {
+                if self.connection_info.select_strategy == SelectStrategy::Never {
+                    return Ok(());
+                }
+                let db = self.connection_info.db;
+                select_db(self, db)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Closes the connection gracefully by sending `QUIT`, waiting for the server to acknowledge
+    /// it and then shutting down the socket.
+    pub fn quit(mut self) -> RedisResult<()> {
+        match cmd("QUIT").query::<Value>(&mut self)? {
+            Value::Okay => {}
+            _ => fail!((
+                ErrorKind::ResponseError,
+                "Redis server refused to close the connection"
+            )),
+        }
+        self.con.shutdown();
+        Ok(())
+    }
+
     /// Creates a [`PubSub`] instance for this connection.
     pub fn as_pubsub(&mut self) -> PubSub<'_> {
         // NOTE: The pubsub flag is intentionally not raised at this time since
@@ -994,13 +2180,9 @@ impl Connection {
             let unsubscribe = cmd("UNSUBSCRIBE").get_packed_command();
             let punsubscribe = cmd("PUNSUBSCRIBE").get_packed_command();
 
-            // Grab a reference to the underlying connection so that we may send
-            // the commands without immediately blocking for a response.
-            let con = &mut self.con;
-
-            // Execute commands
-            con.send_bytes(&unsubscribe)?;
-            con.send_bytes(&punsubscribe)?;
+            // Send the commands without immediately blocking for a response.
+            self.send_request(&unsubscribe)?;
+            self.send_request(&punsubscribe)?;
         }
 
         // Receive responses
@@ -1029,6 +2211,19 @@ impl Connection {
         Ok(())
     }
 
+    /// Writes a request to the connection.
+    fn send_request(&mut self, bytes: &[u8]) -> RedisResult<()> {
+        if let Some(ref table) = self.command_table {
+            table.validate_packed(bytes)?;
+        }
+        #[cfg(feature = "debug-logging")]
+        if self.connection_info.log_commands {
+            crate::logging::log_request(bytes);
+        }
+        self.con.send_bytes(bytes)?;
+        Ok(())
+    }
+
     /// Fetches a single response from the connection.
     fn read_response(&mut self) -> RedisResult<Value> {
         let result = match self.con {
@@ -1057,29 +2252,13 @@ impl Connection {
                 None => false,
             };
             if shutdown {
-                match self.con {
-                    ActualConnection::Tcp(ref mut connection) => {
-                        let _ = connection.reader.shutdown(net::Shutdown::Both);
-                        connection.open = false;
-                    }
-                    #[cfg(all(feature = "tls-native-tls", not(feature = "tls-rustls")))]
-                    ActualConnection::TcpNativeTls(ref mut connection) => {
-                        let _ = connection.reader.shutdown();
-                        connection.open = false;
-                    }
-                    #[cfg(feature = "tls-rustls")]
-                    ActualConnection::TcpRustls(ref mut connection) => {
-                        let _ = connection.reader.get_mut().shutdown(net::Shutdown::Both);
-                        connection.open = false;
-                    }
-                    #[cfg(unix)]
-                    ActualConnection::Unix(ref mut connection) => {
-                        let _ = connection.sock.shutdown(net::Shutdown::Both);
-                        connection.open = false;
-                    }
-                }
+                self.con.shutdown();
             }
         }
+        #[cfg(feature = "debug-logging")]
+        if self.connection_info.log_commands {
+            crate::logging::log_response(&result);
+        }
         result
     }
 }
@@ -1090,7 +2269,7 @@ impl ConnectionLike for Connection {
             self.exit_pubsub()?;
         }
 
-        self.con.send_bytes(cmd)?;
+        self.send_request(cmd)?;
         self.read_response()
     }
 
@@ -1103,8 +2282,8 @@ impl ConnectionLike for Connection {
         if self.pubsub {
             self.exit_pubsub()?;
         }
-        self.con.send_bytes(cmd)?;
-        let mut rv = vec![];
+        self.send_request(cmd)?;
+        let mut responses = Vec::with_capacity(count);
         let mut first_err = None;
         for idx in 0..(offset + count) {
             // When processing a transaction, some responses may be errors.
@@ -1113,11 +2292,14 @@ impl ConnectionLike for Connection {
             // See: https://github.com/redis-rs/redis-rs/issues/436
             let response = self.read_response();
             match response {
-                Ok(item) => {
-                    if idx >= offset {
-                        rv.push(item);
-                    }
+                Err(err) if err.is_unrecoverable_error() => {
+                    // After an I/O or parse error the remaining responses can't be read
+                    // reliably, so close the connection instead of leaving it desynchronized.
+                    self.con.shutdown();
+                    return Err(err.with_partial_responses(responses));
                 }
+                response if idx >= offset => responses.push(response),
+                Ok(_) => {}
                 Err(err) => {
                     if first_err.is_none() {
                         first_err = Some(err);
@@ -1126,7 +2308,7 @@ impl ConnectionLike for Connection {
             }
         }
 
-        first_err.map_or(Ok(rv), Err)
+        first_err.map_or_else(|| responses.into_iter().collect(), Err)
     }
 
     fn get_db(&self) -> i64 {
@@ -1337,6 +2519,83 @@ impl Msg {
             Some(ref x) => from_redis_value(x),
         }
     }
+
+    /// Parses the message as a keyspace notification, or returns `None` if it was not received
+    /// on a keyspace or keyevent channel.
+    pub fn keyspace_event(&self) -> Option<KeyspaceEvent> {
+        let channel = match self.channel {
+            Value::Data(ref bytes) => bytes,
+            _ => return None,
+        };
+        let (is_keyevent, rest) = if let Some(rest) = channel.strip_prefix(b"__keyevent@") {
+            (true, rest)
+        } else {
+            (false, channel.strip_prefix(b"__keyspace@")?)
+        };
+        let separator = rest.windows(3).position(|window| window == b"__:")?;
+        let db = from_utf8(&rest[..separator]).ok()?.parse().ok()?;
+        let suffix = &rest[separator + 3..];
+        let (event, key) = if is_keyevent {
+            (suffix, self.get_payload_bytes())
+        } else {
+            (self.get_payload_bytes(), suffix)
+        };
+        Some(KeyspaceEvent {
+            db,
+            event: from_utf8(event).ok()?.to_string(),
+            key: key.to_vec(),
+        })
+    }
+
+    /// Parses the message as an invalidation sent for keys tracked with `CLIENT TRACKING`, or
+    /// returns `None` if it was not received on the `__redis__:invalidate` channel.
+    pub fn invalidation(&self) -> Option<Invalidation> {
+        match self.channel {
+            Value::Data(ref bytes) if bytes == INVALIDATION_CHANNEL.as_bytes() => {}
+            _ => return None,
+        }
+        match self.payload {
+            Value::Nil => Some(Invalidation::All),
+            Value::Bulk(ref keys) => keys
+                .iter()
+                .map(|key| match key {
+                    Value::Data(key) => Some(key.clone()),
+                    _ => None,
+                })
+                .collect::<Option<_>>()
+                .map(Invalidation::Keys),
+            _ => None,
+        }
+    }
+}
+
+/// The channel the server sends invalidations of tracked keys to, when they are redirected to
+/// another connection.
+pub(crate) const INVALIDATION_CHANNEL: &str = "__redis__:invalidate";
+
+/// An invalidation of keys cached on the client side, parsed from a message on the
+/// `__redis__:invalidate` channel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Invalidation {
+    /// The keys were modified or expired, so their cached values are stale.
+    Keys(Vec<Vec<u8>>),
+    /// The database was flushed, so all cached values are stale.
+    All,
+}
+
+/// A keyspace notification, parsed from a message on a `__keyspace@<db>__:<key>` or
+/// `__keyevent@<db>__:<event>` channel.
+///
+/// Notifications are only sent if they are enabled on the server with the
+/// `notify-keyspace-events` configuration option.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyspaceEvent {
+    /// The database containing the key.
+    pub db: i64,
+    /// The event, such as `set`, `del` or `expired`.
+    pub event: String,
+    /// The key the event happened to.
+    pub key: Vec<u8>,
 }
 
 /// This function simplifies transaction management slightly.  What it
@@ -1404,6 +2663,338 @@ pub fn transaction<
 mod tests {
     use super::*;
 
+    // Sets up a connection over the given stream, with options `from_tcp_stream` can't take.
+    fn connect_stream(stream: TcpStream, info: &RedisConnectionConfig) -> RedisResult<Connection> {
+        let con = ActualConnection::Tcp(TcpConnection {
+            reader: stream,
+            open: true,
+        });
+        setup_connection(con, info)
+    }
+
+    #[test]
+    fn test_aggregate_connect_errors() {
+        let err = aggregate_connect_errors::<SocketAddr>(vec![]);
+        assert_eq!(err.kind(), ErrorKind::InvalidClientConfig);
+
+        let refused = || RedisError::from(io::Error::from(io::ErrorKind::ConnectionRefused));
+        let err = aggregate_connect_errors(vec![(
+            "127.0.0.1:1".parse::<SocketAddr>().unwrap(),
+            refused(),
+        )]);
+        assert!(err.is_connection_refusal());
+
+        let err = aggregate_connect_errors(vec![
+            ("127.0.0.1:1".parse::<SocketAddr>().unwrap(), refused()),
+            ("[::1]:1".parse::<SocketAddr>().unwrap(), refused()),
+        ]);
+        assert!(err.is_connection_refusal());
+        let message = err.to_string();
+        assert!(message.contains("127.0.0.1:1"), "{message}");
+        assert!(message.contains("[::1]:1"), "{message}");
+    }
+
+    #[test]
+    fn test_debug_redacts_credentials() {
+        let builder = ConnectionBuilder::tcp("example.com", 6380)
+            .username("admin")
+            .password("hunter2");
+        let info = builder.clone().into_connection_info().unwrap();
+        for debug in [format!("{builder:?}"), format!("{info:?}")] {
+            assert!(
+                !debug.contains("admin") && !debug.contains("hunter2"),
+                "{debug}"
+            );
+            assert!(debug.contains(r#"password: Some("<redacted>")"#), "{debug}");
+        }
+
+        let client = builder.build().unwrap();
+        assert!(!format!("{client:?}").contains("hunter2"));
+    }
+
+    #[test]
+    fn test_connection_builder() {
+        let info = ConnectionBuilder::tcp("example.com", 6380)
+            .tls(TlsMode::Insecure)
+            .tls_server_name("redis.internal")
+            .db(2)
+            .username("user")
+            .password("pass")
+            .response_timeout(Duration::from_secs(1))
+            .local_addr("127.0.0.1:0".parse().unwrap())
+            .setup_command(cmd("CLIENT").arg("NO-EVICT").arg("on").clone())
+            .log_commands(true)
+            .tls_certificate_check(TlsCertificateCheck::new(|der| der.starts_with(&[0x30])))
+            .into_config()
+            .unwrap();
+        assert_eq!(
+            info.addr,
+            ConnectionAddr::TcpTls {
+                host: "example.com".to_string(),
+                port: 6380,
+                insecure: true,
+            }
+        );
+        assert_eq!(info.redis.db, 2);
+        assert_eq!(info.redis.username.as_deref(), Some("user"));
+        assert_eq!(info.redis.password.as_deref(), Some("pass"));
+        assert_eq!(info.connect_timeout, None);
+        assert_eq!(info.response_timeout, Some(Duration::from_secs(1)));
+        assert_eq!(info.tls_server_name.as_deref(), Some("redis.internal"));
+        assert_eq!(info.local_addr, Some("127.0.0.1:0".parse().unwrap()));
+        assert_eq!(info.redis.setup_commands.len(), 1);
+        assert!(info.redis.log_commands);
+        assert!(format!("{:?}", info.redis).contains(r#"setup_commands: ["CLIENT"]"#));
+        let check = info.tls_certificate_check.unwrap();
+        assert!(check.verify(Some(&[0x30, 0x82])).is_ok());
+        assert!(check.verify(Some(&[0x00])).is_err());
+        assert!(check.verify(None).is_err());
+
+        let invalid = [
+            ConnectionBuilder::unix("/var/run/redis.sock").tls(TlsMode::Secure),
+            ConnectionBuilder::tcp("127.0.0.1", 6379).username("user"),
+            ConnectionBuilder::tcp("127.0.0.1", 6379).connect_timeout(Duration::ZERO),
+            ConnectionBuilder::tcp("127.0.0.1", 6379).tls_server_name("redis.internal"),
+            ConnectionBuilder::tcp("127.0.0.1", 6379)
+                .tls_certificate_check(TlsCertificateCheck::new(|_| true)),
+        ];
+        for builder in invalid {
+            let err = builder.build().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidClientConfig);
+        }
+    }
+
+    #[test]
+    fn test_keyspace_event() {
+        let msg = |channel: &[u8], payload: &[u8]| {
+            Msg::from_value(&Value::Bulk(vec![
+                Value::Data(b"pmessage".to_vec()),
+                Value::Data(b"__key*__:*".to_vec()),
+                Value::Data(channel.to_vec()),
+                Value::Data(payload.to_vec()),
+            ]))
+            .unwrap()
+        };
+        let expected = Some(KeyspaceEvent {
+            db: 3,
+            event: "expired".to_string(),
+            key: b"foo:bar".to_vec(),
+        });
+
+        assert_eq!(
+            msg(b"__keyevent@3__:expired", b"foo:bar").keyspace_event(),
+            expected
+        );
+        assert_eq!(
+            msg(b"__keyspace@3__:foo:bar", b"expired").keyspace_event(),
+            expected
+        );
+        assert_eq!(msg(b"news", b"expired").keyspace_event(), None);
+        assert_eq!(msg(b"__keyevent@x__:del", b"foo").keyspace_event(), None);
+    }
+
+    #[test]
+    fn test_invalidation() {
+        let msg = |channel: &[u8], payload: Value| {
+            Msg::from_value(&Value::Bulk(vec![
+                Value::Data(b"message".to_vec()),
+                Value::Data(channel.to_vec()),
+                payload,
+            ]))
+            .unwrap()
+        };
+        let keys = Value::Bulk(vec![
+            Value::Data(b"foo".to_vec()),
+            Value::Data(b"bar".to_vec()),
+        ]);
+
+        assert_eq!(
+            msg(b"__redis__:invalidate", keys.clone()).invalidation(),
+            Some(Invalidation::Keys(vec![b"foo".to_vec(), b"bar".to_vec()]))
+        );
+        assert_eq!(
+            msg(b"__redis__:invalidate", Value::Nil).invalidation(),
+            Some(Invalidation::All)
+        );
+        assert_eq!(msg(b"news", keys).invalidation(), None);
+    }
+
+    #[test]
+    fn test_protocol_from_hello() {
+        let hello = |proto: i64| {
+            Value::Bulk(vec![
+                Value::Data(b"server".to_vec()),
+                Value::Data(b"redis".to_vec()),
+                Value::Data(b"proto".to_vec()),
+                Value::Int(proto),
+            ])
+        };
+        assert_eq!(
+            protocol_from_hello(&hello(3)).unwrap(),
+            ProtocolVersion::RESP3
+        );
+        assert_eq!(
+            protocol_from_hello(&hello(2)).unwrap(),
+            ProtocolVersion::RESP2
+        );
+        assert!(protocol_from_hello(&hello(4)).is_err());
+        assert!(protocol_from_hello(&Value::Okay).is_err());
+
+        #[cfg(feature = "resp3")]
+        assert_eq!(
+            protocol_from_hello(&Value::Map(vec![(
+                Value::Data(b"proto".to_vec()),
+                Value::Int(3)
+            )]))
+            .unwrap(),
+            ProtocolVersion::RESP3
+        );
+    }
+
+    #[test]
+    fn test_failed_handshake_pipeline_does_not_repeat_setup_commands() {
+        use std::io::Read;
+
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let wrong_args = "-ERR wrong number of arguments for 'auth' command\r\n";
+            let replies = [
+                // The pipelined `AUTH user secret` and `SELECT`
+                wrong_args,
+                "+OK\r\n",
+                // `AUTH user secret`, `AUTH secret` and `SELECT` sent one at a time
+                wrong_args,
+                "+OK\r\n+OK\r\n",
+                // The setup command and `CLIENT SETINFO`
+                "+OK\r\n+OK\r\n+OK\r\n",
+            ];
+            stream.write_all(replies.concat().as_bytes()).unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let setup = cmd("CLIENT").arg("SETNAME").arg("once").clone();
+        let info = RedisConnectionConfig {
+            db: 2,
+            username: Some("user".to_string()),
+            password: Some("secret".to_string()),
+            setup_commands: vec![setup.clone()],
+            ..Default::default()
+        };
+        let con = connect_stream(TcpStream::connect(addr).unwrap(), &info).unwrap();
+        drop(con);
+
+        let received = server.join().unwrap();
+        let packed = setup.get_packed_command();
+        let count = received
+            .windows(packed.len())
+            .filter(|window| *window == &packed[..])
+            .count();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_validate_commands_of_pipelines() {
+        use std::io::Read;
+
+        // Serves `CLIENT SETINFO` and the given reply to `COMMAND`, returning what was received.
+        let serve = |command_reply: &'static str| {
+            let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let server = std::thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                stream
+                    .write_all(format!("+OK\r\n+OK\r\n{command_reply}").as_bytes())
+                    .unwrap();
+                let mut received = Vec::new();
+                stream.read_to_end(&mut received).unwrap();
+                received
+            });
+            (addr, server)
+        };
+        let info = RedisConnectionConfig {
+            validate_commands: true,
+            ..Default::default()
+        };
+        let invalid = crate::pipe().get("a").add_command(cmd("GET")).clone();
+
+        let (addr, server) = serve("*1\r\n*6\r\n$3\r\nget\r\n:2\r\n*0\r\n:1\r\n:1\r\n:1\r\n");
+        let mut con = connect_stream(TcpStream::connect(addr).unwrap(), &info).unwrap();
+        let err = invalid.query::<()>(&mut con).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidClientConfig);
+        drop(con);
+        let received = server.join().unwrap();
+        // Neither command of the pipeline was sent.
+        assert!(!received.windows(3).any(|window| window == b"GET"));
+
+        // A server refusing `COMMAND` is used without validation.
+        let (addr, server) = serve("-ERR unknown command 'COMMAND'\r\n");
+        let con = connect_stream(TcpStream::connect(addr).unwrap(), &info).unwrap();
+        assert!(con.command_table.is_none());
+        drop(con);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_blocking_pop_cmd() {
+        let whole = blocking_pop_cmd("BLPOP", "list", Duration::from_secs(5));
+        assert_eq!(
+            whole.get_packed_command(),
+            cmd("BLPOP").arg("list").arg(5).get_packed_command()
+        );
+        let fractional = blocking_pop_cmd("BRPOP", &["a", "b"], Duration::from_millis(1500));
+        assert_eq!(
+            fractional.get_packed_command(),
+            cmd("BRPOP")
+                .arg(&["a", "b"])
+                .arg("1.5")
+                .get_packed_command()
+        );
+    }
+
+    #[test]
+    fn test_handshake_pipeline() {
+        let mut info = RedisConnectionConfig::default();
+        assert!(handshake_pipeline(&info).unwrap().is_none());
+
+        info.password = Some("secret".to_string());
+        assert!(handshake_pipeline(&info).unwrap().is_none());
+
+        info.db = 2;
+        info.setup_commands
+            .push(cmd("CLIENT").arg("NO-EVICT").arg("on").clone());
+        // The setup commands are never pipelined with the handshake.
+        let pipeline = handshake_pipeline(&info).unwrap().unwrap();
+        assert_eq!(
+            pipeline.get_packed_pipeline(),
+            [
+                cmd("AUTH").arg("secret").get_packed_command(),
+                cmd("SELECT").arg(2).get_packed_command(),
+            ]
+            .concat()
+        );
+
+        assert_eq!(
+            protocol_from_handshake(&info, Ok(vec![Value::Okay; 2])),
+            Some(ProtocolVersion::RESP2)
+        );
+        assert_eq!(
+            protocol_from_handshake(&info, Ok(vec![Value::Okay, Value::Int(0)])),
+            None
+        );
+        assert_eq!(protocol_from_handshake(&info, Ok(vec![Value::Okay])), None);
+
+        info.auth_strategy = AuthStrategy::Commands(vec![cmd("AUTH").arg("token").clone()]);
+        assert!(handshake_pipeline(&info).unwrap().is_none());
+        assert_eq!(
+            protocol_from_handshake(&info, Err((ErrorKind::AuthenticationFailed, "").into())),
+            None
+        );
+    }
+
     #[test]
     fn test_parse_redis_url() {
         let cases = vec![
@@ -1503,6 +3094,92 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_connection_info_from_var() {
+        let var = |value: &str| Ok(value.to_string());
+        let info = connection_info_from_var("REDIS_URL", var("redis://127.0.0.1:6380/2")).unwrap();
+        assert_eq!(
+            info.addr,
+            ConnectionAddr::Tcp("127.0.0.1".to_string(), 6380)
+        );
+        assert_eq!(info.redis.db, 2);
+
+        let err = connection_info_from_var("REDIS_URL", var("not a url")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidClientConfig);
+        assert!(err.to_string().starts_with("Redis URL did not parse"));
+
+        let err =
+            connection_info_from_var("REDIS_URL", Err(std::env::VarError::NotPresent)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidClientConfig);
+        assert!(err
+            .to_string()
+            .starts_with("Redis URL environment variable is not set"));
+        assert_eq!(err.detail(), Some("REDIS_URL"));
+    }
+
+    #[test]
+    fn test_url_options() {
+        let info = "redis://127.0.0.1/3?protocol=RESP3&timeout=1.5"
+            .into_connection_builder()
+            .and_then(ConnectionBuilder::into_config)
+            .unwrap();
+        assert_eq!(info.redis.db, 3);
+        assert_eq!(info.redis.protocol, ProtocolVersion::RESP3);
+        assert_eq!(info.connect_timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(info.response_timeout, Some(Duration::from_millis(1500)));
+
+        let info = "redis://127.0.0.1?connect_timeout=2&handshake_timeout=0.5&unknown=1"
+            .into_connection_builder()
+            .and_then(ConnectionBuilder::into_config)
+            .unwrap();
+        assert_eq!(info.connect_timeout, Some(Duration::from_secs(2)));
+        assert_eq!(info.handshake_timeout, Some(Duration::from_millis(500)));
+        assert_eq!(info.response_timeout, None);
+
+        let cases = [
+            ("redis://127.0.0.1?protocol=resp4", "resp4"),
+            ("redis://127.0.0.1?timeout=0", "timeout=0"),
+            (
+                "redis://127.0.0.1?response_timeout=soon",
+                "response_timeout=soon",
+            ),
+            ("http://127.0.0.1", "unsupported scheme \"http\""),
+            ("redis://127.0.0.1:port", "invalid port number"),
+        ];
+        for (url, detail) in cases {
+            let err = url.parse::<ConnectionInfo>().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidClientConfig, "{url}");
+            assert_eq!(err.detail(), Some(detail), "{url}");
+        }
+    }
+
+    #[test]
+    fn test_check_role() {
+        let role = |name: &str| {
+            Value::Bulk(vec![
+                Value::Data(name.as_bytes().to_vec()),
+                Value::Int(0),
+                Value::Bulk(vec![]),
+            ])
+        };
+        assert!(check_role(RequiredRole::Any, &role("slave")).is_ok());
+        assert!(check_role(RequiredRole::Master, &role("master")).is_ok());
+        assert!(check_role(RequiredRole::Replica, &role("slave")).is_ok());
+
+        let err = check_role(RequiredRole::Master, &role("slave")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseError);
+        assert_eq!(
+            err.detail(),
+            Some("expected a master, but the server is a slave")
+        );
+        let err = check_role(RequiredRole::Replica, &role("master")).unwrap_err();
+        assert_eq!(
+            err.detail(),
+            Some("expected a replica, but the server is a master")
+        );
+        assert!(check_role(RequiredRole::Master, &Value::Okay).is_err());
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_url_to_unix_connection_info() {
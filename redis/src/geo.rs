@@ -71,6 +71,13 @@ impl<T> Coord<T> {
     }
 }
 
+impl<T> From<(T, T)> for Coord<T> {
+    /// Create a new Coord from a `(longitude, latitude)` tuple
+    fn from((longitude, latitude): (T, T)) -> Coord<T> {
+        Coord::lon_lat(longitude, latitude)
+    }
+}
+
 impl<T: FromRedisValue> FromRedisValue for Coord<T> {
     fn from_redis_value(v: &Value) -> RedisResult<Self> {
         let values: Vec<T> = FromRedisValue::from_redis_value(v)?;
@@ -305,10 +312,265 @@ impl RadiusSearchResult {
     }
 }
 
+/// The center of the area searched by [`geo_search`][1]
+///
+/// [1]: ../trait.Commands.html#method.geo_search
+pub enum SearchCenter {
+    /// Search around the position of a member of the sorted set (`FROMMEMBER`).  Members are
+    /// binary-safe, see [`member`](SearchCenter::member).
+    Member(Vec<u8>),
+    /// Search around the given coordinate (`FROMLONLAT`).
+    LonLat(Coord<f64>),
+}
+
+impl SearchCenter {
+    /// Search around the position of the given member, e.g. a `&str` or `&[u8]`.
+    pub fn member<M: ToRedisArgs>(member: M) -> Self {
+        SearchCenter::Member(member.to_redis_args().concat())
+    }
+}
+
+impl ToRedisArgs for SearchCenter {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        match *self {
+            SearchCenter::Member(ref member) => {
+                out.write_arg(b"FROMMEMBER");
+                out.write_arg(member);
+            }
+            SearchCenter::LonLat(ref coord) => {
+                out.write_arg(b"FROMLONLAT");
+                coord.write_redis_args(out);
+            }
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
+
+/// The shape of the area searched by [`geo_search`][1]
+///
+/// [1]: ../trait.Commands.html#method.geo_search
+pub enum SearchShape {
+    /// A circle with the given radius (`BYRADIUS`).
+    Radius(f64, Unit),
+    /// An axis-aligned rectangle with the given width and height (`BYBOX`).
+    Box {
+        /// The width of the rectangle
+        width: f64,
+        /// The height of the rectangle
+        height: f64,
+        /// The unit of the width and height
+        unit: Unit,
+    },
+}
+
+impl ToRedisArgs for SearchShape {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        match *self {
+            SearchShape::Radius(radius, ref unit) => {
+                out.write_arg(b"BYRADIUS");
+                radius.write_redis_args(out);
+                unit.write_redis_args(out);
+            }
+            SearchShape::Box {
+                width,
+                height,
+                ref unit,
+            } => {
+                out.write_arg(b"BYBOX");
+                width.write_redis_args(out);
+                height.write_redis_args(out);
+                unit.write_redis_args(out);
+            }
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
+
+/// Options for the [GEOSEARCH][1] command
+///
+/// [1]: https://redis.io/commands/geosearch
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use redis::{Commands, RedisResult};
+/// use redis::geo::{RadiusOrder, SearchCenter, SearchOptions, SearchResult, SearchShape, Unit};
+/// fn nearest_in_box(
+///     con: &mut redis::Connection,
+///     key: &str,
+///     longitude: f64,
+///     latitude: f64,
+/// ) -> RedisResult<Vec<SearchResult>> {
+///     let center = SearchCenter::LonLat((longitude, latitude).into());
+///     let shape = SearchShape::Box { width: 400.0, height: 400.0, unit: Unit::Kilometers };
+///     let opts = SearchOptions::default()
+///         .order(RadiusOrder::Asc)
+///         .limit(10)
+///         .with_dist();
+///     con.geo_search(key, center, shape, opts)
+/// }
+/// ```
+#[derive(Default)]
+pub struct SearchOptions {
+    order: RadiusOrder,
+    count: Option<usize>,
+    any: bool,
+    with_coord: bool,
+    with_dist: bool,
+    with_hash: bool,
+}
+
+impl SearchOptions {
+    /// Sort the returned items
+    pub fn order(mut self, o: RadiusOrder) -> Self {
+        self.order = o;
+        self
+    }
+
+    /// Limit the results to the first N matching items.
+    pub fn limit(mut self, n: usize) -> Self {
+        self.count = Some(n);
+        self
+    }
+
+    /// Return as soon as enough matching items were found, instead of the ones nearest to the
+    /// center.  Only used with `limit`.
+    pub fn any(mut self) -> Self {
+        self.any = true;
+        self
+    }
+
+    /// Return the `longitude, latitude` coordinates of the matching items.
+    pub fn with_coord(mut self) -> Self {
+        self.with_coord = true;
+        self
+    }
+
+    /// Return the distance of the returned items from the center, in the unit of the shape.
+    pub fn with_dist(mut self) -> Self {
+        self.with_dist = true;
+        self
+    }
+
+    /// Return the raw geohash-encoded sorted set score of the matching items.
+    pub fn with_hash(mut self) -> Self {
+        self.with_hash = true;
+        self
+    }
+}
+
+impl ToRedisArgs for SearchOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        match self.order {
+            RadiusOrder::Asc => out.write_arg(b"ASC"),
+            RadiusOrder::Desc => out.write_arg(b"DESC"),
+            _ => (),
+        };
+
+        if let Some(n) = self.count {
+            out.write_arg(b"COUNT");
+            out.write_arg_fmt(n);
+            if self.any {
+                out.write_arg(b"ANY");
+            }
+        }
+
+        if self.with_coord {
+            out.write_arg(b"WITHCOORD");
+        }
+
+        if self.with_dist {
+            out.write_arg(b"WITHDIST");
+        }
+
+        if self.with_hash {
+            out.write_arg(b"WITHHASH");
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
+
+/// Contain an item returned by [`geo_search`][1].
+///
+/// The name is read as a `String` by default; read it as `SearchResult<Vec<u8>>` for members
+/// which are not valid UTF-8.
+///
+/// [1]: ../trait.Commands.html#method.geo_search
+pub struct SearchResult<T = String> {
+    /// The name that was found.
+    pub name: T,
+    /// The distance if requested with `with_dist`.
+    pub dist: Option<f64>,
+    /// The geohash if requested with `with_hash`.
+    pub hash: Option<i64>,
+    /// The coordinate if requested with `with_coord`.
+    pub coord: Option<Coord<f64>>,
+}
+
+impl<T: FromRedisValue> FromRedisValue for SearchResult<T> {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        // If we receive only the member name, it will be a plain string
+        let items = match *v {
+            Value::Bulk(ref items) => items,
+            _ => {
+                return Ok(SearchResult {
+                    name: FromRedisValue::from_redis_value(v)?,
+                    dist: None,
+                    hash: None,
+                    coord: None,
+                })
+            }
+        };
+
+        // The member name is followed by the distance, hash and coordinate, in this order, if
+        // they were requested, which are told apart by their types
+        let mut iter = items.iter();
+        let mut result = match iter.next() {
+            Some(name) => SearchResult {
+                name: FromRedisValue::from_redis_value(name)?,
+                dist: None,
+                hash: None,
+                coord: None,
+            },
+            None => invalid_type_error!(v, "Response type not SearchResult compatible."),
+        };
+        for item in iter {
+            match *item {
+                Value::Int(hash) => result.hash = Some(hash),
+                Value::Bulk(_) => result.coord = Some(FromRedisValue::from_redis_value(item)?),
+                _ => result.dist = Some(FromRedisValue::from_redis_value(item)?),
+            }
+        }
+        Ok(result)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Coord, RadiusOptions, RadiusOrder};
+    use super::{
+        Coord, RadiusOptions, RadiusOrder, SearchCenter, SearchOptions, SearchResult, SearchShape,
+        Unit,
+    };
     use crate::types::ToRedisArgs;
+    use crate::types::{FromRedisValue, Value};
     use std::str;
 
     macro_rules! assert_args {
@@ -358,4 +620,85 @@ mod tests {
             "ASC"
         );
     }
+
+    #[test]
+    fn test_search_args() {
+        assert_args!(SearchCenter::member("Palermo"), "FROMMEMBER", "Palermo");
+        assert_args!(
+            SearchCenter::LonLat((15.0, 37.5).into()),
+            "FROMLONLAT",
+            "15.0",
+            "37.5"
+        );
+        assert_args!(
+            SearchShape::Radius(200.0, Unit::Kilometers),
+            "BYRADIUS",
+            "200.0",
+            "km"
+        );
+        assert_args!(
+            SearchShape::Box {
+                width: 10.0,
+                height: 20.5,
+                unit: Unit::Meters
+            },
+            "BYBOX",
+            "10.0",
+            "20.5",
+            "m"
+        );
+
+        assert_eq!(
+            ToRedisArgs::to_redis_args(&SearchOptions::default()).len(),
+            0
+        );
+        assert_args!(
+            SearchOptions::default()
+                .with_hash()
+                .with_coord()
+                .any()
+                .limit(5)
+                .order(RadiusOrder::Desc),
+            "DESC",
+            "COUNT",
+            "5",
+            "ANY",
+            "WITHCOORD",
+            "WITHHASH"
+        );
+    }
+
+    #[test]
+    fn test_search_result() {
+        let result = SearchResult::<String>::from_redis_value(&Value::Bulk(vec![
+            Value::Data(b"Palermo".to_vec()),
+            Value::Data(b"190.4424".to_vec()),
+            Value::Int(3479099956230698),
+            Value::Bulk(vec![
+                Value::Data(b"13.361389".to_vec()),
+                Value::Data(b"38.115556".to_vec()),
+            ]),
+        ]))
+        .unwrap();
+        assert_eq!(result.name, "Palermo");
+        assert_eq!(result.dist, Some(190.4424));
+        assert_eq!(result.hash, Some(3479099956230698));
+        assert_eq!(result.coord, Some(Coord::lon_lat(13.361389, 38.115556)));
+
+        let result = SearchResult::<String>::from_redis_value(&Value::Bulk(vec![
+            Value::Data(b"Catania".to_vec()),
+            Value::Int(3479447370796909),
+        ]))
+        .unwrap();
+        assert_eq!((result.dist, result.hash), (None, Some(3479447370796909)));
+
+        let result =
+            SearchResult::<String>::from_redis_value(&Value::Data(b"Agrigento".to_vec())).unwrap();
+        assert_eq!(result.name, "Agrigento");
+        assert!(result.coord.is_none());
+
+        let result =
+            SearchResult::<Vec<u8>>::from_redis_value(&Value::Data(b"\x00\xff".to_vec())).unwrap();
+        assert_eq!(result.name, b"\x00\xff");
+    }
 }
@@ -58,11 +58,15 @@
 //! * `script`: enables script support (enabled by default)
 //! * `r2d2`: enables r2d2 connection pool support (optional)
 //! * `ahash`: enables ahash map/set support & uses ahash internally (+7-10% performance) (optional)
+//! * `smallvec`: enables converting binary replies into `SmallVec<[u8; N]>` (optional)
 //! * `cluster`: enables redis cluster support (optional)
 //! * `cluster-async`: enables async redis cluster support (optional)
 //! * `tokio-comp`: enables support for tokio (optional)
 //! * `connection-manager`: enables support for automatic reconnection (optional)
 //! * `keep-alive`: enables keep-alive option on socket by means of `socket2` crate (optional)
+//! * `resp3`: enables the RESP3 value types (optional)
+//! * `debug-logging`: enables logging all commands and responses at trace level through the `log`
+//!   crate, for connections with `log_commands` set (optional)
 //!
 //! ## Connection Parameters
 //!
@@ -85,6 +89,14 @@
 //!
 //! `unix:///<path>[?db=<db>][&pass=<password>][&user=<username>]]`
 //!
+//! Both formats accept these query parameters as well:
+//!
+//! * `protocol=resp2` or `protocol=resp3`: the protocol version to request.
+//! * `connect_timeout=<seconds>` and `response_timeout=<seconds>`: the default timeouts for
+//!   establishing a connection and waiting for a response.  `timeout=<seconds>` sets both.
+//! * `handshake_timeout=<seconds>`: the timeout for the whole setup of a connection, including
+//!   authenticating and the other setup commands.
+//!
 //! ## Executing Low-Level Commands
 //!
 //! To execute low-level commands you can use the `cmd` function which allows
@@ -365,11 +377,16 @@ assert_eq!(result, Ok(("foo".to_string(), b"bar".to_vec())));
 pub use crate::client::Client;
 pub use crate::cmd::{cmd, pack_command, pipe, Arg, Cmd, Iter};
 pub use crate::commands::{
-    Commands, ControlFlow, Direction, LposOptions, PubSubCommands, SetOptions,
+    BitRange, ClientKillFilter, ClientPauseMode, ClientType, Commands, ControlFlow, CopyOptions,
+    Direction, Eviction, ExpireCondition, LposOptions, PubSubCommands, RestoreOptions, ScanOptions,
+    SetOptions, SortedSetAddOptions, TrackingOptions,
 };
+pub use crate::compression::{CompressingConnection, Compressor};
 pub use crate::connection::{
-    parse_redis_url, transaction, Connection, ConnectionAddr, ConnectionInfo, ConnectionLike,
-    IntoConnectionInfo, Msg, PubSub, RedisConnectionInfo, TlsMode,
+    parse_redis_url, transaction, AuthStrategy, Connection, ConnectionAddr, ConnectionBuilder,
+    ConnectionInfo, ConnectionLike, IntoConnectionInfo, Invalidation, KeyspaceEvent, Msg,
+    ProtocolVersion, PubSub, RedisConnectionInfo, RequiredRole, SelectStrategy,
+    TlsCertificateCheck, TlsMode,
 };
 pub use crate::parser::{parse_redis_value, Parser};
 pub use crate::pipeline::Pipeline;
@@ -392,10 +409,18 @@ pub use crate::types::{
 
     // utility types
     InfoDict,
+    KeyType,
     NumericBehavior,
+    ObjectEncoding,
     Expiry,
     SetExpiry,
     ExistenceCheck,
+    UpdateCheck,
+    Seconds,
+    Millis,
+    Ttl,
+    ScoredMember,
+    IterArgs,
 
     // error and result types
     RedisError,
@@ -407,6 +432,9 @@ pub use crate::types::{
     Value,
 };
 
+#[cfg(feature = "resp3")]
+pub use crate::types::{Double, VerbatimFormat};
+
 #[cfg(feature = "aio")]
 #[cfg_attr(docsrs, doc(cfg(feature = "aio")))]
 pub use crate::{
@@ -464,8 +492,13 @@ pub mod sentinel;
 
 mod client;
 mod cmd;
+mod command_table;
 mod commands;
+mod compression;
 mod connection;
 mod parser;
 mod script;
+
+#[cfg(feature = "debug-logging")]
+mod logging;
 mod types;
@@ -0,0 +1,120 @@
+//! Trace logging of the requests written to and the responses read from connections, enabled
+//! per connection with [`ConnectionBuilder::log_commands`](crate::ConnectionBuilder::log_commands).
+
+use log::trace;
+
+use crate::cmd::parse_packed_command;
+use crate::types::{RedisResult, Value};
+
+const REDACTED: &str = "<redacted>";
+
+/// Logs the packed commands about to be written, redacting credentials.
+pub(crate) fn log_request(bytes: &[u8]) {
+    trace!("sending {}", format_request(bytes));
+}
+
+/// Logs a response that was read.
+pub(crate) fn log_response(response: &RedisResult<Value>) {
+    match response {
+        Ok(value) => trace!("received {value:?}"),
+        Err(err) => trace!("received error: {err}"),
+    }
+}
+
+/// Formats packed commands, separated by semicolons, with the arguments of `AUTH` and the credentials
+/// passed to `HELLO` and `MIGRATE` replaced.
+fn format_request(bytes: &[u8]) -> String {
+    let mut rest = bytes;
+    let mut commands = Vec::new();
+    while !rest.is_empty() {
+        match parse_packed_command(&mut rest) {
+            Some(args) => commands.push(format_command(&args)),
+            None => return format!("<{} bytes which are not a command>", bytes.len()),
+        }
+    }
+    commands.join("; ")
+}
+
+fn format_command(args: &[&[u8]]) -> String {
+    let name = args.first().copied().unwrap_or_default();
+    let upper_name = name.to_ascii_uppercase();
+    let mut out = String::from_utf8_lossy(name).into_owned();
+    let mut redact = 0usize;
+    for arg in args.iter().skip(1) {
+        out.push(' ');
+        if upper_name == b"AUTH" || redact > 0 {
+            redact = redact.saturating_sub(1);
+            out.push_str(REDACTED);
+            continue;
+        }
+        out.push_str(&format!("{:?}", String::from_utf8_lossy(arg)));
+        redact = match (&upper_name[..], &arg.to_ascii_uppercase()[..]) {
+            (b"HELLO", b"AUTH") | (b"MIGRATE", b"AUTH2") => 2,
+            (b"MIGRATE", b"AUTH") => 1,
+            _ => 0,
+        };
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{cmd, pipe};
+
+    #[test]
+    fn test_format_request() {
+        assert_eq!(
+            format_request(&cmd("SET").arg("key").arg("a b").get_packed_command()),
+            r#"SET "key" "a b""#
+        );
+        assert_eq!(
+            format_request(&cmd("auth").arg("user").arg("secret").get_packed_command()),
+            "auth <redacted> <redacted>"
+        );
+        assert_eq!(
+            format_request(
+                &cmd("HELLO")
+                    .arg(3)
+                    .arg("AUTH")
+                    .arg("user")
+                    .arg("secret")
+                    .arg("SETNAME")
+                    .arg("name")
+                    .get_packed_command()
+            ),
+            r#"HELLO "3" "AUTH" <redacted> <redacted> "SETNAME" "name""#
+        );
+        assert_eq!(
+            format_request(
+                &cmd("MIGRATE")
+                    .arg("host")
+                    .arg(6379)
+                    .arg("")
+                    .arg(0)
+                    .arg(1000)
+                    .arg("AUTH")
+                    .arg("secret")
+                    .arg("KEYS")
+                    .arg("key")
+                    .get_packed_command()
+            ),
+            r#"MIGRATE "host" "6379" "" "0" "1000" "AUTH" <redacted> "KEYS" "key""#
+        );
+        assert_eq!(
+            format_request(
+                &pipe()
+                    .cmd("AUTH")
+                    .arg("secret")
+                    .cmd("GET")
+                    .arg("key")
+                    .get_packed_pipeline()
+            ),
+            r#"AUTH <redacted>; GET "key""#
+        );
+        assert_eq!(
+            format_request(b"not a command"),
+            "<13 bytes which are not a command>"
+        );
+    }
+}
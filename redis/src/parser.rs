@@ -4,17 +4,19 @@ use std::{
 };
 
 use crate::types::{make_extension_error, ErrorKind, RedisError, RedisResult, Value};
+#[cfg(feature = "resp3")]
+use crate::types::{Double, VerbatimFormat};
 
 use combine::{
-    any,
     error::StreamError,
     opaque,
     parser::{
         byte::{crlf, take_until_bytes},
         combinator::{any_send_sync_partial_state, AnySendSyncPartialState},
         range::{recognize, take},
+        token::satisfy,
     },
-    stream::{PointerOffset, RangeStream, StreamErrorFor},
+    stream::{easy, PointerOffset, RangeStream, StreamErrorFor},
     ParseError, Parser as _,
 };
 
@@ -55,6 +57,77 @@ where
 
 const MAX_RECURSE_DEPTH: usize = 100;
 
+/// The first bytes of the values the parser supports.
+#[cfg(feature = "resp3")]
+const TYPE_BYTES: &[u8] = b"+:$*-_,#=(%~>|!";
+#[cfg(not(feature = "resp3"))]
+const TYPE_BYTES: &[u8] = b"+:$*-";
+
+/// The maximum number of unparsed bytes included in a parse error.
+const MAX_PARSE_ERROR_SNIPPET_LEN: usize = 64;
+
+/// Builds a parse error that includes the byte offset it occurred at, relative to the unparsed
+/// input, and a bounded, escaped snippet of that input.
+fn parse_error(err: easy::Errors<u8, Vec<u8>, usize>, buffer: &[u8]) -> RedisError {
+    let info = |info: &easy::Info<u8, Vec<u8>>| match info {
+        easy::Info::Token(b) => format!("`{}`", [*b].escape_ascii()),
+        easy::Info::Range(range) => format!("`{}`", range.escape_ascii()),
+        easy::Info::Owned(message) => message.clone(),
+        easy::Info::Static(message) => message.to_string(),
+    };
+    let details: Vec<_> = err
+        .errors
+        .iter()
+        .map(|error| match error {
+            easy::Error::Unexpected(unexpected) => format!("unexpected {}", info(unexpected)),
+            easy::Error::Expected(expected) => format!("expected {}", info(expected)),
+            easy::Error::Message(message) => info(message),
+            easy::Error::Other(other) => other.to_string(),
+        })
+        .collect();
+    let snippet = &buffer[..buffer.len().min(MAX_PARSE_ERROR_SNIPPET_LEN)];
+    let ellipsis = if buffer.len() > snippet.len() {
+        "..."
+    } else {
+        ""
+    };
+    RedisError::from((
+        ErrorKind::ResponseError,
+        "parse error",
+        format!(
+            "Parse error at byte offset {}: {}\nUnparsed bytes: \"{}\"{ellipsis}",
+            err.position,
+            details.join(", "),
+            snippet.escape_ascii()
+        ),
+    ))
+}
+
+/// Converts the line of an error reply into the error it signals.
+fn server_error(line: &str) -> RedisError {
+    let desc = "An error was signalled by the server";
+    let mut pieces = line.splitn(2, ' ');
+    let kind = match pieces.next().unwrap() {
+        "ERR" => ErrorKind::ResponseError,
+        "EXECABORT" => ErrorKind::ExecAbortError,
+        "LOADING" => ErrorKind::BusyLoadingError,
+        "NOSCRIPT" => ErrorKind::NoScriptError,
+        "MOVED" => ErrorKind::Moved,
+        "ASK" => ErrorKind::Ask,
+        "TRYAGAIN" => ErrorKind::TryAgain,
+        "CLUSTERDOWN" => ErrorKind::ClusterDown,
+        "CROSSSLOT" => ErrorKind::CrossSlot,
+        "MASTERDOWN" => ErrorKind::MasterDown,
+        "READONLY" => ErrorKind::ReadOnly,
+        "NOTBUSY" => ErrorKind::NotBusy,
+        code => return make_extension_error(code, pieces.next()),
+    };
+    match pieces.next() {
+        Some(detail) => RedisError::from((kind, desc, detail.to_string())),
+        None => RedisError::from((kind, desc)),
+    }
+}
+
 fn value<'a, I>(
     count: Option<usize>,
 ) -> impl combine::Parser<I, Output = RedisResult<Value>, PartialState = AnySendSyncPartialState>
@@ -65,9 +138,12 @@ where
     let count = count.unwrap_or(1);
 
     opaque!(any_send_sync_partial_state(
-        any()
+        // Checked before consuming the byte, so that inline replies sent by some proxies and
+        // other garbage are reported at their offset.
+        satisfy(|b| TYPE_BYTES.contains(&b))
+            .expected("a RESP type byte")
             .then_partial(move |&mut b| {
-                if b == b'*' && count > MAX_RECURSE_DEPTH {
+                if b"*%~>|".contains(&b) && count > MAX_RECURSE_DEPTH {
                     combine::unexpected_any("Maximum recursion depth exceeded").left()
                 } else {
                     combine::value(b).right()
@@ -102,8 +178,21 @@ where
                     })
                 };
 
+                // Only `-1` is allowed as a negative length, for nil values.
+                let length = || {
+                    int().and_then(|length| {
+                        if length < -1 {
+                            Err(StreamErrorFor::<I>::message_static_message(
+                                "Expected length, got a negative number",
+                            ))
+                        } else {
+                            Ok(length)
+                        }
+                    })
+                };
+
                 let data = || {
-                    int().then_partial(move |size| {
+                    length().then_partial(move |size| {
                         if *size < 0 {
                             combine::value(Value::Nil).left()
                         } else {
@@ -116,7 +205,7 @@ where
                 };
 
                 let bulk = || {
-                    int().then_partial(move |&mut length| {
+                    length().then_partial(move |&mut length| {
                         if length < 0 {
                             combine::value(Value::Nil).map(Ok).left()
                         } else {
@@ -128,39 +217,133 @@ where
                     })
                 };
 
-                let error = || {
-                    line().map(|line: &str| {
-                        let desc = "An error was signalled by the server";
-                        let mut pieces = line.splitn(2, ' ');
-                        let kind = match pieces.next().unwrap() {
-                            "ERR" => ErrorKind::ResponseError,
-                            "EXECABORT" => ErrorKind::ExecAbortError,
-                            "LOADING" => ErrorKind::BusyLoadingError,
-                            "NOSCRIPT" => ErrorKind::NoScriptError,
-                            "MOVED" => ErrorKind::Moved,
-                            "ASK" => ErrorKind::Ask,
-                            "TRYAGAIN" => ErrorKind::TryAgain,
-                            "CLUSTERDOWN" => ErrorKind::ClusterDown,
-                            "CROSSSLOT" => ErrorKind::CrossSlot,
-                            "MASTERDOWN" => ErrorKind::MasterDown,
-                            "READONLY" => ErrorKind::ReadOnly,
-                            "NOTBUSY" => ErrorKind::NotBusy,
-                            code => return make_extension_error(code, pieces.next()),
-                        };
-                        match pieces.next() {
-                            Some(detail) => RedisError::from((kind, desc, detail.to_string())),
-                            None => RedisError::from((kind, desc)),
-                        }
-                    })
+                let error = || line().map(server_error);
+
+                #[cfg(feature = "resp3")]
+                let other = move |b| {
+                    let double = || {
+                        line().and_then(|line| match line.trim().parse::<f64>() {
+                            Err(_) => Err(StreamErrorFor::<I>::message_static_message(
+                                "Expected double, got garbage",
+                            )),
+                            Ok(value) => Ok(Value::Double(Double(value))),
+                        })
+                    };
+
+                    let boolean = || {
+                        line().and_then(|line| match line {
+                            "t" => Ok(Value::Boolean(true)),
+                            "f" => Ok(Value::Boolean(false)),
+                            _ => Err(StreamErrorFor::<I>::message_static_message(
+                                "Expected boolean, got garbage",
+                            )),
+                        })
+                    };
+
+                    let verbatim = || {
+                        int().then_partial(move |size| {
+                            take((*size).max(0) as usize)
+                                .and_then(|bs: &[u8]| {
+                                    let text =
+                                        str::from_utf8(bs).map_err(StreamErrorFor::<I>::other)?;
+                                    match text.split_once(':') {
+                                        Some((format, text)) if format.len() == 3 => {
+                                            Ok(Value::VerbatimString {
+                                                format: VerbatimFormat::from_prefix(format),
+                                                text: text.to_string(),
+                                            })
+                                        }
+                                        _ => Err(StreamErrorFor::<I>::message_static_message(
+                                            "Expected verbatim string format prefix",
+                                        )),
+                                    }
+                                })
+                                .skip(crlf())
+                        })
+                    };
+
+                    let big_number = || line().map(|line| Value::BigNumber(line.to_string()));
+
+                    let pairs = || {
+                        int().then_partial(move |&mut length| {
+                            let length = length.max(0) as usize * 2;
+                            combine::count_min_max(length, length, value(Some(count + 1))).map(
+                                |result: ResultExtend<Vec<_>, _>| {
+                                    result.0.map(|items| {
+                                        let mut items = items.into_iter();
+                                        let mut pairs = Vec::with_capacity(items.len() / 2);
+                                        while let (Some(k), Some(v)) = (items.next(), items.next())
+                                        {
+                                            pairs.push((k, v));
+                                        }
+                                        pairs
+                                    })
+                                },
+                            )
+                        })
+                    };
+
+                    let map = || pairs().map(|result| result.map(Value::Map));
+
+                    let aggregate = |make: fn(Vec<Value>) -> Value| {
+                        int().then_partial(move |&mut length| {
+                            let length = length.max(0) as usize;
+                            combine::count_min_max(length, length, value(Some(count + 1)))
+                                .map(move |result: ResultExtend<_, _>| result.0.map(make))
+                        })
+                    };
+
+                    // The attributes precede the value they describe.
+                    let attribute = || {
+                        pairs().then_partial(move |attributes| {
+                            let mut attributes =
+                                Some(std::mem::replace(attributes, Ok(Vec::new())));
+                            value(Some(count + 1)).map(move |data| {
+                                Ok(Value::Attribute {
+                                    data: Box::new(data?),
+                                    attributes: attributes.take().unwrap_or(Ok(Vec::new()))?,
+                                })
+                            })
+                        })
+                    };
+
+                    let blob_error = || {
+                        length().then_partial(move |size| {
+                            take((*size).max(0) as usize)
+                                .and_then(|bs: &[u8]| {
+                                    str::from_utf8(bs)
+                                        .map(server_error)
+                                        .map_err(StreamErrorFor::<I>::other)
+                                })
+                                .skip(crlf())
+                        })
+                    };
+
+                    combine::dispatch!(b;
+                        b'_' => line().map(|_| Ok(Value::Nil)),
+                        b',' => double().map(Ok),
+                        b'#' => boolean().map(Ok),
+                        b'=' => verbatim().map(Ok),
+                        b'(' => big_number().map(Ok),
+                        b'%' => map(),
+                        b'~' => aggregate(Value::Set),
+                        b'>' => aggregate(Value::Push),
+                        b'|' => attribute(),
+                        b'!' => blob_error().map(Err),
+                        b => combine::unexpected_any(combine::error::Token(b))
+                    )
                 };
 
+                #[cfg(not(feature = "resp3"))]
+                let other = |b| combine::unexpected_any(combine::error::Token(b));
+
                 combine::dispatch!(b;
                     b'+' => status().map(Ok),
                     b':' => int().map(|i| Ok(Value::Int(i))),
                     b'$' => data().map(Ok),
                     b'*' => bulk(),
                     b'-' => error().map(Err),
-                    b => combine::unexpected_any(combine::error::Token(b))
+                    b => other(b)
                 )
             })
     ))
@@ -170,16 +353,69 @@ where
 mod aio_support {
     use super::*;
 
+    use std::sync::{Arc, Mutex};
+
     use bytes::{Buf, BytesMut};
     use tokio::io::AsyncRead;
     use tokio_util::codec::{Decoder, Encoder};
 
+    /// Buffers for packed requests, which the codec returns once it copied them into its write
+    /// buffer, so that encoding requests doesn't allocate once the pool is warm.
+    #[derive(Default)]
+    pub(crate) struct BufferPool {
+        buffers: Mutex<Vec<Vec<u8>>>,
+    }
+
+    impl BufferPool {
+        const MAX_BUFFERS: usize = 32;
+        // Larger buffers are dropped so that a single huge request doesn't pin its memory.
+        const MAX_CAPACITY: usize = 64 * 1024;
+
+        /// Takes an empty buffer from the pool, or a new one if the pool is empty.
+        pub(crate) fn take(&self) -> Vec<u8> {
+            self.buffers.lock().unwrap().pop().unwrap_or_default()
+        }
+
+        /// Returns a buffer to the pool, unless it or the pool is full.
+        pub(crate) fn put(&self, mut buffer: Vec<u8>) {
+            if buffer.capacity() == 0 || buffer.capacity() > Self::MAX_CAPACITY {
+                return;
+            }
+            buffer.clear();
+            let mut buffers = self.buffers.lock().unwrap();
+            if buffers.len() < Self::MAX_BUFFERS {
+                buffers.push(buffer);
+            }
+        }
+
+        #[cfg(test)]
+        pub(crate) fn len(&self) -> usize {
+            self.buffers.lock().unwrap().len()
+        }
+    }
+
     #[derive(Default)]
     pub struct ValueCodec {
         state: AnySendSyncPartialState,
+        #[cfg(feature = "debug-logging")]
+        log_commands: bool,
+        buffer_pool: Option<Arc<BufferPool>>,
     }
 
     impl ValueCodec {
+        /// Sets the pool which encoded requests are returned to.
+        pub(crate) fn buffer_pool(mut self, buffer_pool: Arc<BufferPool>) -> Self {
+            self.buffer_pool = Some(buffer_pool);
+            self
+        }
+
+        /// Sets whether the encoded requests and the decoded responses are logged.
+        #[cfg(feature = "debug-logging")]
+        pub(crate) fn log_commands(mut self, log_commands: bool) -> Self {
+            self.log_commands = log_commands;
+            self
+        }
+
         fn decode_stream(
             &mut self,
             bytes: &mut BytesMut,
@@ -193,19 +429,18 @@ mod aio_support {
                     Ok(x) => x,
                     Err(err) => {
                         let err = err
-                            .map_position(|pos| pos.translate_position(buffer))
-                            .map_range(|range| format!("{range:?}"))
-                            .to_string();
-                        return Err(RedisError::from((
-                            ErrorKind::ResponseError,
-                            "parse error",
-                            err,
-                        )));
+                            .map_range(<[u8]>::to_vec)
+                            .map_position(|pos| pos.translate_position(buffer));
+                        return Err(parse_error(err, buffer));
                     }
                 }
             };
 
             bytes.advance(removed_len);
+            #[cfg(feature = "debug-logging")]
+            if let (true, Some(result)) = (self.log_commands, &opt) {
+                crate::logging::log_response(result);
+            }
             match opt {
                 Some(result) => Ok(Some(result)),
                 None => Ok(None),
@@ -216,7 +451,14 @@ mod aio_support {
     impl Encoder<Vec<u8>> for ValueCodec {
         type Error = RedisError;
         fn encode(&mut self, item: Vec<u8>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+            #[cfg(feature = "debug-logging")]
+            if self.log_commands {
+                crate::logging::log_request(&item);
+            }
             dst.extend_from_slice(item.as_ref());
+            if let Some(buffer_pool) = &self.buffer_pool {
+                buffer_pool.put(item);
+            }
             Ok(())
         }
     }
@@ -253,10 +495,9 @@ mod aio_support {
                         RedisError::from(io::Error::from(io::ErrorKind::UnexpectedEof))
                     } else {
                         let err = err
-                            .map_range(|range| format!("{range:?}"))
-                            .map_position(|pos| pos.translate_position(decoder.buffer()))
-                            .to_string();
-                        RedisError::from((ErrorKind::ResponseError, "parse error", err))
+                            .map_range(<[u8]>::to_vec)
+                            .map_position(|pos| pos.translate_position(decoder.buffer()));
+                        parse_error(err, decoder.buffer())
                     }
                 }
             }),
@@ -311,10 +552,9 @@ impl Parser {
                         RedisError::from(io::Error::from(io::ErrorKind::UnexpectedEof))
                     } else {
                         let err = err
-                            .map_range(|range| format!("{range:?}"))
-                            .map_position(|pos| pos.translate_position(decoder.buffer()))
-                            .to_string();
-                        RedisError::from((ErrorKind::ResponseError, "parse error", err))
+                            .map_range(<[u8]>::to_vec)
+                            .map_position(|pos| pos.translate_position(decoder.buffer()));
+                        parse_error(err, decoder.buffer())
                     }
                 }
             }),
@@ -352,6 +592,234 @@ mod tests {
         assert_eq!(codec.decode_eof(&mut bytes), Ok(None));
     }
 
+    #[test]
+    fn parse_error_contains_unparsed_bytes() {
+        let err = parse_redis_value(b"HTTP/1.1 400 Bad Request\r\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseError);
+        assert!(err.is_unrecoverable_error());
+        let detail = err.detail().unwrap();
+        assert!(
+            detail.contains(r#"Unparsed bytes: "HTTP/1.1 400 Bad Request\r\n""#),
+            "{detail}"
+        );
+    }
+
+    #[test]
+    fn parse_error_contains_offset() {
+        let detail = |bytes: &[u8]| {
+            parse_redis_value(bytes)
+                .unwrap_err()
+                .detail()
+                .unwrap()
+                .to_string()
+        };
+        assert!(
+            detail(b"OK\r\n").starts_with("Parse error at byte offset 0: unexpected `O`"),
+            "{}",
+            detail(b"OK\r\n")
+        );
+        assert!(
+            detail(b"*1\r\nOK\r\n").starts_with("Parse error at byte offset 4"),
+            "{}",
+            detail(b"*1\r\nOK\r\n")
+        );
+        assert!(
+            detail(b"$2\r\nabc\r\n").starts_with("Parse error at byte offset 6: unexpected `c`"),
+            "{}",
+            detail(b"$2\r\nabc\r\n")
+        );
+    }
+
+    #[test]
+    fn negative_lengths_other_than_nil_are_rejected() {
+        assert_eq!(parse_redis_value(b"$-1\r\n"), Ok(Value::Nil));
+        assert_eq!(parse_redis_value(b"*-1\r\n"), Ok(Value::Nil));
+        for bytes in [&b"$-2\r\n"[..], b"*-5\r\n"] {
+            let err = parse_redis_value(bytes).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ResponseError);
+            assert!(err.is_unrecoverable_error());
+        }
+    }
+
+    #[test]
+    fn server_error_may_contain_newlines() {
+        let err = parse_redis_value(b"-ERR first\nsecond\r\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseError);
+        assert_eq!(err.detail(), Some("first\nsecond"));
+    }
+
+    #[test]
+    fn truncated_input_is_unexpected_eof() {
+        let bytes = b"*4\r\n+OK\r\n:-42\r\n$5\r\nhello\r\n*2\r\n$-1\r\n:7\r\n";
+        assert!(parse_redis_value(bytes).is_ok());
+        for end in 0..bytes.len() {
+            let err = parse_redis_value(&bytes[..end]).unwrap_err();
+            assert!(err.is_io_error(), "{end}: {err}");
+        }
+    }
+
+    #[cfg(feature = "aio")]
+    #[test]
+    fn decode_waits_for_truncated_input() {
+        use tokio_util::codec::Decoder;
+        let bytes = b"*3\r\n+OK\r\n$5\r\nhello\r\n:1\r\n";
+        for end in 0..bytes.len() {
+            let mut codec = ValueCodec::default();
+            let mut buffer = bytes::BytesMut::from(&bytes[..end]);
+            assert_eq!(codec.decode(&mut buffer), Ok(None), "{end}");
+            buffer.extend_from_slice(&bytes[end..]);
+            assert_eq!(
+                codec.decode(&mut buffer),
+                Ok(Some(parse_redis_value(bytes))),
+                "{end}"
+            );
+        }
+    }
+
+    #[test]
+    fn garbage_input_does_not_panic() {
+        const ALPHABET: &[u8] = b"+-:$*_,#=(%!~>|0123456789\r\n OKERR\xff";
+        // A fixed xorshift generator, so that failures can be reproduced.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..10_000 {
+            let len = (next() % 24) as usize;
+            let bytes: Vec<u8> = (0..len)
+                .map(|_| ALPHABET[(next() % ALPHABET.len() as u64) as usize])
+                .collect();
+            if let Err(err) = parse_redis_value(&bytes) {
+                // Errors sent by the server can be of any kind.
+                if !matches!(bytes.first(), Some(b'-' | b'!')) && !err.is_io_error() {
+                    assert_eq!(err.kind(), ErrorKind::ResponseError, "{bytes:?}: {err}");
+                }
+            }
+            #[cfg(feature = "aio")]
+            {
+                use tokio_util::codec::Decoder;
+                let mut buffer = bytes::BytesMut::from(&bytes[..]);
+                let _ = ValueCodec::default().decode_eof(&mut buffer);
+            }
+        }
+    }
+
+    #[test]
+    fn server_error_is_recoverable() {
+        let err = parse_redis_value(b"-ERR unknown command\r\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseError);
+        assert!(!err.is_unrecoverable_error());
+    }
+
+    #[test]
+    fn parse_error_truncates_unparsed_bytes() {
+        let mut bytes = b"?".to_vec();
+        bytes.extend(std::iter::repeat(b'a').take(1000));
+        let err = parse_redis_value(&bytes).unwrap_err();
+        let detail = err.detail().unwrap();
+        let expected = format!("Unparsed bytes: \"?{}\"...", "a".repeat(63));
+        assert!(detail.ends_with(&expected), "{detail}");
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn parse_resp3_values() {
+        use crate::types::VerbatimFormat;
+
+        assert_eq!(parse_redis_value(b"_\r\n"), Ok(Value::Nil));
+        assert_eq!(
+            parse_redis_value(b",1.23\r\n"),
+            Ok(Value::Double(Double(1.23)))
+        );
+        assert_eq!(
+            parse_redis_value(b",-inf\r\n"),
+            Ok(Value::Double(Double(f64::NEG_INFINITY)))
+        );
+        assert_eq!(parse_redis_value(b"#t\r\n"), Ok(Value::Boolean(true)));
+        assert_eq!(parse_redis_value(b"#f\r\n"), Ok(Value::Boolean(false)));
+        assert_eq!(
+            parse_redis_value(b"=15\r\ntxt:Some string\r\n"),
+            Ok(Value::VerbatimString {
+                format: VerbatimFormat::Text,
+                text: "Some string".into()
+            })
+        );
+        assert_eq!(
+            parse_redis_value(b"(3492890328409238509324850943850943825024385\r\n"),
+            Ok(Value::BigNumber(
+                "3492890328409238509324850943850943825024385".into()
+            ))
+        );
+        assert_eq!(
+            parse_redis_value(b"%2\r\n+first\r\n:1\r\n+second\r\n#t\r\n"),
+            Ok(Value::Map(vec![
+                (Value::Status("first".into()), Value::Int(1)),
+                (Value::Status("second".into()), Value::Boolean(true)),
+            ]))
+        );
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn parse_resp3_aggregates() {
+        assert_eq!(
+            parse_redis_value(b"~2\r\n+a\r\n:1\r\n"),
+            Ok(Value::Set(vec![Value::Status("a".into()), Value::Int(1)]))
+        );
+        assert_eq!(
+            parse_redis_value(b">3\r\n$10\r\ninvalidate\r\n*1\r\n$3\r\nkey\r\n_\r\n"),
+            Ok(Value::Push(vec![
+                Value::Data(b"invalidate".to_vec()),
+                Value::Bulk(vec![Value::Data(b"key".to_vec())]),
+                Value::Nil,
+            ]))
+        );
+        assert_eq!(
+            parse_redis_value(b"|1\r\n+ttl\r\n:3600\r\n*1\r\n:2\r\n"),
+            Ok(Value::Attribute {
+                data: Box::new(Value::Bulk(vec![Value::Int(2)])),
+                attributes: vec![(Value::Status("ttl".into()), Value::Int(3600))],
+            })
+        );
+        let value = parse_redis_value(b"|1\r\n+ttl\r\n:3600\r\n~2\r\n:1\r\n:2\r\n").unwrap();
+        assert_eq!(crate::from_redis_value::<Vec<i64>>(&value), Ok(vec![1, 2]));
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn parse_resp3_blob_error() {
+        let err = parse_redis_value(b"!21\r\nSYNTAX invalid syntax\r\n").unwrap_err();
+        assert_eq!(err.code(), Some("SYNTAX"));
+        assert_eq!(err.detail(), Some("invalid syntax"));
+        assert!(!err.is_unrecoverable_error());
+
+        // Unlike simple errors, blob errors may contain line breaks.
+        let err = parse_redis_value(b"!17\r\nERR first\r\nsecond\r\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseError);
+        assert_eq!(err.detail(), Some("first\r\nsecond"));
+    }
+
+    #[cfg(all(feature = "resp3", feature = "aio"))]
+    #[test]
+    fn decode_waits_for_truncated_resp3_input() {
+        use tokio_util::codec::Decoder;
+        let bytes = b"|1\r\n+key\r\n:1\r\n~2\r\n>1\r\n+kind\r\n!5\r\nERR x\r\n";
+        for end in 0..bytes.len() {
+            let mut codec = ValueCodec::default();
+            let mut buffer = bytes::BytesMut::from(&bytes[..end]);
+            assert_eq!(codec.decode(&mut buffer), Ok(None), "{end}");
+            buffer.extend_from_slice(&bytes[end..]);
+            assert_eq!(
+                codec.decode(&mut buffer),
+                Ok(Some(parse_redis_value(bytes))),
+                "{end}"
+            );
+        }
+    }
+
     #[test]
     fn test_max_recursion_depth() {
         let bytes = b"*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n";
@@ -80,6 +80,11 @@ impl Pipeline {
         write_pipeline(out, &self.commands, self.transaction_mode)
     }
 
+    #[cfg(feature = "aio")]
+    pub(crate) fn commands_mut(&mut self) -> impl Iterator<Item = &mut Cmd> {
+        self.commands.iter_mut()
+    }
+
     fn execute_pipelined(&self, con: &mut dyn ConnectionLike) -> RedisResult<Value> {
         Ok(self.make_pipeline_results(con.req_packed_commands(
             &encode_pipeline(&self.commands, false),
@@ -171,6 +176,22 @@ impl Pipeline {
     }
 
     /// Async version of `query`.
+    ///
+    /// Like with `query`, the commands can be added with their typed methods, and each response
+    /// which is not ignored is read into the corresponding element of the result:
+    ///
+    /// ```rust,no_run
+    /// # async fn do_something() -> redis::RedisResult<()> {
+    /// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+    /// # let mut con = client.get_multiplexed_async_connection().await.unwrap();
+    /// let (name, (), visits): (String, (), i64) = redis::pipe()
+    ///     .get("name")
+    ///     .set("last_seen", 1_700_000_000)
+    ///     .incr("visits", 1)
+    ///     .query_async(&mut con)
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
     #[inline]
     #[cfg(feature = "aio")]
     pub async fn query_async<C, T: FromRedisValue>(&self, con: &mut C) -> RedisResult<T>
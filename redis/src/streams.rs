@@ -208,6 +208,136 @@ impl ToRedisArgs for StreamReadOptions {
     }
 }
 
+/// Reads the entries added to the streams at `keys` from now on, issuing `XREAD` or, if the
+/// options set a [group](StreamReadOptions::group), `XREADGROUP` again whenever the entries read
+/// before were yielded, and yields each entry together with the key of its stream, as passed in
+/// `keys`.  Each key must be a single argument; keys are binary-safe.
+///
+/// Each read blocks until entries are added, for the time set with
+/// [`block`](StreamReadOptions::block) or indefinitely, so it holds up all other requests on the
+/// connection: use a dedicated [`Connection`](crate::aio::Connection) rather than a
+/// [`MultiplexedConnection`](crate::aio::MultiplexedConnection).  Without a group, the ID of the
+/// last entry of each stream is looked up before the first read, and then the ID of the last
+/// entry read from each stream is tracked, so that no entries are skipped between reads.  In
+/// a group, the entries not yet delivered to any consumer are read, which have to be acknowledged
+/// with `XACK` unless the options set [`noack`](StreamReadOptions::noack).
+///
+/// The stream ends after yielding the first error, e.g. if the connection was closed.
+#[cfg(feature = "aio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "aio")))]
+pub fn read_entries<C, K>(
+    con: C,
+    keys: &[K],
+    mut options: StreamReadOptions,
+) -> impl futures_util::Stream<Item = RedisResult<(K, StreamId)>>
+where
+    C: crate::aio::ConnectionLike,
+    K: ToRedisArgs + Clone,
+{
+    if options.block.is_none() {
+        options.block = Some(0);
+    }
+    let encoded_keys = keys
+        .iter()
+        .map(|key| key.to_redis_args().concat())
+        .collect();
+    let start = if options.read_only() { "$" } else { ">" };
+    let reader = EntryReader {
+        con,
+        ids: vec![start.to_string(); keys.len()],
+        keys: keys.to_vec(),
+        encoded_keys,
+        options,
+        entries: std::collections::VecDeque::new(),
+        failed: false,
+    };
+    futures_util::stream::unfold(reader, |mut reader| async move {
+        loop {
+            if reader.failed {
+                return None;
+            }
+            if let Some(entry) = reader.entries.pop_front() {
+                return Some((Ok(entry), reader));
+            }
+            if let Err(err) = reader.read().await {
+                reader.failed = true;
+                return Some((Err(err), reader));
+            }
+        }
+    })
+}
+
+// The state of the stream returned by `read_entries`.
+#[cfg(feature = "aio")]
+struct EntryReader<C, K> {
+    con: C,
+    keys: Vec<K>,
+    // The keys as they are sent, to find the key of each stream in the reply
+    encoded_keys: Vec<Vec<u8>>,
+    // The ID to read entries after for each key
+    ids: Vec<String>,
+    options: StreamReadOptions,
+    // The entries which were read, but not yielded yet
+    entries: std::collections::VecDeque<(K, StreamId)>,
+    failed: bool,
+}
+
+#[cfg(feature = "aio")]
+impl<C: crate::aio::ConnectionLike, K: Clone> EntryReader<C, K> {
+    async fn read(&mut self) -> RedisResult<()> {
+        if self.options.read_only() {
+            self.resolve_last_ids().await?;
+        }
+        let reply: Vec<(Vec<u8>, Vec<StreamId>)> = crate::cmd(if self.options.read_only() {
+            "XREAD"
+        } else {
+            "XREADGROUP"
+        })
+        .arg(&self.options)
+        .arg("STREAMS")
+        .arg(&self.encoded_keys)
+        .arg(&self.ids)
+        .query_async(&mut self.con)
+        .await?;
+        for (key, ids) in reply {
+            let index = match self.encoded_keys.iter().position(|k| *k == key) {
+                Some(index) => index,
+                None => continue,
+            };
+            for id in ids {
+                if self.options.read_only() {
+                    self.ids[index] = id.id.clone();
+                }
+                self.entries.push_back((self.keys[index].clone(), id));
+            }
+        }
+        Ok(())
+    }
+
+    // Replaces `$` by the ID of the last entry of each stream, as reading with `$` again would
+    // skip the entries added since the previous read.
+    async fn resolve_last_ids(&mut self) -> RedisResult<()> {
+        for (key, id) in self.encoded_keys.iter().zip(self.ids.iter_mut()) {
+            if id != "$" {
+                continue;
+            }
+            let last: Vec<StreamId> = crate::cmd("XREVRANGE")
+                .arg(key)
+                .arg("+")
+                .arg("-")
+                .arg("COUNT")
+                .arg(1)
+                .query_async(&mut self.con)
+                .await?;
+            *id = match last.into_iter().next() {
+                Some(entry) => entry.id,
+                None => "0-0".to_string(),
+            };
+        }
+        Ok(())
+    }
+}
+
 /// Reply type used with [`xread`] or [`xread_options`] commands.
 ///
 /// [`xread`]: ../trait.Commands.html#method.xread
@@ -407,6 +537,9 @@ pub struct StreamPendingId {
 }
 
 /// Represents a stream `key` and its `id`'s parsed from `xread` methods.
+///
+/// The reply of `xread` can also be parsed into a `Vec<StreamKey>` directly, which is empty if
+/// no entries were read.
 #[derive(Default, Debug, Clone)]
 pub struct StreamKey {
     /// The stream `key`.
@@ -416,6 +549,8 @@ pub struct StreamKey {
 }
 
 /// Represents a stream `id` and its field/values as a `HashMap`
+///
+/// The reply of `xrange` and its variants can also be parsed into a `Vec<StreamId>` directly.
 #[derive(Default, Debug, Clone)]
 pub struct StreamId {
     /// The stream `id` (entry ID) of this particular message.
@@ -465,6 +600,25 @@ impl StreamId {
     }
 }
 
+impl FromRedisValue for StreamKey {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let (key, ids) = from_redis_value(v)?;
+        Ok(StreamKey { key, ids })
+    }
+}
+
+impl FromRedisValue for StreamId {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        match *v {
+            Value::Bulk(_) => StreamId::from_bulk_value(v),
+            _ => fail!((
+                crate::types::ErrorKind::TypeError,
+                "Response type not stream entry compatible"
+            )),
+        }
+    }
+}
+
 type SRRows = Vec<HashMap<String, Vec<HashMap<String, HashMap<String, Value>>>>>;
 impl FromRedisValue for StreamReadReply {
     fn from_redis_value(v: &Value) -> RedisResult<Self> {
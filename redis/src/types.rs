@@ -8,6 +8,7 @@ use std::hash::{BuildHasher, Hash};
 use std::io;
 use std::str::{from_utf8, Utf8Error};
 use std::string::FromUtf8Error;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[cfg(feature = "ahash")]
 pub(crate) use ahash::{AHashMap as HashMap, AHashSet as HashSet};
@@ -67,6 +68,15 @@ pub enum ExistenceCheck {
     XX,
 }
 
+/// Helper enum that is used to define update checks for sorted set scores
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum UpdateCheck {
+    /// GT -- Only update existing members if the new score is greater than the current one.
+    GT,
+    /// LT -- Only update existing members if the new score is less than the current one.
+    LT,
+}
+
 /// Helper enum that is used in some situations to describe
 /// the behavior of arguments in a numeric context.
 #[derive(PartialEq, Eq, Clone, Debug, Copy)]
@@ -79,6 +89,136 @@ pub enum NumericBehavior {
     NumberIsFloat,
 }
 
+/// The type of a key, as returned by the `TYPE` command.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum KeyType {
+    /// The key holds a string.
+    String,
+    /// The key holds a list.
+    List,
+    /// The key holds a set.
+    Set,
+    /// The key holds a sorted set.
+    ZSet,
+    /// The key holds a hash.
+    Hash,
+    /// The key holds a stream.
+    Stream,
+    /// The key does not exist.
+    None,
+    /// A type this library doesn't know about, e.g. one added by a module.
+    Unknown(String),
+}
+
+/// The internal encoding of a value, as returned by the `OBJECT ENCODING` command.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ObjectEncoding {
+    /// A string stored as raw bytes.
+    Raw,
+    /// A string holding an integer.
+    Int,
+    /// A short string allocated together with its object.
+    EmbStr,
+    /// A hash table, used for large sets and hashes.
+    HashTable,
+    /// A compact list of entries, which replaced `ziplist` in Redis 7.0.
+    ListPack,
+    /// A compact list of entries, used by older servers.
+    ZipList,
+    /// A linked list of listpacks or ziplists, used for large lists.
+    QuickList,
+    /// A doubly linked list, used for lists by very old servers.
+    LinkedList,
+    /// A set of integers.
+    IntSet,
+    /// A skip list, used for large sorted sets.
+    SkipList,
+    /// A stream.
+    Stream,
+    /// An encoding this library doesn't know about, e.g. one added by a newer server.
+    Other(String),
+}
+
+/// Writes each item of an iterator as arguments, without collecting them first, e.g.
+/// `cmd("DEL").arg(IterArgs(keys.iter()))`.
+///
+/// The iterator is cloned each time the arguments are written, so iterators over references
+/// are the cheapest.  An empty iterator writes no arguments at all, like an empty `Vec`.
+#[derive(Clone, Copy, Debug)]
+pub struct IterArgs<I>(pub I);
+
+/// A duration or point in time passed or read as whole seconds, e.g. for `EXPIRE`, `TTL` or
+/// `EXPIREAT`.
+///
+/// Durations are rounded up to the next second, so that a short but non-zero duration doesn't
+/// expire a key right away.  Points in time are written as Unix timestamps, rounded down.  Values
+/// that don't fit into a `u64` are written as `u64::MAX`, which the server rejects.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Seconds<T = Duration>(pub T);
+
+/// A duration or point in time passed or read as whole milliseconds, e.g. for `PEXPIRE`, `PTTL`
+/// or `PEXPIREAT`.
+///
+/// Durations are rounded up to the next millisecond, points in time are written as Unix
+/// timestamps, rounded down.  Values that don't fit into a `u64` are written as `u64::MAX`, which
+/// the server rejects.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Millis<T = Duration>(pub T);
+
+/// A member of a sorted set and its score, as returned by the `WITHSCORES` variants of the range
+/// commands.
+///
+/// Read the reply as `Vec<ScoredMember<M>>`, which accepts both the members and scores
+/// alternating in a flat list, as sent with RESP2, and the pairs of member and score sent with
+/// RESP3.  Scores are parsed as `f64`, including `inf` and `-inf`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ScoredMember<M = String> {
+    /// The member.
+    pub member: M,
+    /// The score of the member.
+    pub score: f64,
+}
+
+/// The time to live of a key, as returned by `TTL` and `PTTL`, or the time a key expires at, as
+/// returned by `EXPIRETIME` and `PEXPIRETIME`.
+///
+/// Read it as `Ttl<Seconds>` or `Ttl<Millis>` depending on the command, then use
+/// [`duration`](Ttl::duration) for an `Option<Duration>`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Ttl<T = Seconds> {
+    /// The key expires after the given time to live, or at the given time.
+    Expires(T),
+    /// The key exists, but has no expiry.
+    Persistent,
+    /// The key does not exist.
+    Missing,
+}
+
+impl<T> Ttl<T> {
+    /// Returns the time to live or expiry time, or `None` if the key has no expiry or does not
+    /// exist.
+    pub fn expires(self) -> Option<T> {
+        match self {
+            Ttl::Expires(value) => Some(value),
+            Ttl::Persistent | Ttl::Missing => None,
+        }
+    }
+}
+
+impl Ttl<Seconds> {
+    /// Returns the time to live, or `None` if the key has no expiry or does not exist.
+    pub fn duration(self) -> Option<Duration> {
+        self.expires().map(|Seconds(duration)| duration)
+    }
+}
+
+impl Ttl<Millis> {
+    /// Returns the time to live, or `None` if the key has no expiry or does not exist.
+    pub fn duration(self) -> Option<Duration> {
+        self.expires().map(|Millis(duration)| duration)
+    }
+}
+
 /// An enum of all error kinds.
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
 #[non_exhaustive]
@@ -129,6 +269,12 @@ pub enum ErrorKind {
     EmptySentinelList,
     /// Attempted to kill a script/function while they werent' executing
     NotBusy,
+    /// A request was not sent because too many requests are already queued on the connection
+    Busy,
+    /// A request was aborted because its cancellation token was cancelled
+    Cancelled,
+    /// A request was not sent because the connection is being replaced
+    Reconnecting,
 
     #[cfg(feature = "json")]
     /// Error Serializing a struct to JSON form
@@ -136,6 +282,9 @@ pub enum ErrorKind {
 }
 
 /// Internal low-level redis value enum.
+///
+/// The RESP3 variants are only available with the `resp3` feature, and only received when the
+/// server speaks RESP3.
 #[derive(PartialEq, Eq, Clone)]
 pub enum Value {
     /// A nil response from the server.
@@ -154,20 +303,122 @@ pub enum Value {
     Status(String),
     /// A status response which represents the string "OK".
     Okay,
+    #[cfg(feature = "resp3")]
+    /// A RESP3 double response.
+    Double(Double),
+    #[cfg(feature = "resp3")]
+    /// A RESP3 boolean response.
+    Boolean(bool),
+    #[cfg(feature = "resp3")]
+    /// A RESP3 verbatim string response, which carries a hint about its format.
+    VerbatimString {
+        /// The format of the text.
+        format: VerbatimFormat,
+        /// The text itself, without the format prefix.
+        text: String,
+    },
+    #[cfg(feature = "resp3")]
+    /// A RESP3 big number response, holding the decimal digits of the number.
+    BigNumber(String),
+    #[cfg(feature = "resp3")]
+    /// A RESP3 map response, holding the key value pairs in the order they were received.
+    Map(Vec<(Value, Value)>),
+    #[cfg(feature = "resp3")]
+    /// A RESP3 set response.
+    Set(Vec<Value>),
+    #[cfg(feature = "resp3")]
+    /// A RESP3 push message, e.g. a pubsub message or a client tracking invalidation.  The first
+    /// item is the kind of the message, such as `message` or `invalidate`.
+    Push(Vec<Value>),
+    #[cfg(feature = "resp3")]
+    /// A RESP3 response with attributes, which carry auxiliary information about it.
+    ///
+    /// [`from_redis_value`] converts the response itself, ignoring the attributes.
+    Attribute {
+        /// The response.
+        data: Box<Value>,
+        /// The attributes of the response.
+        attributes: Vec<(Value, Value)>,
+    },
+}
+
+#[cfg(feature = "resp3")]
+/// The number held by a [`Value::Double`].
+///
+/// Doubles are compared with [`f64::total_cmp`], so that `NaN` equals itself and [`Value`] can
+/// implement `Eq`.  As a consequence, `0.0` and `-0.0` are not equal.
+#[derive(Clone, Copy, Debug)]
+pub struct Double(pub f64);
+
+#[cfg(feature = "resp3")]
+impl PartialEq for Double {
+    fn eq(&self, other: &Double) -> bool {
+        self.0.total_cmp(&other.0).is_eq()
+    }
+}
+
+#[cfg(feature = "resp3")]
+impl Eq for Double {}
+
+#[cfg(feature = "resp3")]
+impl From<f64> for Double {
+    fn from(val: f64) -> Double {
+        Double(val)
+    }
+}
+
+#[cfg(feature = "resp3")]
+/// The format of a [`Value::VerbatimString`].
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum VerbatimFormat {
+    /// Plain text (`txt`).
+    Text,
+    /// Markdown (`mkd`).
+    Markdown,
+    /// Any other format, as sent by the server.
+    Unknown(String),
+}
+
+#[cfg(feature = "resp3")]
+impl VerbatimFormat {
+    pub(crate) fn from_prefix(prefix: &str) -> VerbatimFormat {
+        match prefix {
+            "txt" => VerbatimFormat::Text,
+            "mkd" => VerbatimFormat::Markdown,
+            other => VerbatimFormat::Unknown(other.to_string()),
+        }
+    }
 }
 
-pub struct MapIter<'a>(std::slice::Iter<'a, Value>);
+/// An iterator over the key value pairs of a map like [`Value`].
+pub struct MapIter<'a>(MapIterInner<'a>);
+
+enum MapIterInner<'a> {
+    Flat(std::slice::Iter<'a, Value>),
+    #[cfg(feature = "resp3")]
+    Pairs(std::slice::Iter<'a, (Value, Value)>),
+}
 
 impl<'a> Iterator for MapIter<'a> {
     type Item = (&'a Value, &'a Value);
 
     fn next(&mut self) -> Option<Self::Item> {
-        Some((self.0.next()?, self.0.next()?))
+        match self.0 {
+            MapIterInner::Flat(ref mut iter) => Some((iter.next()?, iter.next()?)),
+            #[cfg(feature = "resp3")]
+            MapIterInner::Pairs(ref mut iter) => iter.next().map(|(k, v)| (k, v)),
+        }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let (low, high) = self.0.size_hint();
-        (low / 2, high.map(|h| h / 2))
+        match self.0 {
+            MapIterInner::Flat(ref iter) => {
+                let (low, high) = iter.size_hint();
+                (low / 2, high.map(|h| h / 2))
+            }
+            #[cfg(feature = "resp3")]
+            MapIterInner::Pairs(ref iter) => iter.size_hint(),
+        }
     }
 }
 
@@ -211,6 +462,8 @@ impl Value {
     pub fn as_sequence(&self) -> Option<&[Value]> {
         match self {
             Value::Bulk(items) => Some(&items[..]),
+            #[cfg(feature = "resp3")]
+            Value::Set(items) | Value::Push(items) => Some(&items[..]),
             Value::Nil => Some(&[]),
             _ => None,
         }
@@ -219,7 +472,9 @@ impl Value {
     /// Returns an iterator of `(&Value, &Value)` if `self` is compatible with a map type
     pub fn as_map_iter(&self) -> Option<MapIter<'_>> {
         match self {
-            Value::Bulk(items) => Some(MapIter(items.iter())),
+            Value::Bulk(items) => Some(MapIter(MapIterInner::Flat(items.iter()))),
+            #[cfg(feature = "resp3")]
+            Value::Map(items) => Some(MapIter(MapIterInner::Pairs(items.iter()))),
             _ => None,
         }
     }
@@ -227,6 +482,19 @@ impl Value {
 
 impl fmt::Debug for Value {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_items(fmt: &mut fmt::Formatter<'_>, name: &str, values: &[Value]) -> fmt::Result {
+            write!(fmt, "{name}(")?;
+            let mut is_first = true;
+            for val in values.iter() {
+                if !is_first {
+                    write!(fmt, ", ")?;
+                }
+                write!(fmt, "{val:?}")?;
+                is_first = false;
+            }
+            write!(fmt, ")")
+        }
+
         match *self {
             Value::Nil => write!(fmt, "nil"),
             Value::Int(val) => write!(fmt, "int({val:?})"),
@@ -234,20 +502,42 @@ impl fmt::Debug for Value {
                 Ok(x) => write!(fmt, "string-data('{x:?}')"),
                 Err(_) => write!(fmt, "binary-data({val:?})"),
             },
-            Value::Bulk(ref values) => {
-                write!(fmt, "bulk(")?;
+            Value::Bulk(ref values) => write_items(fmt, "bulk", values),
+            Value::Okay => write!(fmt, "ok"),
+            Value::Status(ref s) => write!(fmt, "status({s:?})"),
+            #[cfg(feature = "resp3")]
+            Value::Double(Double(val)) => write!(fmt, "double({val:?})"),
+            #[cfg(feature = "resp3")]
+            Value::Boolean(val) => write!(fmt, "boolean({val:?})"),
+            #[cfg(feature = "resp3")]
+            Value::VerbatimString {
+                ref format,
+                ref text,
+            } => write!(fmt, "verbatim-string({format:?}, {text:?})"),
+            #[cfg(feature = "resp3")]
+            Value::BigNumber(ref val) => write!(fmt, "big-number({val})"),
+            #[cfg(feature = "resp3")]
+            Value::Map(ref values) => {
+                write!(fmt, "map(")?;
                 let mut is_first = true;
-                for val in values.iter() {
+                for (key, val) in values.iter() {
                     if !is_first {
                         write!(fmt, ", ")?;
                     }
-                    write!(fmt, "{val:?}")?;
+                    write!(fmt, "{key:?}: {val:?}")?;
                     is_first = false;
                 }
                 write!(fmt, ")")
             }
-            Value::Okay => write!(fmt, "ok"),
-            Value::Status(ref s) => write!(fmt, "status({s:?})"),
+            #[cfg(feature = "resp3")]
+            Value::Set(ref values) => write_items(fmt, "set", values),
+            #[cfg(feature = "resp3")]
+            Value::Push(ref values) => write_items(fmt, "push", values),
+            #[cfg(feature = "resp3")]
+            Value::Attribute {
+                ref data,
+                ref attributes,
+            } => write!(fmt, "attribute({data:?}, {attributes:?})"),
         }
     }
 }
@@ -257,6 +547,8 @@ impl fmt::Debug for Value {
 /// struct.
 pub struct RedisError {
     repr: ErrorRepr,
+    // The responses read before a pipeline failed, see `partial_responses`.
+    partial_responses: Option<Vec<RedisResult<Value>>>,
 }
 
 #[cfg(feature = "json")]
@@ -298,6 +590,7 @@ impl From<io::Error> for RedisError {
     fn from(err: io::Error) -> RedisError {
         RedisError {
             repr: ErrorRepr::IoError(err),
+            partial_responses: None,
         }
     }
 }
@@ -306,6 +599,7 @@ impl From<Utf8Error> for RedisError {
     fn from(_: Utf8Error) -> RedisError {
         RedisError {
             repr: ErrorRepr::WithDescription(ErrorKind::TypeError, "Invalid UTF-8"),
+            partial_responses: None,
         }
     }
 }
@@ -318,6 +612,7 @@ impl From<NulError> for RedisError {
                 "Value contains interior nul terminator",
                 err.to_string(),
             ),
+            partial_responses: None,
         }
     }
 }
@@ -331,6 +626,7 @@ impl From<native_tls::Error> for RedisError {
                 "TLS error",
                 err.to_string(),
             ),
+            partial_responses: None,
         }
     }
 }
@@ -344,6 +640,7 @@ impl From<rustls::Error> for RedisError {
                 "TLS error",
                 err.to_string(),
             ),
+            partial_responses: None,
         }
     }
 }
@@ -357,6 +654,7 @@ impl From<rustls::client::InvalidDnsNameError> for RedisError {
                 "TLS Error",
                 err.to_string(),
             ),
+            partial_responses: None,
         }
     }
 }
@@ -365,6 +663,7 @@ impl From<FromUtf8Error> for RedisError {
     fn from(_: FromUtf8Error) -> RedisError {
         RedisError {
             repr: ErrorRepr::WithDescription(ErrorKind::TypeError, "Cannot convert from UTF-8"),
+            partial_responses: None,
         }
     }
 }
@@ -373,6 +672,7 @@ impl From<(ErrorKind, &'static str)> for RedisError {
     fn from((kind, desc): (ErrorKind, &'static str)) -> RedisError {
         RedisError {
             repr: ErrorRepr::WithDescription(kind, desc),
+            partial_responses: None,
         }
     }
 }
@@ -381,6 +681,7 @@ impl From<(ErrorKind, &'static str, String)> for RedisError {
     fn from((kind, desc, detail): (ErrorKind, &'static str, String)) -> RedisError {
         RedisError {
             repr: ErrorRepr::WithDescriptionAndDetail(kind, desc, detail),
+            partial_responses: None,
         }
     }
 }
@@ -396,14 +697,49 @@ impl error::Error for RedisError {
         }
     }
 
-    fn cause(&self) -> Option<&dyn error::Error> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self.repr {
-            ErrorRepr::IoError(ref err) => Some(err as &dyn error::Error),
+            ErrorRepr::IoError(ref err) => Some(err),
             _ => None,
         }
     }
 }
 
+/// A copy of an error and its sources, used where errors have to be cloned but `io::Error`
+/// can't be.  The messages are kept, so the chain can still be walked with `Error::source`.
+#[cfg(feature = "aio")]
+#[derive(Debug)]
+struct ChainedError {
+    message: String,
+    source: Option<Box<ChainedError>>,
+}
+
+#[cfg(feature = "aio")]
+impl ChainedError {
+    fn new(message: String, source: Option<&(dyn error::Error + 'static)>) -> Self {
+        ChainedError {
+            message,
+            source: source.map(|err| Box::new(ChainedError::new(err.to_string(), err.source()))),
+        }
+    }
+}
+
+#[cfg(feature = "aio")]
+impl fmt::Display for ChainedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.message.fmt(f)
+    }
+}
+
+#[cfg(feature = "aio")]
+impl error::Error for ChainedError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|err| err as &(dyn error::Error + 'static))
+    }
+}
+
 impl fmt::Display for RedisError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         match self.repr {
@@ -447,6 +783,32 @@ impl RedisError {
         }
     }
 
+    /// Returns the responses that were read before a pipeline failed with an I/O or parse
+    /// error, or `None` for all other errors.
+    ///
+    /// The responses belong to the first commands of the pipeline, in order: the server executed
+    /// those commands, while it's unknown whether it executed the remaining ones.  Commands that
+    /// failed with an error returned by the server have an `Err` response.  For atomic pipelines
+    /// the only response is the one to `EXEC`, so nothing is ever read before it.
+    pub fn partial_responses(&self) -> Option<&[RedisResult<Value>]> {
+        self.partial_responses.as_deref()
+    }
+
+    pub(crate) fn with_partial_responses(mut self, responses: Vec<RedisResult<Value>>) -> Self {
+        self.partial_responses = Some(responses);
+        self
+    }
+
+    // Drops the partial responses to the first `count` commands, which were added by the
+    // connection itself, like `MULTI`.
+    #[cfg(feature = "aio")]
+    pub(crate) fn skip_partial_responses(mut self, count: usize) -> Self {
+        if let Some(ref mut responses) = self.partial_responses {
+            responses.drain(..count.min(responses.len()));
+        }
+        self
+    }
+
     /// Returns the error detail.
     pub fn detail(&self) -> Option<&str> {
         match self.repr {
@@ -502,6 +864,9 @@ impl RedisError {
             ErrorKind::NoValidReplicasFoundBySentinel => "no valid replicas found by sentinel",
             ErrorKind::EmptySentinelList => "empty sentinel list",
             ErrorKind::NotBusy => "not busy",
+            ErrorKind::Busy => "busy",
+            ErrorKind::Cancelled => "cancelled",
+            ErrorKind::Reconnecting => "reconnecting",
             #[cfg(feature = "json")]
             ErrorKind::Serialize => "serializing",
         }
@@ -573,6 +938,27 @@ impl RedisError {
         }
     }
 
+    /// Returns true if the connection can't be used reliably anymore after this error.
+    ///
+    /// This is the case for I/O errors (including timeouts) and parse errors, as any remaining
+    /// responses can no longer be matched with the requests they belong to.  Errors signalled by
+    /// the server are complete responses and leave the connection usable.
+    pub(crate) fn is_unrecoverable_error(&self) -> bool {
+        matches!(
+            self.repr,
+            ErrorRepr::IoError(_)
+                | ErrorRepr::WithDescriptionAndDetail(ErrorKind::ResponseError, "parse error", _)
+        )
+    }
+
+    /// Returns true if the server didn't recognize the command, e.g. because it is too old.
+    pub(crate) fn is_unknown_command(&self) -> bool {
+        self.kind() == ErrorKind::ResponseError
+            && self
+                .detail()
+                .map_or(false, |detail| detail.starts_with("unknown command"))
+    }
+
     /// Returns the node the error refers to.
     ///
     /// This returns `(addr, slot_id)`.
@@ -605,9 +991,10 @@ impl RedisError {
     /// Deriving `Clone` is not possible because the wrapped `io::Error` is not
     /// cloneable.
     ///
-    /// The `ioerror_description` parameter will be prepended to the message in
-    /// case an `IoError` is found.
-    #[cfg(feature = "connection-manager")] // Used to avoid "unused method" warning
+    /// In case an `IoError` is found, the clone is an `IoError` of the same kind
+    /// described by `ioerror_description` followed by the original message, whose
+    /// source is a copy of the original error and its sources.
+    #[cfg(feature = "aio")] // Used to avoid "unused method" warning
     pub(crate) fn clone_mostly(&self, ioerror_description: &'static str) -> Self {
         let repr = match self.repr {
             ErrorRepr::WithDescription(kind, desc) => ErrorRepr::WithDescription(kind, desc),
@@ -619,10 +1006,13 @@ impl RedisError {
             }
             ErrorRepr::IoError(ref e) => ErrorRepr::IoError(io::Error::new(
                 e.kind(),
-                format!("{ioerror_description}: {e}"),
+                ChainedError::new(format!("{ioerror_description}: {e}"), Some(e)),
             )),
         };
-        Self { repr }
+        Self {
+            repr,
+            partial_responses: None,
+        }
     }
 
     // TODO: In addition to/instead of returning a bool here, consider a method
@@ -653,6 +1043,9 @@ impl RedisError {
             ErrorKind::ClientError => false,
             ErrorKind::EmptySentinelList => false,
             ErrorKind::NotBusy => false,
+            ErrorKind::Busy => true,
+            ErrorKind::Cancelled => false,
+            ErrorKind::Reconnecting => true,
             #[cfg(feature = "json")]
             ErrorKind::Serialize => false,
         }
@@ -668,6 +1061,7 @@ pub fn make_extension_error(code: &str, detail: Option<&str>) -> RedisError {
                 None => "Unknown extension error encountered".to_string(),
             },
         ),
+        partial_responses: None,
     }
 }
 
@@ -682,6 +1076,7 @@ pub type RedisFuture<'a, T> = futures_util::future::BoxFuture<'a, RedisResult<T>
 #[derive(Debug, Clone)]
 pub struct InfoDict {
     map: HashMap<String, Value>,
+    sections: HashMap<String, HashMap<String, String>>,
 }
 
 /// This type provides convenient access to key/value data returned by
@@ -698,25 +1093,40 @@ pub struct InfoDict {
 /// # let mut con = client.get_connection().unwrap();
 /// let info : redis::InfoDict = redis::cmd("INFO").query(&mut con)?;
 /// let role : Option<String> = info.get("role");
+/// let memory = info.section("memory");
 /// # Ok(()) }
 /// ```
 impl InfoDict {
     /// Creates a new info dictionary from a string in the response of
     /// the INFO command.  Each line is a key, value pair with the
     /// key and value separated by a colon (`:`).  Lines starting with a
-    /// hash (`#`) are ignored.
+    /// hash (`#`) start a new section (e.g. `# Memory`) and are not
+    /// included as keys.
     pub fn new(kvpairs: &str) -> InfoDict {
         let mut map = HashMap::new();
+        let mut sections = HashMap::new();
+        let mut section: Option<&mut HashMap<String, String>> = None;
         for line in kvpairs.lines() {
-            if line.is_empty() || line.starts_with('#') {
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('#') {
+                section = Some(
+                    sections
+                        .entry(name.trim().to_lowercase())
+                        .or_insert_with(HashMap::new),
+                );
                 continue;
             }
             let mut p = line.splitn(2, ':');
             let k = unwrap_or!(p.next(), continue).to_string();
             let v = unwrap_or!(p.next(), continue).to_string();
+            if let Some(ref mut section) = section {
+                section.insert(k.clone(), v.clone());
+            }
             map.insert(k, Value::Status(v));
         }
-        InfoDict { map }
+        InfoDict { map, sections }
     }
 
     /// Fetches a value by key and converts it into the given type.
@@ -747,6 +1157,31 @@ impl InfoDict {
     pub fn is_empty(&self) -> bool {
         self.map.is_empty()
     }
+
+    /// Returns the key/value pairs of a section by its lowercase name, e.g. `"memory"`.
+    pub fn section(&self, name: &str) -> Option<&HashMap<String, String>> {
+        self.sections.get(name)
+    }
+
+    /// Returns all sections, keyed by their lowercase name.
+    pub fn sections(&self) -> &HashMap<String, HashMap<String, String>> {
+        &self.sections
+    }
+
+    /// Returns the number of bytes allocated by the server (`used_memory`).
+    pub fn used_memory(&self) -> Option<u64> {
+        self.get("used_memory")
+    }
+
+    /// Returns the number of client connections (`connected_clients`).
+    pub fn connected_clients(&self) -> Option<u64> {
+        self.get("connected_clients")
+    }
+
+    /// Returns the replication role of the server (`role`), e.g. `master` or `slave`.
+    pub fn role(&self) -> Option<String> {
+        self.get("role")
+    }
 }
 
 impl Deref for InfoDict {
@@ -952,6 +1387,92 @@ non_zero_itoa_based_to_redis_impl!(core::num::NonZeroIsize, NumericBehavior::Num
 ryu_based_to_redis_impl!(f32, NumericBehavior::NumberIsFloat);
 ryu_based_to_redis_impl!(f64, NumericBehavior::NumberIsFloat);
 
+impl<I> ToRedisArgs for IterArgs<I>
+where
+    I: IntoIterator + Clone,
+    I::Item: ToRedisArgs,
+{
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        for item in self.0.clone() {
+            item.write_redis_args(out);
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        let mut items = self.0.clone().into_iter();
+        match (items.next(), items.next()) {
+            (Some(item), None) => item.is_single_arg(),
+            _ => false,
+        }
+    }
+}
+
+impl ToRedisArgs for Seconds {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        let secs = self
+            .0
+            .as_secs()
+            .saturating_add(u64::from(self.0.subsec_nanos() > 0));
+        secs.write_redis_args(out)
+    }
+
+    fn describe_numeric_behavior(&self) -> NumericBehavior {
+        NumericBehavior::NumberIsInteger
+    }
+}
+
+impl ToRedisArgs for Millis {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        let millis = self.0.as_millis() + u128::from(self.0.subsec_nanos() % 1_000_000 > 0);
+        u64::try_from(millis)
+            .unwrap_or(u64::MAX)
+            .write_redis_args(out)
+    }
+
+    fn describe_numeric_behavior(&self) -> NumericBehavior {
+        NumericBehavior::NumberIsInteger
+    }
+}
+
+impl ToRedisArgs for Seconds<SystemTime> {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        let since_epoch = self.0.duration_since(UNIX_EPOCH).unwrap_or_default();
+        since_epoch.as_secs().write_redis_args(out)
+    }
+
+    fn describe_numeric_behavior(&self) -> NumericBehavior {
+        NumericBehavior::NumberIsInteger
+    }
+}
+
+impl ToRedisArgs for Millis<SystemTime> {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        let since_epoch = self.0.duration_since(UNIX_EPOCH).unwrap_or_default();
+        u64::try_from(since_epoch.as_millis())
+            .unwrap_or(u64::MAX)
+            .write_redis_args(out)
+    }
+
+    fn describe_numeric_behavior(&self) -> NumericBehavior {
+        NumericBehavior::NumberIsInteger
+    }
+}
+
 impl ToRedisArgs for bool {
     fn write_redis_args<W>(&self, out: &mut W)
     where
@@ -1225,6 +1746,45 @@ pub trait FromRedisValue: Sized {
     }
 }
 
+// Converts the numbers of RESP3 doubles.  Integers are only converted if that's lossless.
+#[cfg(feature = "resp3")]
+trait FromDouble: Sized {
+    fn from_double(val: f64) -> Option<Self>;
+}
+
+#[cfg(feature = "resp3")]
+macro_rules! from_double_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl FromDouble for $t {
+                fn from_double(val: f64) -> Option<$t> {
+                    // `MAX as f64` rounds up to a power of two for the larger types, which is
+                    // out of range itself.
+                    let in_range = val >= <$t>::MIN as f64 && val < <$t>::MAX as f64 + 1.0;
+                    (val.fract() == 0.0 && in_range).then_some(val as $t)
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "resp3")]
+from_double_for_int!(i8, u8, i16, u16, i32, u32, i64, u64, i128, u128, isize, usize);
+
+#[cfg(feature = "resp3")]
+impl FromDouble for f32 {
+    fn from_double(val: f64) -> Option<f32> {
+        Some(val as f32)
+    }
+}
+
+#[cfg(feature = "resp3")]
+impl FromDouble for f64 {
+    fn from_double(val: f64) -> Option<f64> {
+        Some(val)
+    }
+}
+
 macro_rules! from_redis_value_for_num_internal {
     ($t:ty, $v:expr) => {{
         let v = $v;
@@ -1238,6 +1798,16 @@ macro_rules! from_redis_value_for_num_internal {
                 Ok(rv) => Ok(rv),
                 Err(_) => invalid_type_error!(v, "Could not convert from string."),
             },
+            #[cfg(feature = "resp3")]
+            Value::Double(Double(val)) => match FromDouble::from_double(val) {
+                Some(rv) => Ok(rv),
+                None => invalid_type_error!(v, "Could not convert from double."),
+            },
+            #[cfg(feature = "resp3")]
+            Value::BigNumber(ref s) => match s.parse::<$t>() {
+                Ok(rv) => Ok(rv),
+                Err(_) => invalid_type_error!(v, "Could not convert from big number."),
+            },
             _ => invalid_type_error!(v, "Response type not convertible to numeric."),
         }
     }};
@@ -1302,6 +1872,8 @@ impl FromRedisValue for bool {
                 }
             }
             Value::Okay => Ok(true),
+            #[cfg(feature = "resp3")]
+            Value::Boolean(val) => Ok(val),
             _ => invalid_type_error!(v, "Response type not bool compatible."),
         }
     }
@@ -1324,6 +1896,10 @@ impl FromRedisValue for String {
             Value::Data(ref bytes) => Ok(from_utf8(bytes)?.to_string()),
             Value::Okay => Ok("OK".to_string()),
             Value::Status(ref val) => Ok(val.to_string()),
+            #[cfg(feature = "resp3")]
+            Value::VerbatimString { ref text, .. } => Ok(text.to_string()),
+            #[cfg(feature = "resp3")]
+            Value::BigNumber(ref val) => Ok(val.to_string()),
             _ => invalid_type_error!(v, "Response type not string compatible."),
         }
     }
@@ -1341,6 +1917,10 @@ impl<T: FromRedisValue> FromRedisValue for Vec<T> {
                 ),
             },
             Value::Bulk(ref items) => FromRedisValue::from_redis_values(items),
+            #[cfg(feature = "resp3")]
+            Value::Set(ref items) | Value::Push(ref items) => {
+                FromRedisValue::from_redis_values(items)
+            }
             Value::Nil => Ok(vec![]),
             _ => invalid_type_error!(v, "Response type not vector compatible."),
         }
@@ -1513,6 +2093,103 @@ impl FromRedisValue for InfoDict {
     }
 }
 
+impl FromRedisValue for KeyType {
+    fn from_redis_value(v: &Value) -> RedisResult<KeyType> {
+        let s: String = from_redis_value(v)?;
+        Ok(match s.as_str() {
+            "string" => KeyType::String,
+            "list" => KeyType::List,
+            "set" => KeyType::Set,
+            "zset" => KeyType::ZSet,
+            "hash" => KeyType::Hash,
+            "stream" => KeyType::Stream,
+            "none" => KeyType::None,
+            _ => KeyType::Unknown(s),
+        })
+    }
+}
+
+impl FromRedisValue for ObjectEncoding {
+    fn from_redis_value(v: &Value) -> RedisResult<ObjectEncoding> {
+        let s: String = from_redis_value(v)?;
+        Ok(match s.as_str() {
+            "raw" => ObjectEncoding::Raw,
+            "int" => ObjectEncoding::Int,
+            "embstr" => ObjectEncoding::EmbStr,
+            "hashtable" => ObjectEncoding::HashTable,
+            "listpack" => ObjectEncoding::ListPack,
+            "ziplist" => ObjectEncoding::ZipList,
+            "quicklist" => ObjectEncoding::QuickList,
+            "linkedlist" => ObjectEncoding::LinkedList,
+            "intset" => ObjectEncoding::IntSet,
+            "skiplist" => ObjectEncoding::SkipList,
+            "stream" => ObjectEncoding::Stream,
+            _ => ObjectEncoding::Other(s),
+        })
+    }
+}
+
+// Reads a non-negative number of seconds or milliseconds.
+fn time_units(v: &Value) -> RedisResult<u64> {
+    let units: i64 = from_redis_value(v)?;
+    match u64::try_from(units) {
+        Ok(units) => Ok(units),
+        Err(_) => invalid_type_error!(v, "Negative time."),
+    }
+}
+
+impl FromRedisValue for Seconds {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        Ok(Seconds(Duration::from_secs(time_units(v)?)))
+    }
+}
+
+impl FromRedisValue for Millis {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        Ok(Millis(Duration::from_millis(time_units(v)?)))
+    }
+}
+
+impl FromRedisValue for Seconds<SystemTime> {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        Ok(Seconds(UNIX_EPOCH + Duration::from_secs(time_units(v)?)))
+    }
+}
+
+impl FromRedisValue for Millis<SystemTime> {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        Ok(Millis(UNIX_EPOCH + Duration::from_millis(time_units(v)?)))
+    }
+}
+
+impl<T: FromRedisValue> FromRedisValue for Ttl<T> {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        match *v {
+            Value::Int(-1) => Ok(Ttl::Persistent),
+            Value::Int(-2) => Ok(Ttl::Missing),
+            _ => Ok(Ttl::Expires(from_redis_value(v)?)),
+        }
+    }
+}
+
+impl<M: FromRedisValue> FromRedisValue for ScoredMember<M> {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let (member, score) = from_redis_value(v)?;
+        Ok(ScoredMember { member, score })
+    }
+
+    fn from_redis_values(items: &[Value]) -> RedisResult<Vec<Self>> {
+        if items.iter().all(|item| item.as_sequence().is_some()) {
+            return items.iter().map(from_redis_value).collect();
+        }
+        let pairs: Vec<(M, f64)> = FromRedisValue::from_redis_values(items)?;
+        Ok(pairs
+            .into_iter()
+            .map(|(member, score)| ScoredMember { member, score })
+            .collect())
+    }
+}
+
 impl<T: FromRedisValue> FromRedisValue for Option<T> {
     fn from_redis_value(v: &Value) -> RedisResult<Option<T>> {
         if *v == Value::Nil {
@@ -1532,8 +2209,56 @@ impl FromRedisValue for bytes::Bytes {
     }
 }
 
+#[cfg(feature = "smallvec")]
+impl<A: smallvec::Array<Item = u8>> FromRedisValue for smallvec::SmallVec<A> {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        match v {
+            Value::Data(bytes_vec) => Ok(smallvec::SmallVec::from_slice(bytes_vec)),
+            _ => invalid_type_error!(v, "Not binary data"),
+        }
+    }
+}
+
 /// A shortcut function to invoke `FromRedisValue::from_redis_value`
 /// to make the API slightly nicer.
 pub fn from_redis_value<T: FromRedisValue>(v: &Value) -> RedisResult<T> {
+    #[cfg(feature = "resp3")]
+    if let Value::Attribute { ref data, .. } = *v {
+        return FromRedisValue::from_redis_value(data);
+    }
     FromRedisValue::from_redis_value(v)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    #[cfg(feature = "aio")]
+    fn test_clone_mostly_keeps_source() {
+        let lookup = io::Error::new(io::ErrorKind::Other, "failed to lookup address");
+        let err = RedisError::from(io::Error::new(
+            io::ErrorKind::NotFound,
+            ChainedError::new("dns error".to_string(), Some(&lookup)),
+        ));
+        let cloned = err.clone_mostly("Reconnecting failed");
+        assert_eq!(cloned.kind(), ErrorKind::IoError);
+        assert_eq!(cloned.to_string(), "Reconnecting failed: dns error");
+
+        let mut chain = Vec::new();
+        let mut source = cloned.source();
+        while let Some(err) = source {
+            chain.push(err.to_string());
+            source = err.source();
+        }
+        assert_eq!(
+            chain,
+            [
+                "Reconnecting failed: dns error",
+                "dns error",
+                "failed to lookup address"
+            ]
+        );
+    }
+}
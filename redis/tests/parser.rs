@@ -43,6 +43,8 @@ impl ::quickcheck::Arbitrary for ArbitraryValue {
             Value::Status(ref status) => {
                 Box::new(status.shrink().map(Value::Status).map(ArbitraryValue))
             }
+            #[cfg(feature = "resp3")]
+            _ => Box::new(None.into_iter()),
         }
     }
 }
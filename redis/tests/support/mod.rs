@@ -247,6 +247,10 @@ impl RedisServer {
         }
     }
 
+    pub fn connection_builder(&self) -> redis::ConnectionBuilder {
+        redis::ConnectionBuilder::new(self.client_addr().clone())
+    }
+
     pub fn stop(&mut self) {
         let _ = self.process.kill();
         let _ = self.process.wait();
@@ -391,6 +395,58 @@ where
         }
         Value::Okay => write!(writer, "+OK\r\n"),
         Value::Status(ref s) => write!(writer, "+{s}\r\n"),
+        #[cfg(feature = "resp3")]
+        Value::Double(redis::Double(val)) => write!(writer, ",{val}\r\n"),
+        #[cfg(feature = "resp3")]
+        Value::Boolean(val) => write!(writer, "#{}\r\n", if val { "t" } else { "f" }),
+        #[cfg(feature = "resp3")]
+        Value::VerbatimString {
+            ref format,
+            ref text,
+        } => {
+            let format = match format {
+                redis::VerbatimFormat::Text => "txt",
+                redis::VerbatimFormat::Markdown => "mkd",
+                redis::VerbatimFormat::Unknown(format) => format,
+            };
+            write!(writer, "={}\r\n{format}:{text}\r\n", text.len() + 4)
+        }
+        #[cfg(feature = "resp3")]
+        Value::BigNumber(ref val) => write!(writer, "({val}\r\n"),
+        #[cfg(feature = "resp3")]
+        Value::Map(ref values) => {
+            write!(writer, "%{}\r\n", values.len())?;
+            for (key, val) in values.iter() {
+                encode_value(key, writer)?;
+                encode_value(val, writer)?;
+            }
+            Ok(())
+        }
+        #[cfg(feature = "resp3")]
+        Value::Set(ref values) | Value::Push(ref values) => {
+            let prefix = if matches!(value, Value::Set(_)) {
+                '~'
+            } else {
+                '>'
+            };
+            write!(writer, "{prefix}{}\r\n", values.len())?;
+            for val in values.iter() {
+                encode_value(val, writer)?;
+            }
+            Ok(())
+        }
+        #[cfg(feature = "resp3")]
+        Value::Attribute {
+            ref data,
+            ref attributes,
+        } => {
+            write!(writer, "|{}\r\n", attributes.len())?;
+            for (key, val) in attributes.iter() {
+                encode_value(key, writer)?;
+                encode_value(val, writer)?;
+            }
+            encode_value(data, writer)
+        }
     }
 }
 
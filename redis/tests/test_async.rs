@@ -97,6 +97,26 @@ fn test_pipeline_transaction() {
     .unwrap();
 }
 
+#[test]
+fn test_typed_pipeline() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+        con.set("name", "redis").await?;
+
+        let (name, (), visits): (String, (), i64) = redis::pipe()
+            .get("name")
+            .set("last_seen", 42)
+            .incr("visits", 2)
+            .query_async(&mut con)
+            .await?;
+        assert_eq!((name.as_str(), visits), ("redis", 2));
+        assert_eq!(con.get("last_seen").await, Ok(42));
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
 #[test]
 fn test_pipeline_transaction_with_errors() {
     use redis::RedisError;
@@ -262,6 +282,685 @@ fn test_transaction_multiplexed_connection() {
     .unwrap();
 }
 
+#[test]
+fn test_pipeline_stream_multiplexed_connection() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        let mut pipe = redis::pipe();
+        for i in 0..100 {
+            pipe.cmd("SET").arg(format!("key{i}")).arg(i).ignore();
+            pipe.cmd("GET").arg(format!("key{i}"));
+        }
+        pipe.cmd("INCR").arg("key0");
+
+        let responses: Vec<redis::Value> = con
+            .send_packed_commands_stream(&pipe, 0, 201)
+            .await?
+            .try_collect()
+            .await?;
+        assert_eq!(responses.len(), 201);
+        for i in 0..100 {
+            assert_eq!(responses[2 * i], redis::Value::Okay);
+            assert_eq!(redis::from_redis_value::<usize>(&responses[2 * i + 1])?, i);
+        }
+
+        let mut responses = con.send_packed_commands_stream(&pipe, 200, 1).await?;
+        assert_eq!(responses.next().await, Some(Ok(redis::Value::Int(2))));
+        assert_eq!(responses.next().await, None);
+
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_default_response_timeout_multiplexed() {
+    let ctx = TestContext::new();
+    let info = ctx
+        .server
+        .connection_builder()
+        .response_timeout(std::time::Duration::from_millis(100));
+    let client = redis::Client::open(info).unwrap();
+    block_on_all(async move {
+        let mut con = client.get_multiplexed_async_connection().await?;
+
+        let err = cmd("BLPOP")
+            .arg("missing_list")
+            .arg(1)
+            .query_async::<_, redis::Value>(&mut con)
+            .await
+            .unwrap_err();
+        assert!(err.is_timeout(), "{err}");
+
+        // The connection stays usable once the server answered the timed out request.
+        con.set_response_timeout(None);
+        let _: () = con.set("foo", 42).await?;
+        let value: i32 = con.get("foo").await?;
+        assert_eq!(value, 42);
+
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_default_response_timeout_async_connection() {
+    let ctx = TestContext::new();
+    let info = ctx
+        .server
+        .connection_builder()
+        .response_timeout(std::time::Duration::from_millis(100));
+    let client = redis::Client::open(info).unwrap();
+    block_on_all(async move {
+        let mut con = client.get_async_connection().await?;
+
+        let err = cmd("BLPOP")
+            .arg("missing_list")
+            .arg(1)
+            .query_async::<_, redis::Value>(&mut con)
+            .await
+            .unwrap_err();
+        assert!(err.is_timeout(), "{err}");
+
+        // The late response can't be told apart from the next one, so the connection is closed.
+        assert!(cmd("PING").query_async::<_, ()>(&mut con).await.is_err());
+
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_blocking_pop_with_response_timeout_async_connection() {
+    let ctx = TestContext::new();
+    let info = ctx
+        .server
+        .connection_builder()
+        .response_timeout(std::time::Duration::from_millis(100));
+    let client = redis::Client::open(info).unwrap();
+    block_on_all(async move {
+        let mut con = client.get_async_connection().await?;
+
+        // The server-side timeout is longer than the response timeout, yet the pop returns nil.
+        let popped: Option<(String, i32)> = con
+            .blpop_timeout("missing_list", std::time::Duration::from_millis(300))
+            .await?;
+        assert_eq!(popped, None);
+
+        let _: () = con.rpush("list", &[1, 2, 3]).await?;
+        let popped: Option<(String, i32)> = con
+            .blpop_timeout(&["missing_list", "list"], std::time::Duration::from_secs(1))
+            .await?;
+        assert_eq!(popped, Some(("list".to_string(), 1)));
+        let popped: Option<(String, i32)> = con
+            .brpop_timeout("list", std::time::Duration::from_secs(1))
+            .await?;
+        assert_eq!(popped, Some(("list".to_string(), 3)));
+
+        // The response timeout applies to other commands again.
+        let err = cmd("BLPOP")
+            .arg("missing_list")
+            .arg(1)
+            .query_async::<_, redis::Value>(&mut con)
+            .await
+            .unwrap_err();
+        assert!(err.is_timeout(), "{err}");
+
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_try_send_packed_command_busy() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        // The driver is never polled, so the requests pile up in the connection's buffer.
+        let (mut con, _driver) = ctx.client.create_multiplexed_tokio_connection().await?;
+        let ping = cmd("PING");
+        let result = loop {
+            if let Some(result) = con.try_send_packed_command(&ping).now_or_never() {
+                break result;
+            }
+        };
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::Busy);
+
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_getdel_getex() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        con.set("foo", 42).await?;
+        let value: usize = con.get_ex("foo", redis::Expiry::PX(100_000)).await?;
+        assert_eq!(value, 42);
+        let ttl: isize = con.pttl("foo").await?;
+        assert!(ttl > 0);
+
+        let value: usize = con.get_ex_unchanged("foo").await?;
+        assert_eq!(value, 42);
+        let ttl: isize = con.pttl("foo").await?;
+        assert!(ttl > 0);
+
+        let value: usize = con.get_ex("foo", redis::Expiry::PERSIST).await?;
+        assert_eq!(value, 42);
+        let ttl: isize = con.ttl("foo").await?;
+        assert_eq!(ttl, -1);
+
+        let value: usize = con.get_del("foo").await?;
+        assert_eq!(value, 42);
+        let value: Option<usize> = con.get_del("foo").await?;
+        assert_eq!(value, None);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_partial_string_commands() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        let len: u64 = con.append("foo", b"ab\xffc").await?;
+        assert_eq!(len, 4);
+        let len: u64 = con.setrange("foo", 4, "de").await?;
+        assert_eq!(len, 6);
+        let len: u64 = con.strlen("foo").await?;
+        assert_eq!(len, 6);
+
+        // The substring is not valid UTF-8, so it is read as bytes.
+        let range: Vec<u8> = con.getrange("foo", 1, -2).await?;
+        assert_eq!(range, b"b\xffcd");
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_quit() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let con = ctx.async_connection().await?;
+        con.quit().await
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_multiplexed_reconnect() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+        assert!(!con.is_closed());
+
+        let _: () = redis::cmd("CLIENT")
+            .arg("KILL")
+            .arg("SKIPME")
+            .arg("NO")
+            .query_async(&mut con)
+            .await
+            .unwrap_or(());
+        let result: RedisResult<()> = redis::cmd("PING").query_async(&mut con).await;
+        assert!(result.is_err());
+        assert!(con.is_closed());
+
+        con.reconnect().await?;
+        assert!(!con.is_closed());
+        let pong: String = redis::cmd("PING").query_async(&mut con).await?;
+        assert_eq!(pong, "PONG");
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_expire_options() {
+    use redis::ExpireCondition;
+
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        let _: () = con.set("foo", 42).await?;
+        assert!(
+            !con.expire_options("foo", 100, Some(ExpireCondition::LT { xx: true }))
+                .await?
+        );
+        assert!(
+            !con.expire_options("foo", 100, Some(ExpireCondition::XX))
+                .await?
+        );
+        assert!(
+            con.expire_options("foo", 100, Some(ExpireCondition::NX))
+                .await?
+        );
+        assert!(
+            !con.expire_options("foo", 200, Some(ExpireCondition::LT { xx: false }))
+                .await?
+        );
+        assert!(
+            con.expire_options("foo", 200, Some(ExpireCondition::GT { xx: false }))
+                .await?
+        );
+        assert!(
+            con.expire_options("foo", 150, Some(ExpireCondition::LT { xx: true }))
+                .await?
+        );
+        assert!(
+            !con.expire_options("foo", 100, Some(ExpireCondition::GT { xx: true }))
+                .await?
+        );
+        assert!(con.pexpire_options("foo", 50_000, None).await?);
+        let ttl: i64 = con.ttl("foo").await?;
+        assert!(ttl <= 50);
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap();
+        let at = now.as_secs() as usize + 1000;
+        assert!(
+            !con.expire_at_options("foo", at, Some(ExpireCondition::LT { xx: false }))
+                .await?
+        );
+        assert!(
+            con.expire_at_options("foo", at, Some(ExpireCondition::GT { xx: false }))
+                .await?
+        );
+        let at = now.as_millis() as usize + 10_000;
+        assert!(
+            con.pexpire_at_options("foo", at, Some(ExpireCondition::XX))
+                .await?
+        );
+        assert!(con.ttl::<_, i64>("foo").await? <= 10);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_dump_and_restore() {
+    use redis::{Eviction, RestoreOptions};
+
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        assert_eq!(con.dump::<_, Option<Vec<u8>>>("missing").await?, None);
+        let value = vec![0u8, 0xff, b'\n', 0x80];
+        let _: () = con.set("foo", &value).await?;
+        let serialized: Option<Vec<u8>> = con.dump("foo").await?;
+        let serialized = serialized.unwrap();
+
+        let _: () = con
+            .restore("bar", 0, &serialized, RestoreOptions::default())
+            .await?;
+        assert_eq!(con.get::<_, Vec<u8>>("bar").await?, value);
+        let err = con
+            .restore::<_, _, ()>("bar", 0, &serialized, RestoreOptions::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Some("BUSYKEY"));
+
+        let options = RestoreOptions::default()
+            .replace(true)
+            .eviction(Eviction::IdleTime(100));
+        let _: () = con.restore("bar", 10_000, &serialized, options).await?;
+        assert!(con.pttl::<_, i64>("bar").await? > 0);
+        assert!(con.object_idletime::<_, i64>("bar").await? >= 100);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_copy_and_object_freq() {
+    use redis::CopyOptions;
+
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        let _: () = con.set("foo", 42).await?;
+        let _: () = con.set("bar", 1).await?;
+        assert!(!con.copy("foo", "bar", CopyOptions::default()).await?);
+        assert!(
+            con.copy("foo", "bar", CopyOptions::default().replace(true))
+                .await?
+        );
+        assert_eq!(con.get::<_, i32>("bar").await?, 42);
+        assert!(con.copy("foo", "foo", CopyOptions::default().db(1)).await?);
+
+        let idle: i64 = con.object_idletime("foo").await?;
+        assert!(idle >= 0);
+        let err = con.object_freq::<_, i64>("foo").await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseError);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_admin_commands() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        assert_eq!(con.random_key::<Option<String>>().await?, None);
+        assert_eq!(con.db_size::<u64>().await?, 0);
+        let _: () = con.set("foo", 1).await?;
+        assert_eq!(
+            con.random_key::<Option<String>>().await?,
+            Some("foo".to_string())
+        );
+        assert_eq!(con.db_size::<u64>().await?, 1);
+
+        let _: () = con.swap_db(0, 1).await?;
+        assert_eq!(con.db_size::<u64>().await?, 0);
+        let _: () = con.swap_db(0, 1).await?;
+        assert_eq!(con.db_size::<u64>().await?, 1);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_client_pause_and_kill() {
+    use redis::{ClientKillFilter, ClientPauseMode};
+
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+        let mut other = ctx.multiplexed_async_connection().await?;
+
+        let _: () = con.client_pause(100, Some(ClientPauseMode::Write)).await?;
+        let _: () = con.client_unpause().await?;
+
+        let id: u64 = redis::cmd("CLIENT")
+            .arg("ID")
+            .query_async(&mut other)
+            .await?;
+        let filter = ClientKillFilter::default().id(id);
+        assert_eq!(con.client_kill(filter.clone()).await?, 1);
+        assert_eq!(con.client_kill(filter).await?, 0);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_client_tracking() {
+    use redis::{Invalidation, TrackingOptions};
+
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+        let mut other = ctx.multiplexed_async_connection().await?;
+
+        let mut invalidations = con.enable_tracking(TrackingOptions::default()).await?;
+        let _: () = con.set("tracked", 1).await?;
+        let _: i32 = con.get("tracked").await?;
+        let _: () = other.set("tracked", 2).await?;
+        assert_eq!(
+            invalidations.next().await,
+            Some(Invalidation::Keys(vec![b"tracked".to_vec()]))
+        );
+
+        con.reconnect().await?;
+        let _: i32 = con.get("tracked").await?;
+        let _: () = other.del("tracked").await?;
+        assert_eq!(
+            invalidations.next().await,
+            Some(Invalidation::Keys(vec![b"tracked".to_vec()]))
+        );
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_info_sections() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        let info: redis::InfoDict = con.info().await?;
+        assert_eq!(info.role(), Some("master".to_string()));
+        assert!(info.section("server").is_some());
+
+        let info: redis::InfoDict = con.info_sections("clients").await?;
+        assert!(info.connected_clients().unwrap() >= 1);
+        assert!(info.section("memory").is_none());
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_object_encoding() {
+    use redis::ObjectEncoding;
+
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        let _: () = con.set("int", 42).await?;
+        let _: () = con.set("str", "short").await?;
+        let _: () = con.sadd("set", &[1, 2, 3]).await?;
+        assert_eq!(con.object_encoding("int").await, Ok(ObjectEncoding::Int));
+        assert_eq!(con.object_encoding("str").await, Ok(ObjectEncoding::EmbStr));
+        assert_eq!(con.object_encoding("set").await, Ok(ObjectEncoding::IntSet));
+        let refcount: i64 = con.object_refcount("str").await?;
+        assert!(refcount >= 1);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_wait() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        // The test server has no replicas
+        let _: () = con.set("foo", 42).await?;
+        assert_eq!(
+            con.wait(0, std::time::Duration::from_millis(100)).await,
+            Ok(0)
+        );
+        let err = con
+            .wait(1, std::time::Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseError);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_pipeline_guard() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        let mut guard = con.pipeline_guard();
+        let set = guard.send_packed_command(redis::cmd("SET").arg("key").arg(1));
+        let incr = guard.send_packed_command(redis::cmd("INCR").arg("key"));
+        let fail = guard.send_packed_command(redis::cmd("INCR").arg("key").arg("extra"));
+        guard.flush().await?;
+        assert_eq!(set.await?, redis::Value::Okay);
+        assert_eq!(incr.await?, redis::Value::Int(2));
+        assert!(fail.await.is_err());
+
+        // Dropping the guard discards the commands that were not flushed
+        let incr = {
+            let mut guard = con.pipeline_guard();
+            guard.send_packed_command(redis::cmd("INCR").arg("key"))
+        };
+        assert_eq!(incr.await.unwrap_err().kind(), ErrorKind::Cancelled);
+
+        // The commands are sent to the database the connection is bound to
+        let other_db = con.with_db(1).await?;
+        let mut guard = other_db.pipeline_guard();
+        let set = guard.send_packed_command(redis::cmd("SET").arg("key").arg(10));
+        guard.flush().await?;
+        assert_eq!(set.await?, redis::Value::Okay);
+
+        let value: i64 = con.get("key").await?;
+        assert_eq!(value, 2);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_transaction() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.async_connection().await?;
+
+        let mut transaction = con.multi().await?;
+        transaction.queue(cmd("SET").arg("foo").arg(1)).await?;
+        transaction.queue(cmd("INCR").arg("foo")).await?;
+        let (_, value): ((), i64) = transaction.exec().await?;
+        assert_eq!(value, 2);
+
+        let mut transaction = con.multi().await?;
+        transaction.queue(cmd("INCR").arg("foo")).await?;
+        transaction.discard().await?;
+        let value: i64 = con.get("foo").await?;
+        assert_eq!(value, 2);
+
+        // An abandoned transaction is discarded before the next command, which therefore
+        // isn't queued.
+        {
+            let mut transaction = con.multi().await?;
+            transaction.queue(cmd("INCR").arg("foo")).await?;
+        }
+        let value: i64 = con.get("foo").await?;
+        assert_eq!(value, 2);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_command_hook() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.async_connection().await?;
+        // Prefixes the first argument after the command name, which is the key for most commands.
+        con.set_command_hook(|cmd| {
+            let mut prefixed = redis::Cmd::new();
+            for (index, arg) in cmd.args_iter().enumerate() {
+                if let redis::Arg::Simple(arg) = arg {
+                    if index == 1 {
+                        prefixed.arg([&b"tenant:"[..], arg].concat());
+                    } else {
+                        prefixed.arg(arg);
+                    }
+                }
+            }
+            *cmd = prefixed;
+        });
+
+        con.set("foo", 1).await?;
+        let (value,): (i64,) = redis::pipe().incr("foo", 1).query_async(&mut con).await?;
+        assert_eq!(value, 2);
+
+        con.clear_command_hook();
+        let value: Option<i64> = con.get("foo").await?;
+        assert_eq!(value, None);
+        let value: i64 = con.get("tenant:foo").await?;
+        assert_eq!(value, 2);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_reset() {
+    let ctx = TestContext::new();
+    let info = ctx.server.connection_builder().db(1);
+    let client = redis::Client::open(info).unwrap();
+    block_on_all(async move {
+        let mut con = client.get_async_connection().await?;
+
+        let _: () = redis::cmd("SELECT").arg(2).query_async(&mut con).await?;
+        let _: () = redis::cmd("MULTI").query_async(&mut con).await?;
+        con.reset().await?;
+
+        // The transaction was discarded and the configured database selected again.
+        let _: () = con.set("foo", 42).await?;
+        let mut other = client.get_async_connection().await?;
+        let value: i32 = other.get("foo").await?;
+        assert_eq!(value, 42);
+
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_reset_multiplexed() {
+    let ctx = TestContext::new();
+    let info = ctx.server.connection_builder().db(1);
+    let client = redis::Client::open(info).unwrap();
+    block_on_all(async move {
+        let mut con = client.get_multiplexed_async_connection().await?;
+
+        let _: () = redis::cmd("SELECT").arg(2).query_async(&mut con).await?;
+        con.reset().await?;
+
+        let _: () = con.set("foo", 42).await?;
+        let mut other = client.get_async_connection().await?;
+        let value: i32 = other.get("foo").await?;
+        assert_eq!(value, 42);
+
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_query_optional() {
+    use redis::aio::ConnectionLike;
+
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        let missing: Option<String> = con
+            .query_optional(redis::cmd("GET").arg("missing_key"))
+            .await?;
+        assert_eq!(missing, None);
+
+        let _: () = con.set("existing_key", "foo").await?;
+        let existing: Option<String> = con
+            .query_optional(redis::cmd("GET").arg("existing_key"))
+            .await?;
+        assert_eq!(existing, Some("foo".to_string()));
+
+        let err = con
+            .query_optional::<i32>(redis::cmd("GET").arg("existing_key"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TypeError);
+
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
 fn test_async_scanning(batch_size: usize) {
     let ctx = TestContext::new();
     block_on_all(async move {
@@ -314,6 +1013,101 @@ fn test_async_scanning_small_batch() {
     test_async_scanning(2)
 }
 
+#[test]
+fn test_pipeline_is_not_interleaved_across_clones() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let con = ctx.multiplexed_async_connection().await?;
+
+        let requests = (0..100).map(|_| {
+            let mut con = con.clone();
+            async move {
+                let (incremented, read): (i64, i64) = redis::pipe()
+                    .incr("counter", 1)
+                    .get("counter")
+                    .query_async(&mut con)
+                    .await?;
+                // No command from another clone ran between the two commands of the pipeline.
+                assert_eq!(incremented, read);
+                Ok::<_, redis::RedisError>(())
+            }
+        });
+        future::try_join_all(requests).await?;
+
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_pipeline_partial_responses() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+        let id: i64 = cmd("CLIENT").arg("ID").query_async(&mut con).await?;
+
+        // The server closes the connection after answering `CLIENT KILL`.
+        let err = redis::pipe()
+            .set("foo", 1)
+            .incr("foo", 1)
+            .cmd("CLIENT")
+            .arg("KILL")
+            .arg("ID")
+            .arg(id)
+            .get("foo")
+            .query_async::<_, ()>(&mut con)
+            .await
+            .unwrap_err();
+        let responses = err.partial_responses().unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0], Ok(redis::Value::Okay));
+        assert_eq!(responses[1], Ok(redis::Value::Int(2)));
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_del_all() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.multiplexed_async_connection().await?;
+
+        assert_eq!(con.del_all(std::iter::empty::<&str>()).await, Ok(0));
+
+        let _: () = con.mset(&[("foo", 1), ("bar", 2)]).await?;
+        assert_eq!(con.del_all(["foo", "bar", "baz"]).await, Ok(2));
+
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_async_hscan_pairs() {
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let mut con = ctx.async_connection().await?;
+
+        // Enough fields to have the hash encoded as a hashtable, which is scanned in batches.
+        let fields: Vec<_> = (0..1000).map(|x| (format!("field_{x}"), x)).collect();
+        let _: () = con.hset_multiple("foo", &fields).await?;
+
+        let mut unseen: std::collections::HashSet<_> = fields.into_iter().collect();
+        let mut iter: redis::AsyncIter<(String, redis::Value)> = con.hscan_pairs("foo").await?;
+        while let Some((field, value)) = iter.next_item().await {
+            let value: usize = redis::from_redis_value(&value)?;
+            assert_eq!(field, format!("field_{value}"));
+            // if this assertion fails, too many items were returned by the iterator.
+            assert!(unseen.remove(&(field, value)));
+        }
+        assert!(unseen.is_empty());
+
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
 #[test]
 #[cfg(feature = "script")]
 fn test_script() {
@@ -525,6 +1319,39 @@ mod pub_sub {
         .unwrap();
     }
 
+    #[test]
+    fn pub_sub_keyevents() {
+        use redis::RedisError;
+
+        let ctx = TestContext::new();
+        block_on_all(async move {
+            let mut conn = ctx.async_connection().await?;
+            let _: () = redis::cmd("CONFIG")
+                .arg("SET")
+                .arg("notify-keyspace-events")
+                .arg("E$")
+                .query_async(&mut conn)
+                .await?;
+
+            let mut pubsub_conn = ctx.async_connection().await?.into_pubsub();
+            let mut events = pubsub_conn.subscribe_keyevents("set").await?;
+            let _: () = conn.set("phonewave", "banana").await?;
+
+            let event = events.next().await.unwrap();
+            assert_eq!(
+                event,
+                redis::KeyspaceEvent {
+                    db: 0,
+                    event: "set".to_string(),
+                    key: b"phonewave".to_vec(),
+                }
+            );
+
+            Ok::<_, RedisError>(())
+        })
+        .unwrap();
+    }
+
     #[test]
     fn pub_sub_unsubscription() {
         use redis::RedisError;
@@ -664,6 +1491,26 @@ async fn wait_for_server_to_become_ready(client: redis::Client) {
     }
 }
 
+#[test]
+fn test_handshake_timeout() {
+    // The listener accepts connections, but nothing ever responds to the setup commands.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let client = redis::ConnectionBuilder::tcp("127.0.0.1", port)
+        .handshake_timeout(std::time::Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    block_on_all(async move {
+        let err = client
+            .get_multiplexed_async_connection()
+            .await
+            .err()
+            .unwrap();
+        assert!(err.is_timeout(), "{err}");
+    });
+}
+
 #[test]
 #[cfg(feature = "connection-manager")]
 fn test_connection_manager_reconnect_after_delay() {
@@ -687,3 +1534,328 @@ fn test_connection_manager_reconnect_after_delay() {
         assert_eq!(result, redis::Value::Okay);
     });
 }
+
+#[test]
+#[cfg(feature = "connection-manager")]
+fn test_connection_manager_never_reconnects() {
+    let ctx = TestContext::new();
+
+    block_on_all(async move {
+        let mut manager = redis::aio::ConnectionManager::new(ctx.client.clone())
+            .await
+            .unwrap();
+        manager.set_reconnect_policy(redis::aio::ReconnectPolicy::Never);
+        let server = ctx.server;
+        let addr = server.client_addr().clone();
+        drop(server);
+
+        let result: RedisResult<redis::Value> = manager.set("foo", "bar").await;
+        assert!(result.is_err());
+
+        let _new_server = RedisServer::new_with_addr_and_modules(addr.clone(), &[]);
+        wait_for_server_to_become_ready(ctx.client.clone()).await;
+
+        // The dead connection was kept, so the new server is not used.
+        let result: RedisResult<redis::Value> = manager.set("foo", "bar").await;
+        assert!(result.is_err());
+    });
+}
+
+#[test]
+#[cfg(feature = "connection-manager")]
+fn test_connection_manager_fail_fast_while_reconnecting() {
+    let ctx = TestContext::new();
+
+    block_on_all(async move {
+        let mut manager = redis::aio::ConnectionManager::new(ctx.client.clone()).await?;
+        manager.set_fail_fast_while_reconnecting(true);
+
+        let mut con = ctx.async_connection().await?;
+        redis::cmd("CLIENT")
+            .arg("KILL")
+            .arg("TYPE")
+            .arg("normal")
+            .arg("SKIPME")
+            .arg("yes")
+            .query_async::<_, ()>(&mut con)
+            .await?;
+        // Noticing the killed connection triggers the reconnection, which didn't get to run yet.
+        let _: RedisResult<()> = manager.set("foo", "bar").await;
+        let err = manager.set::<_, _, ()>("foo", "bar").await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Reconnecting);
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let _: () = manager.set("foo", "bar").await?;
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "connection-manager")]
+fn test_connection_manager_current_connection() {
+    let ctx = TestContext::new();
+
+    block_on_all(async move {
+        let manager = redis::aio::ConnectionManager::new(ctx.client.clone()).await?;
+        let mut con = manager.current_connection().unwrap();
+
+        let result: redis::Value = con.set("foo", "bar").await?;
+        assert_eq!(result, redis::Value::Okay);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "connection-manager")]
+fn test_connection_manager_connection_id() {
+    let ctx = TestContext::new();
+
+    block_on_all(async move {
+        let mut manager = redis::aio::ConnectionManager::new(ctx.client.clone()).await?;
+        let first = manager.current_connection().unwrap().connection_id();
+
+        let mut con = ctx.async_connection().await?;
+        redis::cmd("CLIENT")
+            .arg("KILL")
+            .arg("TYPE")
+            .arg("normal")
+            .arg("SKIPME")
+            .arg("yes")
+            .query_async::<_, ()>(&mut con)
+            .await?;
+        // The first request notices the connection was killed, the next one reconnects.
+        let _: RedisResult<()> = manager.set("foo", "bar").await;
+        let _: () = manager.set("foo", "bar").await?;
+
+        let second = manager.current_connection().unwrap().connection_id();
+        assert!(second > first);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "connection-manager")]
+fn test_connection_manager_max_connection_lifetime() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let ctx = TestContext::new();
+
+    block_on_all(async move {
+        let mut manager = redis::aio::ConnectionManager::new(ctx.client.clone()).await?;
+        let client_id = |mut manager: redis::aio::ConnectionManager| async move {
+            redis::cmd("CLIENT")
+                .arg("ID")
+                .query_async::<_, i64>(&mut manager)
+                .await
+        };
+        let first = client_id(manager.clone()).await?;
+        assert_eq!(client_id(manager.clone()).await?, first);
+
+        // New connections have to authenticate with the password from the provider.
+        let mut con = ctx.async_connection().await?;
+        redis::cmd("CONFIG")
+            .arg("SET")
+            .arg("requirepass")
+            .arg("rotated")
+            .query_async::<_, ()>(&mut con)
+            .await?;
+        let fetched = Arc::new(AtomicUsize::new(0));
+        let counter = fetched.clone();
+        manager.set_password_provider(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Ok("rotated".to_string()) }
+        })?;
+        manager.set_max_connection_lifetime(Some(Duration::from_millis(10)));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // The expired connection is still used for this request, and replaced once the new
+        // connection is established.
+        assert_eq!(client_id(manager.clone()).await?, first);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(fetched.load(Ordering::SeqCst), 1);
+        assert_ne!(client_id(manager.clone()).await?, first);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "connection-manager")]
+fn test_connection_manager_keeps_connection_when_rotation_fails() {
+    use std::time::Duration;
+
+    let ctx = TestContext::new();
+
+    block_on_all(async move {
+        let mut manager = redis::aio::ConnectionManager::new(ctx.client.clone()).await?;
+        manager.set_password_provider(|| async {
+            Err((redis::ErrorKind::AuthenticationFailed, "No token").into())
+        })?;
+        manager.set_max_connection_lifetime(Some(Duration::from_millis(10)));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // The old connection keeps serving requests while, and after, replacing it fails.
+        let first: i64 = redis::cmd("CLIENT")
+            .arg("ID")
+            .query_async(&mut manager)
+            .await?;
+        for _ in 0..5 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let id: i64 = redis::cmd("CLIENT")
+                .arg("ID")
+                .query_async(&mut manager)
+                .await?;
+            assert_eq!(id, first);
+        }
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "connection-manager")]
+fn test_connection_manager_rejects_password_provider_with_auth_commands() {
+    let ctx = TestContext::new();
+
+    block_on_all(async move {
+        let mut manager = redis::aio::ConnectionManager::new(ctx.client.clone()).await?;
+        let client = ctx
+            .server
+            .connection_builder()
+            .auth_strategy(redis::AuthStrategy::Commands(vec![redis::cmd("PING")]))
+            .build()?;
+        let mut with_commands = redis::aio::ConnectionManager::new(client).await?;
+
+        manager.set_password_provider(|| async { Ok("token".to_string()) })?;
+        let err = with_commands
+            .set_password_provider(|| async { Ok("token".to_string()) })
+            .unwrap_err();
+        assert_eq!(err.kind(), redis::ErrorKind::InvalidClientConfig);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "connection-manager")]
+fn test_connection_manager_closes_connection_when_dropped() {
+    let ctx = TestContext::new();
+
+    block_on_all(async move {
+        let mut con = ctx.async_connection().await?;
+        let client_count = |list: String| list.lines().count();
+
+        let manager = redis::aio::ConnectionManager::new(ctx.client.clone()).await?;
+        let clone = manager.clone();
+        let list: String = redis::cmd("CLIENT")
+            .arg("LIST")
+            .query_async(&mut con)
+            .await?;
+        assert_eq!(client_count(list), 2);
+
+        drop(manager);
+        drop(clone);
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let list: String = redis::cmd("CLIENT")
+            .arg("LIST")
+            .query_async(&mut con)
+            .await?;
+        assert_eq!(client_count(list), 1);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "connection-manager")]
+fn test_pubsub_manager_resubscribes_after_reconnect() {
+    use redis::aio::PubSubEvent;
+
+    let ctx = TestContext::new();
+
+    block_on_all(async move {
+        let mut manager = redis::aio::PubSubManager::new(ctx.client.clone()).await?;
+        manager.subscribe("phonewave").await?;
+        manager.psubscribe("news.*").await?;
+
+        let mut con = ctx.async_connection().await?;
+        let _: () = redis::cmd("CLIENT")
+            .arg("KILL")
+            .arg("TYPE")
+            .arg("pubsub")
+            .query_async(&mut con)
+            .await?;
+
+        assert!(matches!(
+            manager.next_event().await?,
+            PubSubEvent::Resubscribed
+        ));
+
+        let _: () = con.publish("phonewave", "banana").await?;
+        let _: () = con.publish("news.today", "apple").await?;
+
+        let mut payloads = Vec::new();
+        for _ in 0..2 {
+            match manager.next_event().await? {
+                PubSubEvent::Message(msg) => payloads.push(msg.get_payload::<String>()?),
+                PubSubEvent::Resubscribed => panic!("unexpected resubscription"),
+            }
+        }
+        assert_eq!(payloads, vec!["banana".to_string(), "apple".to_string()]);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "connection-manager")]
+fn test_pubsub_manager_forgets_all_channels_when_unsubscribing_without_arguments() {
+    use redis::aio::PubSubEvent;
+
+    let ctx = TestContext::new();
+
+    block_on_all(async move {
+        let mut manager = redis::aio::PubSubManager::new(ctx.client.clone()).await?;
+        manager.subscribe(&["phonewave", "banana"]).await?;
+        manager.psubscribe("news.*").await?;
+        manager.unsubscribe(Vec::<String>::new()).await?;
+        manager.punsubscribe(Vec::<String>::new()).await?;
+
+        let mut con = ctx.async_connection().await?;
+        let _: () = redis::cmd("CLIENT")
+            .arg("KILL")
+            .arg("TYPE")
+            .arg("pubsub")
+            .query_async(&mut con)
+            .await?;
+
+        assert!(matches!(
+            manager.next_event().await?,
+            PubSubEvent::Resubscribed
+        ));
+
+        let subscribers: Vec<(String, usize)> = redis::cmd("PUBSUB")
+            .arg("NUMSUB")
+            .arg("phonewave")
+            .arg("banana")
+            .query_async(&mut con)
+            .await?;
+        assert_eq!(
+            subscribers,
+            vec![("phonewave".to_string(), 0), ("banana".to_string(), 0)]
+        );
+        let patterns: usize = redis::cmd("PUBSUB")
+            .arg("NUMPAT")
+            .query_async(&mut con)
+            .await?;
+        assert_eq!(patterns, 0);
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
@@ -2,7 +2,7 @@
 
 use redis::{
     Commands, ConnectionInfo, ConnectionLike, ControlFlow, ErrorKind, ExistenceCheck, Expiry,
-    PubSubCommands, RedisResult, SetExpiry, SetOptions, ToRedisArgs,
+    KeyType, PubSubCommands, RedisResult, SetExpiry, SetOptions, ToRedisArgs,
 };
 
 use std::collections::{BTreeMap, BTreeSet};
@@ -42,6 +42,134 @@ fn test_args() {
     );
 }
 
+#[test]
+fn test_custom_auth_strategy() {
+    let ctx = TestContext::new();
+    let mut setname = redis::cmd("CLIENT");
+    setname.arg("SETNAME").arg("custom-handshake");
+    let info = ctx
+        .server
+        .connection_builder()
+        .password("ignored")
+        .auth_strategy(redis::AuthStrategy::Commands(vec![setname]));
+
+    let mut con = redis::Client::open(info).unwrap().get_connection().unwrap();
+    let name: String = redis::cmd("CLIENT").arg("GETNAME").query(&mut con).unwrap();
+    assert_eq!(name, "custom-handshake");
+}
+
+#[test]
+fn test_select_strategy_never() {
+    let ctx = TestContext::new();
+    let info = ctx
+        .server
+        .connection_builder()
+        .db(1)
+        .select_strategy(redis::SelectStrategy::Never);
+    let mut con = redis::Client::open(info).unwrap().get_connection().unwrap();
+    redis::cmd("SET").arg("foo").arg(42).execute(&mut con);
+
+    // The database was never switched, so the key was written to database 0.
+    let mut other = ctx.connection();
+    assert_eq!(redis::cmd("GET").arg("foo").query(&mut other), Ok(42));
+}
+
+#[test]
+fn test_protocol_version() {
+    let ctx = TestContext::new();
+    assert_eq!(
+        ctx.connection().protocol_version(),
+        redis::ProtocolVersion::RESP2
+    );
+
+    let info = ctx
+        .server
+        .connection_builder()
+        .protocol(redis::ProtocolVersion::RESP3);
+    let result = redis::Client::open(info).unwrap().get_connection();
+    if cfg!(feature = "resp3") {
+        let mut con = result.unwrap();
+        assert_eq!(con.protocol_version(), redis::ProtocolVersion::RESP3);
+        con.reset().unwrap();
+        assert_eq!(con.protocol_version(), redis::ProtocolVersion::RESP3);
+    } else {
+        assert!(matches!(result, Err(e) if e.kind() == redis::ErrorKind::InvalidClientConfig));
+    }
+}
+
+#[test]
+fn test_quit() {
+    let ctx = TestContext::new();
+    let con = ctx.connection();
+    assert_eq!(con.quit(), Ok(()));
+}
+
+#[test]
+fn test_reset() {
+    let ctx = TestContext::new();
+    let info = ctx.server.connection_builder().db(1);
+    let client = redis::Client::open(info).unwrap();
+    let mut con = client.get_connection().unwrap();
+
+    redis::cmd("SELECT").arg(2).execute(&mut con);
+    redis::cmd("MULTI").execute(&mut con);
+    con.reset().unwrap();
+
+    // The transaction was discarded and the configured database selected again.
+    redis::cmd("SET").arg("foo").arg(42).execute(&mut con);
+    let mut other = client.get_connection().unwrap();
+    assert_eq!(redis::cmd("GET").arg("foo").query(&mut other), Ok(42));
+}
+
+#[test]
+fn test_default_response_timeout() {
+    let ctx = TestContext::new();
+    let info = ctx
+        .server
+        .connection_builder()
+        .response_timeout(Duration::from_millis(100));
+    let mut con = redis::Client::open(info).unwrap().get_connection().unwrap();
+
+    let err = redis::cmd("BLPOP")
+        .arg("missing_list")
+        .arg(0)
+        .query::<redis::Value>(&mut con)
+        .unwrap_err();
+    assert!(err.is_timeout(), "{err}");
+}
+
+#[test]
+fn test_blocking_pop_with_response_timeout() {
+    let ctx = TestContext::new();
+    let info = ctx
+        .server
+        .connection_builder()
+        .response_timeout(Duration::from_millis(100));
+    let mut con = redis::Client::open(info).unwrap().get_connection().unwrap();
+
+    // The server-side timeout is longer than the response timeout, yet the pop returns nil.
+    let popped: Option<(String, i32)> = con
+        .blpop_timeout("missing_list", Duration::from_millis(300))
+        .unwrap();
+    assert_eq!(popped, None);
+
+    let _: () = con.rpush("list", &[1, 2, 3]).unwrap();
+    let popped: Option<(String, i32)> = con
+        .blpop_timeout(&["missing_list", "list"], Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(popped, Some(("list".to_string(), 1)));
+    let popped: Option<(String, i32)> = con.brpop_timeout("list", Duration::from_secs(1)).unwrap();
+    assert_eq!(popped, Some(("list".to_string(), 3)));
+
+    // The response timeout applies to other commands again.
+    let err = redis::cmd("BLPOP")
+        .arg("missing_list")
+        .arg(0)
+        .query::<redis::Value>(&mut con)
+        .unwrap_err();
+    assert!(err.is_timeout(), "{err}");
+}
+
 #[test]
 fn test_getset() {
     let ctx = TestContext::new();
@@ -57,6 +185,27 @@ fn test_getset() {
     );
 }
 
+#[test]
+fn test_binary_keys_and_values() {
+    let ctx = TestContext::new();
+    let mut con = ctx.connection();
+
+    let key: &[u8] = b"key\x00\xff";
+    let value: Vec<u8> = (0..=255).collect();
+    assert_eq!(con.set(key, &value[..]), Ok(()));
+    assert_eq!(con.get(key), Ok(value.clone()));
+    assert_eq!(con.append(key, b"\x00"), Ok(257));
+    assert_eq!(con.getrange(key, -3, -1), Ok(b"\xfe\xff\x00".to_vec()));
+    assert_eq!(con.hset(b"hash\x00", b"\xc3\x28", &value[..]), Ok(1));
+    assert_eq!(
+        con.hgetall(b"hash\x00"),
+        Ok(vec![(b"\xc3\x28".to_vec(), value)])
+    );
+    let mut keys: Vec<Vec<u8>> = con.keys(b"*\x00*").unwrap();
+    keys.sort();
+    assert_eq!(keys, [b"hash\x00".to_vec(), key.to_vec()]);
+}
+
 //unit test for key_type function
 #[test]
 fn test_key_type() {
@@ -101,6 +250,23 @@ fn test_key_type() {
         .execute(&mut con);
     let hash_key_type: String = con.key_type("hset_bar").unwrap();
     assert_eq!(hash_key_type, "hash");
+
+    //The keys as typed enum
+    assert_eq!(con.key_type::<_, KeyType>("foo"), Ok(KeyType::String));
+    assert_eq!(con.key_type::<_, KeyType>("hset_bar"), Ok(KeyType::Hash));
+    assert_eq!(con.key_type::<_, KeyType>("missing"), Ok(KeyType::None));
+}
+
+#[test]
+fn test_wait() {
+    let ctx = TestContext::new();
+    let mut con = ctx.connection();
+
+    // The test server has no replicas
+    let _: () = con.set("foo", 42).unwrap();
+    assert_eq!(con.wait(0, Duration::from_millis(100)), Ok(0));
+    let err = con.wait(1, Duration::from_millis(100)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResponseError);
 }
 
 #[test]
@@ -112,6 +278,110 @@ fn test_incr() {
     assert_eq!(redis::cmd("INCR").arg("foo").query(&mut con), Ok(43usize));
 }
 
+#[test]
+fn test_with_tcp_stream() {
+    let ctx = TestContext::new();
+    let con = ctx.connection();
+
+    let nodelay = con.with_tcp_stream(|stream| {
+        stream.set_nodelay(true).unwrap();
+        stream.nodelay().unwrap()
+    });
+    match ctx.server.client_addr() {
+        redis::ConnectionAddr::Unix(_) => assert_eq!(nodelay, None),
+        _ => assert_eq!(nodelay, Some(true)),
+    }
+}
+
+#[test]
+fn test_from_stream() {
+    let ctx = TestContext::new();
+    let mut info = ctx.server.connection_info().redis;
+    info.db = 1;
+    let mut con = match ctx.server.client_addr() {
+        redis::ConnectionAddr::Tcp(host, port) => {
+            let stream = std::net::TcpStream::connect((host.as_str(), *port)).unwrap();
+            redis::Connection::from_tcp_stream(stream, &info).unwrap()
+        }
+        #[cfg(unix)]
+        redis::ConnectionAddr::Unix(path) => {
+            let stream = std::os::unix::net::UnixStream::connect(path).unwrap();
+            redis::Connection::from_unix_stream(stream, &info).unwrap()
+        }
+        _ => return,
+    };
+
+    redis::cmd("SET").arg("foo").arg(1).execute(&mut con);
+    let mut default_db = ctx.connection();
+    assert_eq!(default_db.exists("foo"), Ok(false));
+    assert_eq!(con.exists("foo"), Ok(true));
+}
+
+#[test]
+fn test_local_addr() {
+    let ctx = TestContext::new();
+    if let redis::ConnectionAddr::Unix(_) = ctx.server.client_addr() {
+        return;
+    }
+    let info = ctx
+        .server
+        .connection_builder()
+        .local_addr("127.0.0.1:0".parse().unwrap());
+    let mut con = redis::Client::open(info).unwrap().get_connection().unwrap();
+
+    let local_addr = con.with_tcp_stream(|stream| stream.local_addr().unwrap());
+    assert!(local_addr.unwrap().ip().is_loopback());
+    assert_eq!(redis::cmd("PING").query(&mut con), Ok("PONG".to_string()));
+}
+
+#[test]
+fn test_fallback_addrs() {
+    let ctx = TestContext::new();
+    // Nothing listens on port 1, so connecting falls back to the server.
+    let client = redis::ConnectionBuilder::tcp("127.0.0.1", 1)
+        .fallback_addr(ctx.server.client_addr().clone())
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        let mut con = client.get_connection().unwrap();
+        assert_eq!(redis::cmd("PING").query(&mut con), Ok("PONG".to_string()));
+    }
+}
+
+#[test]
+fn test_handshake_timeout() {
+    // The listener accepts connections, but nothing ever responds to the setup commands.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let client = redis::ConnectionBuilder::tcp("127.0.0.1", port)
+        .handshake_timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let err = client.get_connection().err().unwrap();
+    assert!(err.is_timeout(), "{err}");
+}
+
+#[test]
+fn test_setup_commands() {
+    let ctx = TestContext::new();
+    let mut setname = redis::cmd("CLIENT");
+    setname.arg("SETNAME").arg("warmup");
+    let info = ctx.server.connection_builder().setup_command(setname);
+    let mut con = redis::Client::open(info).unwrap().get_connection().unwrap();
+    let name: String = redis::cmd("CLIENT").arg("GETNAME").query(&mut con).unwrap();
+    assert_eq!(name, "warmup");
+
+    // Setup commands must reply with `OK`.
+    let info = ctx
+        .server
+        .connection_builder()
+        .setup_command(redis::cmd("PING"));
+    let result = redis::Client::open(info).unwrap().get_connection();
+    assert!(matches!(result, Err(err) if err.kind() == ErrorKind::ResponseError));
+}
+
 #[test]
 fn test_getdel() {
     let ctx = TestContext::new();
@@ -161,6 +431,42 @@ fn test_getex() {
     assert_eq!(delayed_get, 420usize);
 }
 
+#[test]
+fn test_ttl() {
+    use redis::{Millis, Seconds, Ttl};
+    use std::time::SystemTime;
+
+    let ctx = TestContext::new();
+    let mut con = ctx.connection();
+
+    assert_eq!(con.ttl("foo"), Ok(Ttl::<Seconds>::Missing));
+    assert_eq!(con.ttl("foo"), Ok(-2));
+    assert_eq!(con.set("foo", 42), Ok(()));
+    assert_eq!(con.pttl("foo"), Ok(Ttl::<Millis>::Persistent));
+    assert_eq!(con.pttl("foo"), Ok(-1));
+
+    assert_eq!(con.expire("foo", 100), Ok(true));
+    let ttl: Ttl = con.ttl("foo").unwrap();
+    assert!(ttl.duration().unwrap() <= Duration::from_secs(100));
+    let pttl: Ttl<Millis> = con.pttl("foo").unwrap();
+    assert!(pttl.duration().unwrap() > Duration::from_secs(99));
+
+    if ctx.get_version().0 >= 7 {
+        let expires: Ttl<Seconds<SystemTime>> = con.expire_time("foo").unwrap();
+        assert!(expires.expires().unwrap().0 > SystemTime::now());
+        let expires: Ttl<Millis<SystemTime>> = con.pexpire_time("foo").unwrap();
+        assert!(expires.expires().unwrap().0 > SystemTime::now());
+        assert_eq!(
+            con.expire_time("bar"),
+            Ok(Ttl::<Seconds<SystemTime>>::Missing)
+        );
+    }
+
+    assert_eq!(con.persist("foo"), Ok(true));
+    assert_eq!(con.ttl("foo"), Ok(Ttl::<Seconds>::Persistent));
+    assert_eq!(con.persist("foo"), Ok(false));
+}
+
 #[test]
 fn test_info() {
     let ctx = TestContext::new();
@@ -175,6 +481,11 @@ fn test_info() {
     assert_eq!(info.get("loading"), Some(false));
     assert!(!info.is_empty());
     assert!(info.contains_key(&"role"));
+
+    let info: redis::InfoDict = con.info_sections("memory").unwrap();
+    assert!(info.used_memory().is_some());
+    assert!(info.section("memory").is_some());
+    assert!(info.section("replication").is_none());
 }
 
 #[test]
@@ -221,6 +532,21 @@ fn test_unlink() {
     assert_eq!(con.unlink(&["foo", "bar"]), Ok(2));
 }
 
+#[test]
+fn test_del_all() {
+    let ctx = TestContext::new();
+    let mut con = ctx.connection();
+
+    assert_eq!(con.del_all(Vec::<String>::new()), Ok(0));
+
+    let keys: Vec<String> = (0..2500).map(|i| format!("key_{i}")).collect();
+    for key in keys.iter().step_by(2) {
+        let _: () = con.set(key, 42).unwrap();
+    }
+    assert_eq!(con.del_all(&keys), Ok(1250));
+    assert_eq!(con.exists("key_0"), Ok(false));
+}
+
 #[test]
 fn test_set_ops() {
     let ctx = TestContext::new();
@@ -339,6 +665,32 @@ fn test_filtered_scanning() {
     assert_eq!(unseen.len(), 0);
 }
 
+#[test]
+fn test_scan_with_options() {
+    let ctx = TestContext::new();
+    let mut con = ctx.connection();
+    let mut unseen = HashSet::new();
+
+    for x in 0..1000 {
+        let _: () = con.set(format!("user:{x}"), x).unwrap();
+        let _: () = con.rpush(format!("user:list:{x}"), x).unwrap();
+        let _: () = con.set(format!("other:{x}"), x).unwrap();
+        unseen.insert(format!("user:{x}"));
+    }
+
+    let opts = redis::ScanOptions::default()
+        .match_pattern("user:*")
+        .count(20)
+        .key_type("string");
+    let iter = con.scan_options::<String>(opts).unwrap();
+
+    for key in iter {
+        assert!(unseen.remove(&key), "unexpected key {key}");
+    }
+
+    assert_eq!(unseen.len(), 0);
+}
+
 #[test]
 fn test_pipeline() {
     let ctx = TestContext::new();
@@ -409,6 +761,30 @@ fn test_empty_pipeline() {
     let _: () = redis::pipe().query(&mut con).unwrap();
 }
 
+#[test]
+fn test_pipeline_partial_responses() {
+    let ctx = TestContext::new();
+    let mut con = ctx.connection();
+    let id: i64 = redis::cmd("CLIENT").arg("ID").query(&mut con).unwrap();
+
+    // The server closes the connection after answering `CLIENT KILL`.
+    let err = redis::pipe()
+        .set("foo", 1)
+        .incr("foo", 1)
+        .cmd("CLIENT")
+        .arg("KILL")
+        .arg("ID")
+        .arg(id)
+        .get("foo")
+        .query::<()>(&mut con)
+        .unwrap_err();
+    let responses = err.partial_responses().unwrap();
+    assert_eq!(responses.len(), 3);
+    assert_eq!(responses[0], Ok(redis::Value::Okay));
+    assert_eq!(responses[1], Ok(redis::Value::Int(2)));
+    assert!(!con.is_open());
+}
+
 #[test]
 fn test_pipeline_transaction() {
     let ctx = TestContext::new();
@@ -894,6 +1270,9 @@ fn test_nice_hash_api() {
     assert_eq!(found.len(), 2);
     assert!(found.contains(&("f3".to_string(), 4)));
     assert!(found.contains(&("f4".to_string(), 8)));
+
+    let pairs: HashSet<(String, isize)> = con.hscan_pairs("my_hash").unwrap().collect();
+    assert_eq!(pairs, found);
 }
 
 #[test]
@@ -942,13 +1321,65 @@ fn test_tuple_decoding_regression() {
     assert_eq!(vec.len(), 0);
 }
 
+#[test]
+fn test_zadd_options() {
+    use redis::{ExistenceCheck, SortedSetAddOptions, UpdateCheck};
+
+    let ctx = TestContext::new();
+    let mut con = ctx.connection();
+
+    assert_eq!(con.zadd("my_zset", "one", 1), Ok(1));
+    let opts = SortedSetAddOptions::default().conditional_set(ExistenceCheck::XX);
+    assert_eq!(con.zadd_options("my_zset", "two", 2, opts), Ok(0));
+
+    let opts = SortedSetAddOptions::default()
+        .conditional_update(UpdateCheck::GT)
+        .include_changed(true);
+    assert_eq!(
+        con.zadd_multiple_options("my_zset", &[(0.0, "one"), (f64::INFINITY, "two")], opts),
+        Ok(1)
+    );
+
+    let opts = SortedSetAddOptions::default().increment(true);
+    assert_eq!(con.zadd_options("my_zset", "one", 1.5, opts), Ok(2.5));
+
+    let scores: Vec<(String, f64)> = con.zrange_withscores("my_zset", 0, -1).unwrap();
+    assert_eq!(
+        scores,
+        vec![("one".to_string(), 2.5), ("two".to_string(), f64::INFINITY)]
+    );
+}
+
 #[test]
 fn test_bit_operations() {
+    use redis::BitRange;
+
     let ctx = TestContext::new();
     let mut con = ctx.connection();
 
     assert_eq!(con.setbit("bitvec", 10, true), Ok(false));
     assert_eq!(con.getbit("bitvec", 10), Ok(true));
+    assert_eq!(con.getbit("bitvec", 11), Ok(false));
+
+    // 0b00000000 0b00100001
+    assert_eq!(con.setbit("bitvec", 15, true), Ok(false));
+    assert_eq!(con.bitcount("bitvec"), Ok(2));
+    assert_eq!(con.bitcount_in("bitvec", BitRange::bytes(-1, -1)), Ok(2));
+    assert_eq!(con.bitcount_in("bitvec", BitRange::bytes(0, 0)), Ok(0));
+    assert_eq!(con.bitpos("bitvec", true, None), Ok(10));
+    assert_eq!(
+        con.bitpos("bitvec", false, Some(BitRange::bytes(1, 1))),
+        Ok(8)
+    );
+    assert_eq!(con.bitpos("missing", true, None), Ok(-1));
+
+    if ctx.get_version().0 >= 7 {
+        assert_eq!(con.bitcount_in("bitvec", BitRange::bits(11, 15)), Ok(1));
+        assert_eq!(
+            con.bitpos("bitvec", true, Some(BitRange::bits(11, -1))),
+            Ok(15)
+        );
+    }
 }
 
 #[test]
@@ -1171,6 +1602,19 @@ fn test_object_commands() {
         "int"
     );
 
+    assert_eq!(
+        con.object_encoding::<_, Option<String>>("object_key_missing")
+            .unwrap(),
+        None
+    );
+
+    let usage: Option<u64> = con.memory_usage("object_key_str", None).unwrap();
+    assert!(usage.unwrap() > 0);
+    let usage: Option<u64> = con.memory_usage("object_key_str", Some(0)).unwrap();
+    assert!(usage.unwrap() > 0);
+    let usage: Option<u64> = con.memory_usage("object_key_missing", None).unwrap();
+    assert_eq!(usage, None);
+
     assert!(con.object_idletime::<_, i32>("object_key_str").unwrap() <= 1);
     assert_eq!(con.object_refcount::<_, i32>("object_key_str").unwrap(), 1);
 
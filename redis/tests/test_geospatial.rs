@@ -195,3 +195,68 @@ fn test_georadius_by_member() {
 
     assert_eq!(names, vec!["Agrigento", "Palermo"]);
 }
+
+#[test]
+fn test_geosearch() {
+    use redis::geo::{SearchCenter, SearchOptions, SearchResult, SearchShape};
+
+    let ctx = TestContext::new();
+    let mut con = ctx.connection();
+
+    assert_eq!(con.geo_add("my_gis", &[PALERMO, CATANIA, AGRIGENTO]), Ok(3));
+
+    let opts = SearchOptions::default()
+        .order(RadiusOrder::Asc)
+        .with_dist()
+        .with_hash()
+        .with_coord();
+    let result: Vec<SearchResult> = con
+        .geo_search(
+            "my_gis",
+            SearchCenter::member(AGRIGENTO.2),
+            SearchShape::Radius(100.0, Unit::Kilometers),
+            opts,
+        )
+        .unwrap();
+    let names: Vec<_> = result.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["Agrigento", "Palermo"]);
+    assert_eq!(result[0].dist, Some(0.0));
+    assert!(result[1].hash.is_some());
+    let coord = result[1].coord.as_ref().unwrap();
+    assert_approx_eq!(coord.longitude, 13.361389, 0.0001);
+    assert_approx_eq!(coord.latitude, 38.115556, 0.0001);
+
+    let shape = SearchShape::Box {
+        width: 400.0,
+        height: 400.0,
+        unit: Unit::Kilometers,
+    };
+    let opts = SearchOptions::default().order(RadiusOrder::Desc).limit(1);
+    let result: Vec<SearchResult> = con
+        .geo_search(
+            "my_gis",
+            SearchCenter::LonLat((15.0, 37.0).into()),
+            shape,
+            opts,
+        )
+        .unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].name, "Palermo");
+    assert!(result[0].dist.is_none());
+
+    let member: &[u8] = b"\x00\xff";
+    assert_eq!(
+        con.geo_add("my_gis", (Coord::lon_lat(13.4, 38.1), member)),
+        Ok(1)
+    );
+    let result: Vec<SearchResult<Vec<u8>>> = con
+        .geo_search(
+            "my_gis",
+            SearchCenter::member(member),
+            SearchShape::Radius(1.0, Unit::Kilometers),
+            SearchOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].name, member);
+}
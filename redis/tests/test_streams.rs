@@ -600,6 +600,119 @@ fn test_xrange() {
     assert_eq!(reply.ids.len(), 1);
 }
 
+#[test]
+fn test_typed_entries() {
+    let ctx = TestContext::new();
+    let mut con = ctx.connection();
+
+    let id: String = con.xadd("k1", "*", &[("hello", "world")]).unwrap();
+    let _: String = con.xadd("k2", "1000-0", &[("ab", "cd")]).unwrap();
+
+    let entries: Vec<StreamId> = con.xrange_all("k1").unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].id, id);
+    assert_eq!(entries[0].get("hello"), Some("world".to_string()));
+
+    let entries: Vec<StreamId> = con.xrange("k1", "+", "-").unwrap();
+    assert!(entries.is_empty());
+    let entries: Vec<StreamId> = con.xrange_all("missing").unwrap();
+    assert!(entries.is_empty());
+
+    let keys: Vec<StreamKey> = con.xread(&["k1", "k2"], &["0", "0"]).unwrap();
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0].key, "k1");
+    assert_eq!(keys[0].ids[0].id, id);
+    assert_eq!(keys[1].key, "k2");
+    assert_eq!(keys[1].ids[0].id, "1000-0");
+
+    let keys: Vec<StreamKey> = con.xread(&["k1"], &["$"]).unwrap();
+    assert!(keys.is_empty());
+}
+
+#[cfg(feature = "aio")]
+#[test]
+fn test_read_entries() {
+    use futures::StreamExt;
+    use redis::AsyncCommands;
+
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let reader = ctx.async_connection().await?;
+        let mut con = ctx.async_connection().await?;
+        let entries = read_entries(reader, &["k1", "k2"], StreamReadOptions::default());
+        futures::pin_mut!(entries);
+
+        let (entry, id) = futures::future::join(entries.next(), async {
+            sleep(Duration::from_millis(50));
+            con.xadd("k2", "*", &[("hello", "world")]).await
+        })
+        .await;
+        let id: String = id?;
+        let (key, entry) = entry.unwrap()?;
+        assert_eq!(key, "k2");
+        assert_eq!(entry.id, id);
+        assert_eq!(entry.get("hello"), Some("world".to_string()));
+
+        let _: String = con.xadd("k1", "*", &[("a", "1")]).await?;
+        let _: String = con.xadd("k1", "*", &[("b", "2")]).await?;
+        let (key, entry) = entries.next().await.unwrap()?;
+        assert_eq!((key, entry.get("a")), ("k1", Some(1)));
+        let (key, entry) = entries.next().await.unwrap()?;
+        assert_eq!((key, entry.get("b")), ("k1", Some(2)));
+
+        let binary_key: &[u8] = b"k\x00\xff";
+        let binary = read_entries(
+            ctx.async_connection().await?,
+            &[binary_key],
+            StreamReadOptions::default(),
+        );
+        futures::pin_mut!(binary);
+        let (entry, id) = futures::future::join(binary.next(), async {
+            sleep(Duration::from_millis(50));
+            con.xadd(binary_key, "*", &[("v", b"\x00\xff")]).await
+        })
+        .await;
+        let id: String = id?;
+        let (key, entry) = entry.unwrap()?;
+        assert_eq!((key, &entry.id), (binary_key, &id));
+        assert_eq!(entry.get("v"), Some(b"\x00\xff".to_vec()));
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
+#[cfg(feature = "aio")]
+#[test]
+fn test_read_entries_added_between_reads() {
+    use futures::StreamExt;
+    use redis::AsyncCommands;
+
+    let ctx = TestContext::new();
+    block_on_all(async move {
+        let reader = ctx.async_connection().await?;
+        let mut con = ctx.async_connection().await?;
+        let _: String = con.xadd("k2", "*", &[("old", "0")]).await?;
+        let entries = read_entries(reader, &["k1", "k2"], StreamReadOptions::default());
+        futures::pin_mut!(entries);
+
+        let (entry, id) = futures::future::join(entries.next(), async {
+            sleep(Duration::from_millis(50));
+            con.xadd("k1", "*", &[("a", "1")]).await
+        })
+        .await;
+        let _: String = id?;
+        let (key, entry) = entry.unwrap()?;
+        assert_eq!((key, entry.get("a")), ("k1", Some(1)));
+
+        // No entry of k2 was read yet, but the entries added to it since the first read are.
+        let _: String = con.xadd("k2", "*", &[("b", "2")]).await?;
+        let (key, entry) = entries.next().await.unwrap()?;
+        assert_eq!((key, entry.get("b")), ("k2", Some(2)));
+        Ok::<_, redis::RedisError>(())
+    })
+    .unwrap();
+}
+
 #[test]
 fn test_xrevrange() {
     // Tests the following commands....
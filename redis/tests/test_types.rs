@@ -17,6 +17,10 @@ fn test_is_single_arg() {
 
     assert!(!twobytesslice.is_single_arg());
     assert!(!twobytesvec.is_single_arg());
+
+    assert!(redis::IterArgs(["foo"].iter()).is_single_arg());
+    assert!(!redis::IterArgs(["foo", "bar"].iter()).is_single_arg());
+    assert!(!redis::IterArgs(std::iter::empty::<&str>()).is_single_arg());
 }
 
 #[test]
@@ -33,6 +37,30 @@ fn test_info_dict() {
     assert_eq!(d.get::<String>("key3"), None);
 }
 
+#[test]
+fn test_info_dict_sections() {
+    use redis::{FromRedisValue, InfoDict, Value};
+
+    let d: InfoDict = FromRedisValue::from_redis_value(&Value::Data(
+        b"# Server\r\nredis_version:7.2.0\r\n\r\n# Clients\r\nconnected_clients:3\r\n\r\n\
+          # Memory\r\nused_memory:1024\r\n\r\n# Replication\r\nrole:master\r\n"
+            .to_vec(),
+    ))
+    .unwrap();
+
+    assert_eq!(d.len(), 4);
+    assert_eq!(d.sections().len(), 4);
+    assert_eq!(
+        d.section("server").unwrap().get("redis_version"),
+        Some(&"7.2.0".to_string())
+    );
+    assert_eq!(d.section("memory").unwrap().len(), 1);
+    assert!(d.section("missing").is_none());
+    assert_eq!(d.used_memory(), Some(1024));
+    assert_eq!(d.connected_clients(), Some(3));
+    assert_eq!(d.role(), Some("master".to_string()));
+}
+
 #[test]
 fn test_i32() {
     use redis::{ErrorKind, FromRedisValue, Value};
@@ -123,6 +151,58 @@ fn test_tuple() {
     assert_eq!(v, Ok(((1i32, 2, 3,),)));
 }
 
+#[test]
+fn test_scored_members() {
+    use redis::{FromRedisValue, ScoredMember, Value};
+
+    let flat = Value::Bulk(vec![
+        Value::Data("a".into()),
+        Value::Data("-inf".into()),
+        Value::Data("b".into()),
+        Value::Data("1.5".into()),
+        Value::Data("c".into()),
+        Value::Data("inf".into()),
+    ]);
+    let expected = vec![
+        ("a".to_string(), f64::NEG_INFINITY),
+        ("b".to_string(), 1.5),
+        ("c".to_string(), f64::INFINITY),
+    ];
+    let v: Vec<(String, f64)> = FromRedisValue::from_redis_value(&flat).unwrap();
+    assert_eq!(v, expected);
+
+    let nested = Value::Bulk(vec![
+        Value::Bulk(vec![Value::Data("a".into()), Value::Data("-inf".into())]),
+        Value::Bulk(vec![Value::Data("b".into()), Value::Data("1.5".into())]),
+        Value::Bulk(vec![Value::Data("c".into()), Value::Data("inf".into())]),
+    ]);
+    let expected: Vec<_> = expected
+        .into_iter()
+        .map(|(member, score)| ScoredMember { member, score })
+        .collect();
+    let v: Vec<ScoredMember> = FromRedisValue::from_redis_value(&flat).unwrap();
+    assert_eq!(v, expected);
+    let v: Vec<ScoredMember> = FromRedisValue::from_redis_value(&nested).unwrap();
+    assert_eq!(v, expected);
+
+    // Tuples are only read from flat lists, even if the items are lists themselves.
+    let lists = Value::Bulk(vec![
+        Value::Bulk(vec![Value::Data("a".into()), Value::Data("b".into())]),
+        Value::Bulk(vec![Value::Data("c".into()), Value::Data("d".into())]),
+    ]);
+    let v: Vec<(Vec<String>, Vec<String>)> = FromRedisValue::from_redis_value(&lists).unwrap();
+    assert_eq!(
+        v,
+        vec![(
+            vec!["a".to_string(), "b".to_string()],
+            vec!["c".to_string(), "d".to_string()]
+        )]
+    );
+
+    let v: f64 = FromRedisValue::from_redis_value(&Value::Data("nan".into())).unwrap();
+    assert!(v.is_nan());
+}
+
 #[test]
 fn test_hashmap() {
     use fnv::FnvHasher;
@@ -200,6 +280,70 @@ fn test_bool() {
     assert_eq!(v, Ok(true));
 }
 
+#[cfg(feature = "resp3")]
+#[test]
+fn test_double_conversions() {
+    use redis::{Double, FromRedisValue, Value};
+
+    let double = |val: f64| Value::Double(Double(val));
+
+    assert_eq!(i64::from_redis_value(&double(3.0)), Ok(3));
+    assert_eq!(i8::from_redis_value(&double(-128.0)), Ok(-128));
+    assert_eq!(u8::from_redis_value(&double(255.0)), Ok(255));
+    assert_eq!(f32::from_redis_value(&double(1.25)), Ok(1.25));
+
+    // Integers are only converted if that's lossless.
+    assert!(i64::from_redis_value(&double(1.9)).is_err());
+    assert!(i64::from_redis_value(&double(f64::NAN)).is_err());
+    assert!(i64::from_redis_value(&double(f64::INFINITY)).is_err());
+    assert!(i64::from_redis_value(&double(9_223_372_036_854_775_808.0)).is_err());
+    assert!(u64::from_redis_value(&double(-1.0)).is_err());
+    assert!(u8::from_redis_value(&double(256.0)).is_err());
+
+    // `Eq` is reflexive for `NaN`.
+    assert_eq!(double(f64::NAN), double(f64::NAN));
+    assert_ne!(double(0.0), double(-0.0));
+}
+
+#[cfg(feature = "resp3")]
+#[test]
+fn test_resp3_values() {
+    use redis::{Double, FromRedisValue, Value, VerbatimFormat};
+    use std::collections::HashMap;
+
+    let v = FromRedisValue::from_redis_value(&Value::Double(Double(1.5)));
+    assert_eq!(v, Ok(1.5f64));
+
+    let v = FromRedisValue::from_redis_value(&Value::Boolean(true));
+    assert_eq!(v, Ok(true));
+
+    let v = FromRedisValue::from_redis_value(&Value::VerbatimString {
+        format: VerbatimFormat::Text,
+        text: "hello".into(),
+    });
+    assert_eq!(v, Ok("hello".to_string()));
+
+    let v = FromRedisValue::from_redis_value(&Value::BigNumber(
+        "3492890328409238509324850943850943825024385".into(),
+    ));
+    assert_eq!(
+        v,
+        Ok("3492890328409238509324850943850943825024385".to_string())
+    );
+
+    let v = FromRedisValue::from_redis_value(&Value::BigNumber("-42".into()));
+    assert_eq!(v, Ok(-42i128));
+
+    let v: Result<HashMap<String, i64>, _> = FromRedisValue::from_redis_value(&Value::Map(vec![
+        (Value::Data("a".into()), Value::Int(1)),
+        (Value::Data("b".into()), Value::Int(2)),
+    ]));
+    let mut expected = HashMap::new();
+    expected.insert("a".to_string(), 1);
+    expected.insert("b".to_string(), 2);
+    assert_eq!(v, Ok(expected));
+}
+
 #[cfg(feature = "bytes")]
 #[test]
 fn test_bytes() {
@@ -229,6 +373,100 @@ fn test_bytes() {
     assert_eq!(v.unwrap_err().kind(), ErrorKind::TypeError);
 }
 
+#[cfg(feature = "smallvec")]
+#[test]
+fn test_smallvec() {
+    use redis::{ErrorKind, FromRedisValue, RedisResult, Value};
+    use smallvec::SmallVec;
+
+    let content: &[u8] = b"\x01\x02\x03\x04";
+
+    let v: RedisResult<SmallVec<[u8; 8]>> =
+        FromRedisValue::from_redis_value(&Value::Data(content.to_vec()));
+    let v = v.unwrap();
+    assert_eq!(&v[..], content);
+    assert!(!v.spilled());
+
+    let v: RedisResult<SmallVec<[u8; 2]>> =
+        FromRedisValue::from_redis_value(&Value::Data(content.to_vec()));
+    assert_eq!(&v.unwrap()[..], content);
+
+    let v: RedisResult<SmallVec<[u8; 8]>> = FromRedisValue::from_redis_value(&Value::Nil);
+    assert_eq!(v.unwrap_err().kind(), ErrorKind::TypeError);
+
+    let v: RedisResult<SmallVec<[u8; 8]>> = FromRedisValue::from_redis_value(&Value::Int(42));
+    assert_eq!(v.unwrap_err().kind(), ErrorKind::TypeError);
+}
+
+#[test]
+fn test_key_type() {
+    use redis::{FromRedisValue, KeyType, Value};
+
+    let key_type = |s: &str| KeyType::from_redis_value(&Value::Status(s.to_string()));
+    assert_eq!(key_type("string"), Ok(KeyType::String));
+    assert_eq!(key_type("zset"), Ok(KeyType::ZSet));
+    assert_eq!(key_type("stream"), Ok(KeyType::Stream));
+    assert_eq!(key_type("none"), Ok(KeyType::None));
+    assert_eq!(
+        key_type("ReJSON-RL"),
+        Ok(KeyType::Unknown("ReJSON-RL".to_string()))
+    );
+    assert!(KeyType::from_redis_value(&Value::Int(1)).is_err());
+}
+
+#[test]
+fn test_object_encoding() {
+    use redis::{FromRedisValue, ObjectEncoding, Value};
+
+    let encoding = |s: &str| ObjectEncoding::from_redis_value(&Value::Data(s.as_bytes().to_vec()));
+    assert_eq!(encoding("embstr"), Ok(ObjectEncoding::EmbStr));
+    assert_eq!(encoding("listpack"), Ok(ObjectEncoding::ListPack));
+    assert_eq!(encoding("skiplist"), Ok(ObjectEncoding::SkipList));
+    assert_eq!(
+        encoding("compact"),
+        Ok(ObjectEncoding::Other("compact".to_string()))
+    );
+    assert!(ObjectEncoding::from_redis_value(&Value::Nil).is_err());
+}
+
+#[test]
+fn test_time_units() {
+    use redis::{FromRedisValue, Millis, Seconds, ToRedisArgs, Ttl, Value};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    assert_eq!(Seconds(Duration::from_secs(5)).to_redis_args(), [b"5"]);
+    assert_eq!(Seconds(Duration::from_millis(1500)).to_redis_args(), [b"2"]);
+    assert_eq!(Millis(Duration::from_micros(1500)).to_redis_args(), [b"2"]);
+    let at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_500);
+    assert_eq!(Seconds(at).to_redis_args(), [b"1700000000"]);
+    assert_eq!(Millis(at).to_redis_args(), [b"1700000000500"]);
+
+    // Durations too long to write saturate instead of overflowing.
+    let max = vec![u64::MAX.to_string().into_bytes()];
+    assert_eq!(Seconds(Duration::MAX).to_redis_args(), max);
+    assert_eq!(Seconds(Duration::new(u64::MAX - 1, 1)).to_redis_args(), max);
+    assert_eq!(Millis(Duration::MAX).to_redis_args(), max);
+    let max_millis = Duration::from_millis(u64::MAX);
+    assert_eq!(Millis(max_millis).to_redis_args(), max);
+    assert_eq!(
+        Millis(max_millis + Duration::from_nanos(1)).to_redis_args(),
+        max
+    );
+
+    let ttl = |v| Ttl::<Seconds>::from_redis_value(&Value::Int(v));
+    assert_eq!(ttl(10).unwrap().duration(), Some(Duration::from_secs(10)));
+    assert_eq!(ttl(-1), Ok(Ttl::Persistent));
+    assert_eq!(ttl(-2), Ok(Ttl::Missing));
+    assert!(ttl(-3).is_err());
+    let pttl = Ttl::<Millis>::from_redis_value(&Value::Int(250)).unwrap();
+    assert_eq!(pttl.duration(), Some(Duration::from_millis(250)));
+    let expire_time = Seconds::from_redis_value(&Value::Int(1_700_000_000)).unwrap();
+    assert_eq!(
+        expire_time,
+        Seconds(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+    );
+}
+
 #[test]
 fn test_cstring() {
     use redis::{ErrorKind, FromRedisValue, RedisResult, Value};
@@ -304,3 +542,21 @@ fn test_types_to_redis_args() {
         .to_redis_args()
         .is_empty());
 }
+
+#[test]
+fn test_expire_condition_args() {
+    use redis::{ExpireCondition, ToRedisArgs};
+
+    let cases: [(ExpireCondition, &[&[u8]]); 6] = [
+        (ExpireCondition::NX, &[b"NX"]),
+        (ExpireCondition::XX, &[b"XX"]),
+        (ExpireCondition::GT { xx: false }, &[b"GT"]),
+        (ExpireCondition::GT { xx: true }, &[b"XX", b"GT"]),
+        (ExpireCondition::LT { xx: false }, &[b"LT"]),
+        (ExpireCondition::LT { xx: true }, &[b"XX", b"LT"]),
+    ];
+    for (condition, expected) in cases {
+        assert_eq!(condition.to_redis_args(), expected);
+        assert_eq!(condition.is_single_arg(), expected.len() == 1);
+    }
+}